@@ -0,0 +1,111 @@
+//! Plain text announcements of a run for screen readers, printed by `run --accessible`
+//! instead of drawing the split table. Times are spelled out and nothing is printed that a
+//! screen reader would read out as noise.
+
+use clap::ValueEnum;
+
+/// How much is said about each split
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Verbosity {
+    /// The split number and the delta
+    Brief,
+    /// Also the section name and the time
+    #[default]
+    Normal,
+    /// Also the segment time, golds and the next section
+    Full,
+}
+
+/// A duration in words, like "4 minutes 12 seconds" or "3.4 seconds"
+pub fn duration(ms: u32) -> String {
+    let unit = |n: u32, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    let (hours, minutes, seconds) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60);
+    if ms < 10_000 {
+        let tenths = ms / 100;
+        return if tenths.is_multiple_of(10) {
+            unit(tenths / 10, "second")
+        } else {
+            format!("{}.{} seconds", tenths / 10, tenths % 10)
+        };
+    }
+
+    let mut parts = Vec::new();
+    if hours > 0 {
+        parts.push(unit(hours, "hour"));
+    }
+    if minutes > 0 {
+        parts.push(unit(minutes, "minute"));
+    }
+    if seconds > 0 || parts.is_empty() {
+        parts.push(unit(seconds, "second"));
+    }
+    parts.join(" ")
+}
+
+/// A delta in words, like "3 seconds ahead", negative deltas are ahead
+pub fn delta(delta: i64) -> String {
+    if delta.unsigned_abs() < 100 {
+        return "even".to_owned();
+    }
+    let words = duration(delta.unsigned_abs().min(u32::MAX as u64) as u32);
+    format!("{} {}", words, if delta < 0 { "ahead" } else { "behind" })
+}
+
+/// What is known about a split when it is announced
+pub struct Split<'a> {
+    /// Counted from 1
+    pub number: usize,
+    pub name: &'a str,
+    pub time: u32,
+    pub segment: u32,
+    /// To the active comparison
+    pub delta: Option<i64>,
+    pub gold: bool,
+    pub next: Option<&'a str>,
+}
+
+/// Like "Split 3, Library, 4 minutes 12 seconds, 3 seconds ahead"
+pub fn split(split: &Split, verbosity: Verbosity) -> String {
+    let mut parts = vec![format!("Split {}", split.number)];
+    if verbosity >= Verbosity::Normal {
+        parts.push(split.name.to_owned());
+        parts.push(duration(split.time));
+    }
+    if let Some(d) = split.delta {
+        parts.push(delta(d));
+    }
+    if verbosity >= Verbosity::Full {
+        parts.push(format!("segment {}", duration(split.segment)));
+        if split.gold {
+            parts.push("gold".to_owned());
+        }
+        if let Some(next) = split.next {
+            parts.push(format!("next {}", next));
+        }
+    }
+    parts.join(", ")
+}
+
+/// Like "Finished in 1 hour 2 minutes, new personal best"
+pub fn finish(
+    time: u32,
+    pb: bool,
+    delta: Option<i64>,
+    golds: usize,
+    verbosity: Verbosity,
+) -> String {
+    let mut line = format!("Finished in {}", duration(time));
+    if pb {
+        line += ", new personal best";
+    }
+    if let Some(d) = delta.filter(|_| verbosity >= Verbosity::Normal) {
+        line += &format!(", {}", self::delta(d));
+    }
+    if verbosity >= Verbosity::Full && golds > 0 {
+        line += &format!(", {} gold{}", golds, if golds == 1 { "" } else { "s" });
+    }
+    line
+}
+
+pub const HELP: &str = "Commands, each followed by enter: t for the time, r to reset, \
+k for a checkpoint, s to save, y or n to keep or drop golds when asked, q to quit";
@@ -0,0 +1,509 @@
+//! The bridge script, which splits for the runner with signals or lines on its stdout
+
+use crate::{open_log, GameConfig};
+use anyhow::{Context, Result};
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use signal_hook::consts::{SIGINT, SIGTERM, SIGUSR1, SIGUSR2};
+use signal_hook::iterator::Signals;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+
+/// The `[bridge]` table of a game's config
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Config {
+    /// Passed to the bridge as environment variables, `${VAR}` is replaced with speedy's own
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Config {
+    pub fn is_empty(&self) -> bool {
+        self.env.is_empty()
+    }
+}
+
+/// Replaces every `${VAR}` with the variable from speedy's environment
+fn interpolate(value: &str) -> Result<String> {
+    let re = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap();
+    let mut result = String::new();
+    let mut rest = 0;
+    for captures in re.captures_iter(value) {
+        let var = captures.get(0).unwrap();
+        result += &value[rest..var.start()];
+        result += &std::env::var(&captures[1])
+            .with_context(|| format!("${{{}}} is not set", &captures[1]))?;
+        rest = var.end();
+    }
+    result += &value[rest..];
+
+    Ok(result)
+}
+
+/// What happens to the bridge once a run is finished, `bridge_after_finish` in the config
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum AfterFinish {
+    /// Keeps running until speedy exits
+    #[default]
+    Keep,
+    /// Gets SIGTERM, for bridges that split on content after the end
+    Stop,
+    /// Gets SIGTERM and is started again when the finished run is reset
+    RestartOnNewRun,
+}
+
+/// Where the bridge process is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Running,
+    /// Stopped after a finished run
+    Stopped,
+    /// Exited on its own, or couldn't be started again
+    Exited,
+}
+
+#[derive(Debug)]
+struct Process {
+    child: Option<Child>,
+    state: State,
+}
+
+/// The bridge process, shared by the thread reading its lines and the run that stops and
+/// restarts it
+#[derive(Debug, Clone)]
+pub struct Status {
+    process: Arc<Mutex<Process>>,
+    restart: mpsc::Sender<()>,
+}
+
+impl Status {
+    pub fn state(&self) -> State {
+        self.process.lock().expect("Mutex not poisoned").state
+    }
+
+    /// Sends SIGTERM, the reading thread waits for a restart afterwards
+    pub fn stop(&self) {
+        let mut process = self.process.lock().expect("Mutex not poisoned");
+        if process.state != State::Running {
+            return;
+        }
+        process.state = State::Stopped;
+        if let Some(child) = &process.child {
+            let _ = signal::kill(Pid::from_raw(child.id() as i32), Signal::SIGTERM);
+        }
+    }
+
+    /// Starts a stopped bridge again
+    pub fn restart(&self) {
+        if self.state() == State::Stopped {
+            let _ = self.restart.send(());
+        }
+    }
+
+    /// Kills the bridge for good, when speedy exits or switches games
+    pub fn kill(&self) {
+        let mut process = self.process.lock().expect("Mutex not poisoned");
+        process.state = State::Exited;
+        if let Some(mut child) = process.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// A line printed by the bridge
+pub enum Message<'a> {
+    /// Starts a run, ignored while one is active
+    Start,
+    /// `split` or `split <trigger>`, which only starts a run with `auto_start`
+    Split(Option<&'a str>),
+    /// `checkpoint` or `checkpoint <name>`, recorded without splitting
+    Checkpoint(Option<&'a str>),
+    /// `capabilities <trigger> <trigger>...`, the triggers the bridge knows about
+    Capabilities(Vec<&'a str>),
+    /// `category <name>`, switches to the game of that category before a run
+    Category(&'a str),
+    /// Anything else, passed on to stderr
+    Other(&'a str),
+}
+
+pub fn parse(line: &str) -> Message<'_> {
+    match line.split_once(' ') {
+        _ if line == "start" => Message::Start,
+        _ if line == "split" => Message::Split(None),
+        Some(("split", trigger)) => Message::Split(Some(trigger.trim())),
+        _ if line == "checkpoint" => Message::Checkpoint(None),
+        Some(("checkpoint", name)) => Message::Checkpoint(Some(name.trim())),
+        _ if line == "capabilities" => Message::Capabilities(Vec::new()),
+        Some(("capabilities", triggers)) => {
+            Message::Capabilities(triggers.split_whitespace().collect())
+        }
+        Some(("category", name)) => Message::Category(name.trim()),
+        _ => Message::Other(line),
+    }
+}
+
+/// Splits the sequence number off a line like `split boss #42`. Bridges that can send a
+/// line twice number them, and every numbered line is acknowledged with `ack 42` on the
+/// bridge's stdin.
+fn numbered(line: &str) -> (&str, Option<u64>) {
+    let Some((rest, number)) = line.rsplit_once(" #") else {
+        return (line, None);
+    };
+    match number.parse() {
+        Ok(number) => (rest.trim_end(), Some(number)),
+        Err(_) => (line, None),
+    }
+}
+
+/// The sequence numbers a bridge sent so far, numbers that don't grow are sent again
+#[derive(Debug, Default)]
+struct Sequence {
+    last: Option<u64>,
+}
+
+impl Sequence {
+    /// Whether a line with this number is seen for the first time
+    fn is_new(&mut self, number: u64) -> bool {
+        if self.last.is_some_and(|last| number <= last) {
+            return false;
+        }
+        self.last = Some(number);
+        true
+    }
+}
+
+/// Tells the bridge its numbered line arrived, duplicates included
+fn acknowledge(stdin: &mut ChildStdin, number: u64) -> io::Result<()> {
+    writeln!(stdin, "ack {}", number)?;
+    stdin.flush()
+}
+
+/// Starts the bridge script with the game's environment and its stdin and stdout piped, so
+/// its lines can be read and acknowledged. The expected triggers are in `SPEEDY_TRIGGERS`,
+/// one per line, for bridges that answer with a `capabilities` line. Every start is noted
+/// in `bridge.log`, without the values of the environment, which may be secrets.
+fn spawn(config: &GameConfig, script: &Path) -> Result<(Child, ChildStdin, ChildStdout)> {
+    let mut env = BTreeMap::new();
+    for (key, value) in &config.bridge.env {
+        let value = interpolate(value)
+            .with_context(|| format!("{}: bridge.env.{}", config.directory_name, key))?;
+        env.insert(key, value);
+    }
+
+    let mut log = open_log(&config.directory_name, "bridge.log")?;
+    let masked = env
+        .keys()
+        .map(|key| format!("{}=***", key))
+        .collect::<Vec<_>>();
+    writeln!(
+        log,
+        "{} started {} {}",
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S"),
+        script.display(),
+        masked.join(" ")
+    )?;
+
+    let mut child = Command::new(script)
+        .env("SPEEDY_TRIGGERS", config.triggers.join("\n"))
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start the bridge script {}", script.display()))?;
+    let stdin = child.stdin.take().expect("stdin is piped");
+    let stdout = child.stdout.take().expect("stdout is piped");
+    Ok((child, stdin, stdout))
+}
+
+/// Starts the bridge and a thread that hands each of its lines to `on_line`, without the
+/// sequence number, until `on_line` returns false. Numbered lines are acknowledged and lines
+/// sent again are dropped.
+pub fn run(
+    config: &GameConfig,
+    script: &Path,
+    mut on_line: impl FnMut(&str) -> bool + Send + 'static,
+) -> Result<Status> {
+    let (child, mut stdin, mut stdout) = spawn(config, script)?;
+    let process = Arc::new(Mutex::new(Process {
+        child: Some(child),
+        state: State::Running,
+    }));
+    let (restart, restarts) = mpsc::channel();
+    let status = Status {
+        process: Arc::clone(&process),
+        restart,
+    };
+
+    let (config, script) = (config.clone(), script.to_owned());
+    std::thread::spawn(move || loop {
+        // Numbers start over with every process
+        let mut sequence = Sequence::default();
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let (line, number) = numbered(&line);
+            if let Some(number) = number {
+                // A bridge that stopped reading its stdin doesn't stop the splits
+                let _ = acknowledge(&mut stdin, number);
+                if !sequence.is_new(number) {
+                    continue;
+                }
+            }
+            if !on_line(line) {
+                return;
+            }
+        }
+
+        {
+            let mut process = process.lock().expect("Mutex not poisoned");
+            if let Some(mut child) = process.child.take() {
+                let _ = child.wait();
+            }
+            if process.state != State::Stopped {
+                process.state = State::Exited;
+                return;
+            }
+        }
+        if restarts.recv().is_err() {
+            return;
+        }
+        let mut process = process.lock().expect("Mutex not poisoned");
+        if process.state != State::Stopped {
+            // Killed while waiting for the restart
+            return;
+        }
+        match spawn(&config, &script) {
+            Ok((child, new_stdin, new_stdout)) => {
+                process.child = Some(child);
+                process.state = State::Running;
+                (stdin, stdout) = (new_stdin, new_stdout);
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                process.state = State::Exited;
+                return;
+            }
+        }
+    });
+
+    Ok(status)
+}
+
+/// Compares the triggers a bridge knows about with the config's, None if they match
+pub fn check_capabilities(config: &GameConfig, triggers: &[&str]) -> Option<String> {
+    let missing: Vec<&str> = config
+        .triggers
+        .iter()
+        .map(String::as_str)
+        .filter(|trigger| !triggers.contains(trigger))
+        .collect();
+    let extra: Vec<&str> = triggers
+        .iter()
+        .copied()
+        .filter(|trigger| !config.triggers.iter().any(|t| t == trigger))
+        .collect();
+
+    let mut problems = Vec::new();
+    if !missing.is_empty() {
+        problems.push(format!("doesn't know {}", missing.join(", ")));
+    }
+    if !extra.is_empty() {
+        problems.push(format!("also sends {}", extra.join(", ")));
+    }
+    (!problems.is_empty()).then(|| format!("The bridge {}", problems.join(" and ")))
+}
+
+/// Describes a split that named a trigger other than the expected one
+pub fn mismatch(trigger: &str, expected: &str, ignored: bool) -> String {
+    format!(
+        "Bridge sent {}, expected {}{}",
+        trigger,
+        expected,
+        if ignored { ", ignored" } else { "" }
+    )
+}
+
+/// Something the simulation received
+enum Event {
+    Signal(i32),
+    Line(String),
+    /// The bridge closed its stdout, usually because it exited
+    Closed,
+}
+
+/// Runs the game's bridge script and prints what every signal and line it sends would do,
+/// without timing a run or writing anything. Stops on SIGINT, SIGTERM or when the bridge exits.
+pub fn simulate(config: &GameConfig) -> Result<()> {
+    let script = config
+        .bridge_script
+        .as_deref()
+        .context("The game has no bridge_script to simulate")?;
+
+    let (sender, receiver) = mpsc::channel();
+    let mut signals = Signals::new([SIGUSR1, SIGUSR2, SIGTERM, SIGINT])?;
+    let signal_sender = sender.clone();
+    std::thread::spawn(move || {
+        for sig in signals.forever() {
+            if signal_sender.send(Event::Signal(sig)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (mut child, mut stdin, stdout) = spawn(config, script)?;
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(Event::Line(line)).is_err() {
+                return;
+            }
+        }
+        let _ = sender.send(Event::Closed);
+    });
+
+    println!(
+        "Simulating {} with pid {}, stop with ctrl-c",
+        script.display(),
+        std::process::id()
+    );
+
+    // Index of the active section, None while no run would be active
+    let mut section: Option<usize> = None;
+    let (mut signal_count, mut line_count) = (0, 0);
+    let mut sequence = Sequence::default();
+    for event in receiver {
+        let number = match &event {
+            Event::Line(line) => numbered(line).1,
+            _ => None,
+        };
+        if let Some(number) = number {
+            // The bridge may wait for it, so it is sent even though nothing is timed
+            let _ = acknowledge(&mut stdin, number);
+        }
+        let (received, action) = match &event {
+            Event::Line(line) if number.is_some_and(|n| !sequence.is_new(n)) => (
+                format!("{:?}", line),
+                "would be ignored, the number was sent before".to_owned(),
+            ),
+            Event::Signal(SIGUSR1) => ("SIGUSR1".to_owned(), split(config, &mut section, None)),
+            Event::Signal(SIGUSR2) => ("SIGUSR2".to_owned(), reset(&mut section)),
+            Event::Signal(_) | Event::Closed => break,
+            Event::Line(line) => match parse(numbered(line).0) {
+                Message::Start if section.is_some() => (
+                    format!("{:?}", line),
+                    "would do nothing, a run is active".to_owned(),
+                ),
+                Message::Start => (format!("{:?}", line), split(config, &mut section, None)),
+                Message::Split(_) if section.is_none() && !config.auto_start => (
+                    format!("{:?}", line),
+                    "would be ignored, no run is active and auto_start is off".to_owned(),
+                ),
+                Message::Split(trigger) => {
+                    (format!("{:?}", line), split(config, &mut section, trigger))
+                }
+                Message::Checkpoint(name) => (
+                    format!("{:?}", line),
+                    match section {
+                        Some(i) => format!(
+                            "would record {} in {}",
+                            name.unwrap_or("a checkpoint"),
+                            config.sections[i]
+                        ),
+                        None => "would be ignored, no run is active".to_owned(),
+                    },
+                ),
+                Message::Capabilities(triggers) if config.triggers.is_empty() => (
+                    format!("{:?}", line),
+                    format!("lists {} triggers, the config has none", triggers.len()),
+                ),
+                Message::Capabilities(triggers) => (
+                    format!("{:?}", line),
+                    check_capabilities(config, &triggers)
+                        .unwrap_or_else(|| "matches the triggers of the config".to_owned()),
+                ),
+                Message::Category(_) if section.is_some() => (
+                    format!("{:?}", line),
+                    "would be refused, a run is active".to_owned(),
+                ),
+                Message::Category(name) => (
+                    format!("{:?}", line),
+                    match crate::category_game(config, name) {
+                        Ok(Some(game)) if game == config.directory_name => {
+                            "would do nothing, it is the category of the game".to_owned()
+                        }
+                        Ok(Some(game)) => format!("would switch to {}", game),
+                        Ok(None) => format!("would be refused, no game has the category {}", name),
+                        Err(e) => format!("would fail: {:#}", e),
+                    },
+                ),
+                Message::Other(_) => (format!("{:?}", line), "passed on to stderr".to_owned()),
+            },
+        };
+        match event {
+            Event::Signal(_) => signal_count += 1,
+            _ => line_count += 1,
+        }
+        println!(
+            "{}  {:<24} {}",
+            chrono::Local::now().format("%H:%M:%S%.3f"),
+            received,
+            action
+        );
+    }
+
+    let _ = child.kill();
+    println!("Received {} signals and {} lines", signal_count, line_count);
+
+    Ok(())
+}
+
+/// What a split would have done, advancing the simulated run like a real one
+fn split(config: &GameConfig, section: &mut Option<usize>, trigger: Option<&str>) -> String {
+    let Some(i) = *section else {
+        *section = Some(0);
+        return format!("would start the run in {}", config.sections[0]);
+    };
+
+    let mut action = String::new();
+    if let (Some(trigger), Some(expected)) = (trigger, config.triggers.get(i)) {
+        if trigger != expected {
+            let ignored = config.ignore_mismatched_triggers;
+            action = mismatch(trigger, expected, ignored);
+            if ignored {
+                return action;
+            }
+            action += ", ";
+        }
+    }
+
+    if i + 1 >= config.sections.len() {
+        *section = None;
+        action + &format!("would finish the run after {}", config.sections[i])
+    } else {
+        *section = Some(i + 1);
+        action
+            + &format!(
+                "would split {} and go on to {}",
+                config.sections[i],
+                config.sections[i + 1]
+            )
+    }
+}
+
+/// What a reset would have done
+fn reset(section: &mut Option<usize>) -> String {
+    match section.take() {
+        Some(_) => "would reset the run".to_owned(),
+        None => "would do nothing, no run is active".to_owned(),
+    }
+}
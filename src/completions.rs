@@ -0,0 +1,91 @@
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Returns a completion script that asks speedy itself for game names and run selectors
+pub fn script(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash => BASH,
+        Shell::Zsh => ZSH,
+        Shell::Fish => FISH,
+    }
+}
+
+const BASH: &str = r#"_speedy() {
+    local cur words cword
+    if declare -F _get_comp_words_by_ref >/dev/null; then
+        # Run names contain colons, which bash would otherwise split on
+        _get_comp_words_by_ref -n : cur words cword
+    else
+        cur=${COMP_WORDS[COMP_CWORD]}
+        words=("${COMP_WORDS[@]}")
+        cword=$COMP_CWORD
+    fi
+
+    if [ "$cword" -eq 1 ]; then
+        COMPREPLY=($(compgen -W "run relay against list-games new-game list-runs stats check export sheet report share-splits adopt-splits recompute-sob show compare audit simulate theme timestamps golds edit-run undo-last migrate retime calibrate validate-config paths completions" -- "$cur"))
+        return
+    fi
+
+    case "${words[1]}" in
+        relay)
+            COMPREPLY=($(compgen -W "$(speedy __complete-games 2>/dev/null)" -- "$cur"))
+            ;;
+        run|against|list-runs|stats|check|export|sheet|report|share-splits|adopt-splits|recompute-sob|show|compare|audit|simulate|theme|timestamps|golds|edit-run|undo-last|migrate|retime|calibrate|validate-config)
+            if [ "$cword" -eq 2 ]; then
+                COMPREPLY=($(compgen -W "$(speedy __complete-games 2>/dev/null)" -- "$cur"))
+            elif { [[ "${words[1]}" =~ ^(against|show|export|audit|timestamps|golds|edit-run)$ ]] && [ "$cword" -eq 3 ]; } ||
+                { [ "${words[1]}" = compare ] && [ "$cword" -le 4 ]; }; then
+                COMPREPLY=($(compgen -W "$(speedy __complete-runs "${words[2]}" 2>/dev/null)" -- "$cur"))
+            fi
+            ;;
+        completions)
+            COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur"))
+            ;;
+    esac
+
+    if declare -F __ltrim_colon_completions >/dev/null; then
+        __ltrim_colon_completions "$cur"
+    fi
+}
+complete -F _speedy speedy
+"#;
+
+const ZSH: &str = r#"#compdef speedy
+_speedy() {
+    if (( CURRENT == 2 )); then
+        compadd run relay against list-games new-game list-runs stats check export sheet report share-splits adopt-splits recompute-sob show compare audit simulate theme timestamps golds edit-run undo-last migrate retime calibrate validate-config paths completions
+        return
+    fi
+
+    case $words[2] in
+        relay)
+            compadd -- ${(f)"$(speedy __complete-games 2>/dev/null)"}
+            ;;
+        run|against|list-runs|stats|check|export|sheet|report|share-splits|adopt-splits|recompute-sob|show|compare|audit|simulate|theme|timestamps|golds|edit-run|undo-last|migrate|retime|calibrate|validate-config)
+            if (( CURRENT == 3 )); then
+                compadd -- ${(f)"$(speedy __complete-games 2>/dev/null)"}
+            elif [[ $words[2] == (against|show|export|audit|timestamps|golds|edit-run) && CURRENT -eq 4 || $words[2] == compare && CURRENT -le 5 ]]; then
+                compadd -- ${(f)"$(speedy __complete-runs $words[3] 2>/dev/null)"}
+            fi
+            ;;
+        completions)
+            compadd bash zsh fish
+            ;;
+    esac
+}
+compdef _speedy speedy
+"#;
+
+const FISH: &str = r#"complete -c speedy -f
+complete -c speedy -n __fish_use_subcommand -a "run relay against list-games new-game list-runs stats check export sheet report share-splits adopt-splits recompute-sob show compare audit simulate theme timestamps golds edit-run undo-last migrate retime calibrate validate-config paths completions"
+complete -c speedy -n "__fish_seen_subcommand_from relay" -a "(speedy __complete-games 2>/dev/null)"
+complete -c speedy -n "__fish_seen_subcommand_from run against list-runs stats check export sheet report share-splits adopt-splits recompute-sob show compare audit simulate theme timestamps golds edit-run undo-last migrate retime calibrate validate-config; and test (count (commandline -opc)) -eq 2" -a "(speedy __complete-games 2>/dev/null)"
+complete -c speedy -n "__fish_seen_subcommand_from against show export audit timestamps golds edit-run compare; and test (count (commandline -opc)) -ge 3" -a "(speedy __complete-runs (commandline -opc)[3] 2>/dev/null)"
+complete -c speedy -n "__fish_seen_subcommand_from completions" -a "bash zsh fish"
+"#;
@@ -0,0 +1,127 @@
+//! Converting runs into formats other timers and sites understand
+
+use crate::stats::{self, HistoricalRun};
+use crate::{GameConfig, Section};
+use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The splits.io Exchange Format, JSON
+    Exchange,
+}
+
+/// Builds a splits.io Exchange Format document of a run.
+///
+/// Segment IDs are derived from the game and section name, so they stay the same across
+/// exports. The run itself only gets an ID when `run_id` is set, and that one is a hash
+/// that doesn't reveal when the run happened.
+pub fn exchange(
+    config: &GameConfig,
+    run_name: &str,
+    sections: &[Section],
+    sum_of_best: Option<&[Section]>,
+    history: &[HistoricalRun],
+    run_id: bool,
+) -> Value {
+    let golds = sum_of_best.map(stats::segment_times);
+    let histories = history
+        .iter()
+        .enumerate()
+        .map(|(i, run)| {
+            let mut attempt = json!({ "attemptNumber": i + 1 });
+            if run.sections.len() == config.sections.len() {
+                let duration = run.sections.last().map_or(0, |s| s.time);
+                attempt["realtimeDurationMS"] = json!(duration);
+                if let Some(started_at) = run_start(&run.name) {
+                    let ended_at = started_at + Duration::milliseconds(duration as i64);
+                    attempt["endedAt"] = json!(ended_at.to_rfc3339());
+                }
+            }
+            if let Some(started_at) = run_start(&run.name) {
+                attempt["startedAt"] = json!(started_at.to_rfc3339());
+            }
+            attempt
+        })
+        .collect::<Vec<_>>();
+
+    let segments = sections
+        .iter()
+        .zip(stats::segment_times(sections))
+        .enumerate()
+        .map(|(i, (section, duration))| {
+            let segment_histories = history
+                .iter()
+                .enumerate()
+                .filter(|(_, run)| run.sections.len() > i)
+                .map(|(attempt, run)| {
+                    json!({
+                        "attemptNumber": attempt + 1,
+                        "realtimeMS": stats::segment_times(&run.sections)[i],
+                    })
+                })
+                .collect::<Vec<_>>();
+            let best = golds
+                .as_ref()
+                .and_then(|golds| golds.get(i).copied())
+                .unwrap_or(duration);
+
+            json!({
+                "id": stable_id(&[&config.directory_name, &section.name]),
+                "name": section.name,
+                "endedAt": { "realtimeMS": section.time },
+                "bestDuration": { "realtimeMS": best },
+                "histories": segment_histories,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut document = json!({
+        "_schemaVersion": "v1.0.1",
+        "timer": {
+            "shortname": "speedy",
+            "longname": "Speedy",
+            "version": concat!("v", env!("CARGO_PKG_VERSION")),
+        },
+        "attempts": {
+            "total": history.len(),
+            "histories": histories,
+        },
+        "game": { "longname": config.full_game_name },
+        "category": { "longname": config.category.as_deref().unwrap_or("Any%") },
+        "runners": [],
+        "defaultTiming": "real",
+        "segments": segments,
+    });
+    if let Some(started_at) = run_start(run_name) {
+        document["startedAt"] = json!(started_at.to_rfc3339());
+    }
+    if run_id {
+        document["id"] = json!(stable_id(&[&config.directory_name, run_name]));
+    }
+
+    document
+}
+
+/// Reads the start time out of a dated run name like `2024-05-01T10:00:00_2.run`
+pub fn run_start(run_name: &str) -> Option<chrono::DateTime<Utc>> {
+    let stem = run_name.get(..19)?;
+    let naive = NaiveDateTime::parse_from_str(stem, "%Y-%m-%dT%H:%M:%S").ok()?;
+    chrono::Local
+        .from_local_datetime(&naive)
+        .single()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// FNV-1a over the parts, which unlike the std hasher is guaranteed to never change
+fn stable_id(parts: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for byte in part.bytes().chain([0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,85 @@
+//! Picking dated runs by what is known about them, shared by `list-runs` and `stats`
+
+use crate::export::run_start;
+use crate::RunMetadata;
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use clap::Args;
+
+// Filters that can be given to the commands listing runs. Not a doc comment, clap would show
+// it as the help text of the command.
+#[derive(Args, Debug, Clone, Default)]
+pub struct RunFilter {
+    /// Only runs that finished the last section
+    #[arg(long)]
+    pub complete_only: bool,
+    /// Only runs started on this local day or later, like 2024-01-01
+    #[arg(long, value_name = "DATE", value_parser = parse_day)]
+    pub since: Option<NaiveDate>,
+    /// Only runs timed in this category, ignoring case
+    #[arg(long)]
+    pub category: Option<String>,
+    /// Only runs timed on this route, ignoring case
+    #[arg(long)]
+    pub route: Option<String>,
+    /// Only runs whose note contains this text, ignoring case
+    #[arg(long, value_name = "TEXT")]
+    pub with_note: Option<String>,
+}
+
+fn parse_day(day: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .with_context(|| format!("{:?} is not a day like 2024-01-01", day))
+}
+
+impl RunFilter {
+    /// No filter was given, every run passes
+    pub fn is_empty(&self) -> bool {
+        !self.complete_only
+            && self.since.is_none()
+            && self.category.is_none()
+            && self.route.is_none()
+            && self.with_note.is_none()
+    }
+
+    /// Whether a run passes every given filter. Runs without a start time only pass without
+    /// `--since`, runs without a category, route or note only pass without those filters.
+    pub fn matches(&self, run: &str, complete: bool, metadata: &RunMetadata) -> bool {
+        if self.complete_only && !complete {
+            return false;
+        }
+        if let Some(since) = self.since {
+            // Midnight where the runner is, which is where run names are dated as well
+            let since = since
+                .and_hms_opt(0, 0, 0)
+                .and_then(|midnight| midnight.and_local_timezone(chrono::Local).earliest())
+                .map(|midnight| midnight.with_timezone(&Utc));
+            let started = metadata
+                .started_at
+                .map(|date| date.with_timezone(&Utc))
+                .or_else(|| run_start(run));
+            if !matches!((started, since), (Some(started), Some(since)) if started >= since) {
+                return false;
+            }
+        }
+        if let Some(category) = &self.category {
+            let timed_in = metadata.category.as_deref().unwrap_or_default();
+            if !timed_in.eq_ignore_ascii_case(category) {
+                return false;
+            }
+        }
+        if let Some(route) = &self.route {
+            let timed_on = metadata.route.as_deref().unwrap_or_default();
+            if !timed_on.eq_ignore_ascii_case(route) {
+                return false;
+            }
+        }
+        if let Some(text) = &self.with_note {
+            let note = metadata.note.as_deref().unwrap_or_default().to_lowercase();
+            if !note.contains(&text.to_lowercase()) {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,112 @@
+//! Which window has the focus, so a practice run can pause while the game is in the background
+
+use crate::settings::FocusBackend;
+use anyhow::{ensure, Context, Result};
+use serde_json::Value;
+use std::process::Command;
+
+/// The focused window of the desktop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    pub title: String,
+    /// The app id on Wayland, the class on X11
+    pub class: Option<String>,
+}
+
+impl Window {
+    /// Whether `pattern` is part of the title or class, ignoring case
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.to_lowercase();
+        [Some(&self.title), self.class.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|name| name.to_lowercase().contains(&pattern))
+    }
+}
+
+/// Asks the desktop which window has the focus
+pub trait FocusSource {
+    /// Shown with the reason of a pause
+    fn name(&self) -> &'static str;
+
+    /// None if no window has the focus
+    fn focused_window(&mut self) -> Result<Option<Window>>;
+}
+
+/// The source of the `focus_backend` setting, None if it's off
+pub fn source(backend: FocusBackend) -> Option<Box<dyn FocusSource + Send>> {
+    match backend {
+        FocusBackend::Off => None,
+        FocusBackend::Sway => Some(Box::new(Sway)),
+        FocusBackend::X11 => Some(Box::new(X11)),
+    }
+}
+
+/// Sway and other compositors that speak its IPC, through `swaymsg`
+pub struct Sway;
+
+impl FocusSource for Sway {
+    fn name(&self) -> &'static str {
+        "sway"
+    }
+
+    fn focused_window(&mut self) -> Result<Option<Window>> {
+        let output = Command::new("swaymsg")
+            .args(["-t", "get_tree", "--raw"])
+            .output()
+            .context("Failed to run swaymsg")?;
+        ensure!(
+            output.status.success(),
+            "swaymsg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        let tree: Value =
+            serde_json::from_slice(&output.stdout).context("swaymsg printed no tree")?;
+        Ok(focused_node(&tree).map(|node| Window {
+            title: node["name"].as_str().unwrap_or_default().to_owned(),
+            class: node["app_id"]
+                .as_str()
+                .or_else(|| node["window_properties"]["class"].as_str())
+                .map(str::to_owned),
+        }))
+    }
+}
+
+/// The focused window in a tree of `swaymsg -t get_tree`. Workspaces and outputs can be
+/// focused too while they are empty, they have no window.
+fn focused_node(node: &Value) -> Option<&Value> {
+    if node["focused"].as_bool() == Some(true) {
+        return (node["type"] == "con" || node["type"] == "floating_con").then_some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+        .find_map(focused_node)
+}
+
+/// X11 window managers, through `xdotool`
+pub struct X11;
+
+impl FocusSource for X11 {
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn focused_window(&mut self) -> Result<Option<Window>> {
+        let output = Command::new("xdotool")
+            .args(["getactivewindow", "getwindowname", "getwindowclassname"])
+            .output()
+            .context("Failed to run xdotool")?;
+        // Also when no window is active
+        if !output.status.success() {
+            return Ok(None);
+        }
+        let output = String::from_utf8_lossy(&output.stdout);
+        let mut lines = output.lines();
+        Ok(lines.next().map(|title| Window {
+            title: title.to_owned(),
+            class: lines.next().map(str::to_owned),
+        }))
+    }
+}
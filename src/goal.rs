@@ -0,0 +1,126 @@
+//! A goal for a session of runs, like `get 2 runs to Boss` or `gold Library`. Goals that
+//! name a section or a number of runs are tracked as the runs happen, any other text is
+//! only shown.
+
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    /// Runs that begin the section
+    Reach {
+        section: String,
+        runs: usize,
+    },
+    Gold {
+        section: String,
+    },
+    Finish {
+        runs: usize,
+    },
+    Pb,
+    Text,
+}
+
+#[derive(Debug, Clone)]
+pub struct Goal {
+    pub text: String,
+    target: Target,
+    progress: usize,
+}
+
+impl Goal {
+    /// Understands `[get] [N runs] to <section>`, `reach <section>`, `gold [the] <section>
+    /// [split]`, `finish [N runs]` and `[get a] pb`, with section names in any case
+    pub fn parse(text: &str, sections: &[String]) -> Self {
+        let text = text.trim().to_owned();
+        let section = |name: &str| {
+            sections
+                .iter()
+                .find(|s| s.eq_ignore_ascii_case(name.trim()))
+                .cloned()
+        };
+        let runs = |n: Option<regex::Match>| n.map_or(Ok(1), |n| n.as_str().parse()).unwrap_or(1);
+
+        let reach = Regex::new(r"(?i)^(?:get )?(?:(\d+) runs? )?(?:to|reach) (?:the )?(.+)$");
+        let gold = Regex::new(r"(?i)^gold (?:the )?(.+?)(?: split)?$");
+        let finish = Regex::new(r"(?i)^finish(?: (\d+) runs?)?$");
+        let pb = Regex::new(r"(?i)^(?:get )?(?:a )?(?:new )?pb$");
+        let target = if let Some(cap) = reach.unwrap().captures(&text) {
+            section(&cap[2]).map(|section| Target::Reach {
+                section,
+                runs: runs(cap.get(1)),
+            })
+        } else if let Some(cap) = gold.unwrap().captures(&text) {
+            section(&cap[1]).map(|section| Target::Gold { section })
+        } else if let Some(cap) = finish.unwrap().captures(&text) {
+            Some(Target::Finish {
+                runs: runs(cap.get(1)),
+            })
+        } else {
+            pb.unwrap().is_match(&text).then_some(Target::Pb)
+        };
+
+        Self {
+            text,
+            target: target.unwrap_or(Target::Text),
+            progress: 0,
+        }
+    }
+
+    /// Whether the goal is tracked and was reached
+    pub fn is_done(&self) -> bool {
+        self.progress >= self.needed().unwrap_or(usize::MAX)
+    }
+
+    /// Whether the goal is more than text to show
+    pub fn is_tracked(&self) -> bool {
+        self.needed().is_some()
+    }
+
+    fn needed(&self) -> Option<usize> {
+        match self.target {
+            Target::Reach { runs, .. } | Target::Finish { runs } => Some(runs),
+            Target::Gold { .. } | Target::Pb => Some(1),
+            Target::Text => None,
+        }
+    }
+
+    /// The goal with how far it got, like "get 2 runs to Boss (1/2)"
+    pub fn status(&self) -> String {
+        match self.needed() {
+            _ if self.is_done() => format!("{} (done)", self.text),
+            Some(needed) if needed > 1 => format!("{} ({}/{})", self.text, self.progress, needed),
+            _ => self.text.clone(),
+        }
+    }
+
+    /// Counts an event of a run, true if that completed the goal
+    fn count(&mut self, counts: bool) -> bool {
+        if !counts || self.is_done() {
+            return false;
+        }
+        self.progress += 1;
+        self.is_done()
+    }
+
+    /// A run began the section
+    pub fn reached(&mut self, name: &str) -> bool {
+        let counts = matches!(&self.target, Target::Reach { section, .. } if section == name);
+        self.count(counts)
+    }
+
+    /// The section was split as a gold
+    pub fn gold(&mut self, name: &str) -> bool {
+        let counts = matches!(&self.target, Target::Gold { section } if section == name);
+        self.count(counts)
+    }
+
+    pub fn finished(&mut self, pb: bool) -> bool {
+        let counts = match self.target {
+            Target::Finish { .. } => true,
+            Target::Pb => pb,
+            _ => false,
+        };
+        self.count(counts)
+    }
+}
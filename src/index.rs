@@ -0,0 +1,113 @@
+//! A cache of the parsed dated runs of a game in its `index.toml` in the cache directory, so
+//! read-only commands don't have to parse every run file each time. Entries are checked against
+//! the run's version in the store, the file's mtime for runs kept in files.
+
+use crate::{cache_path, game_dir, list_runs, load_run, store, write_atomically, GameConfig};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::OnceLock;
+
+/// Set by `--no-cache`
+pub static DISABLED: OnceLock<bool> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Default)]
+struct Index {
+    /// The config's sections when the index was written, it is rebuilt when they change
+    sections: Vec<String>,
+    #[serde(default)]
+    runs: BTreeMap<String, Entry>,
+}
+
+/// What is known about a dated run without parsing it again
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Version of the run in the store, its modification time in nanoseconds since the epoch
+    modified: String,
+    /// Number of sections in the run
+    pub sections: usize,
+    pub final_time: Option<u32>,
+    /// Covers every section of the config
+    pub complete: bool,
+    /// The section names are the config's first sections
+    pub matches_config: bool,
+    /// Cumulative times of the sections
+    pub times: Vec<u32>,
+}
+
+/// Every readable dated run of a game with its entry, oldest first. Entries of changed files
+/// are refreshed and the index written back if anything changed.
+pub fn runs(config: &GameConfig) -> Result<Vec<(String, Entry)>> {
+    let game = &config.directory_name;
+    let path = cache_path(game, "index.toml")?;
+    let disabled = *DISABLED.get_or_init(|| false);
+
+    // A corrupted or outdated index is simply rebuilt
+    let mut index = if disabled {
+        Index::default()
+    } else {
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|index_str| toml::from_str::<Index>(&index_str).ok())
+            .filter(|index| index.sections == config.sections)
+            .unwrap_or_default()
+    };
+    let mut changed = index.sections != config.sections;
+    index.sections = config.sections.clone();
+
+    let store = store::open(game)?;
+    let mut runs = Vec::new();
+    let names = list_runs(game)?;
+    for name in &names {
+        let Some(modified) = store.version(name)? else {
+            continue;
+        };
+        match index.runs.get(name) {
+            Some(entry) if entry.modified == modified => {}
+            _ => {
+                // Unreadable runs stay out of the index and are tried again next time
+                let Ok(Some(sections)) = load_run(game, name) else {
+                    continue;
+                };
+                let matches_config = sections.len() <= config.sections.len()
+                    && sections
+                        .iter()
+                        .zip(&config.sections)
+                        .all(|(section, name)| section.name == *name);
+                let entry = Entry {
+                    modified,
+                    sections: sections.len(),
+                    final_time: sections.last().map(|s| s.time),
+                    complete: matches_config && sections.len() == config.sections.len(),
+                    matches_config,
+                    times: sections.iter().map(|s| s.time).collect(),
+                };
+                index.runs.insert(name.clone(), entry);
+                changed = true;
+            }
+        }
+        runs.push((name.clone(), index.runs[name].clone()));
+    }
+
+    let len = index.runs.len();
+    index.runs.retain(|name, _| names.contains(name));
+    changed |= index.runs.len() != len;
+
+    if changed && !disabled {
+        // Only a cache, a read-only data directory just means parsing again next time
+        if let Ok(index_str) = toml::to_string(&index) {
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            let _ = write_atomically(&path, index_str.as_bytes());
+        }
+        // Older versions kept it next to the runs
+        let old = game_dir(game)?.join("index.toml");
+        if old != path {
+            let _ = fs::remove_file(old);
+        }
+    }
+
+    Ok(runs)
+}
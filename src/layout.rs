@@ -0,0 +1,236 @@
+use crate::settings::{Comparison, Layout, Settings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Name,
+    Comparison,
+    /// Cumulative time and its delta
+    Total,
+    /// Segment time and its delta
+    Segment,
+    Gold,
+    /// Median segment of the history
+    Median,
+}
+
+/// Positions of the split table columns, computed once from the settings
+#[derive(Debug, Clone)]
+pub struct TableLayout {
+    /// Columns with the x coordinate where their content starts
+    pub columns: Vec<(Column, i32)>,
+    /// Width of a single formatted time like "12:34.5"
+    pub time_width: usize,
+    /// Width of the widest delta like "(+1:23)" that the comparisons allow for
+    pub delta_width: usize,
+    /// The total column shows the delta to the sum of best next to the one to the comparison
+    pub dual_delta: bool,
+    /// Minimum terminal width needed for the table
+    pub width: u16,
+    /// Named in the header of the comparison column
+    pub comparison: Comparison,
+    /// Copies of the table side by side that the sections are spread over
+    pub blocks: usize,
+    /// Rows of each copy, the sections continue in the next one after that
+    pub block_rows: usize,
+    /// Width of a single copy
+    pub block_width: u16,
+    /// For each section, whether its name is drawn in the highlight color
+    pub highlighted: Vec<bool>,
+    /// Comparisons whose times were rewritten by a section migration, marked with a `~`
+    pub migrated: Vec<Comparison>,
+    /// Comparisons recorded under another route than the config's, marked with a `*`
+    pub other_route: Vec<Comparison>,
+}
+
+impl TableLayout {
+    pub fn new(settings: &Settings, sum_of_best_column: bool, median_column: bool) -> Self {
+        let mut kinds = match settings.layout {
+            Layout::Full => vec![
+                Column::Name,
+                Column::Comparison,
+                Column::Total,
+                Column::Segment,
+            ],
+            Layout::Compact => vec![Column::Name, Column::Total],
+        };
+        if !settings.comparison_column {
+            kinds.retain(|&kind| kind != Column::Comparison);
+        }
+        if sum_of_best_column {
+            kinds.push(Column::Gold);
+        }
+        // Only next to the comparisons of the full layout
+        if median_column && settings.layout == Layout::Full {
+            kinds.push(Column::Median);
+        }
+
+        Self::with_columns(settings, kinds, 1, usize::MAX)
+    }
+
+    /// Spreads the rows of the table over several copies side by side, for games with more
+    /// sections than fit below each other. Only the name and segment columns are kept.
+    pub fn multi_column(settings: &Settings, rows: usize, blocks: usize) -> Self {
+        let kinds = vec![Column::Name, Column::Segment];
+        Self::with_columns(settings, kinds, blocks, rows.div_ceil(blocks))
+    }
+
+    fn with_columns(
+        settings: &Settings,
+        kinds: Vec<Column>,
+        blocks: usize,
+        block_rows: usize,
+    ) -> Self {
+        let time_width = if settings.precision == 0 {
+            5
+        } else {
+            6 + settings.precision as usize
+        };
+
+        let mut layout = Self {
+            columns: kinds.into_iter().map(|kind| (kind, 0)).collect(),
+            time_width,
+            // Parentheses and a sign around a time of less than ten minutes
+            delta_width: time_width + 2,
+            dual_delta: false,
+            width: 0,
+            comparison: settings.comparison,
+            blocks,
+            block_rows,
+            block_width: 0,
+            highlighted: Vec::new(),
+            migrated: Vec::new(),
+            other_route: Vec::new(),
+        };
+
+        layout.place_columns();
+        layout
+    }
+
+    /// Makes room for deltas of another width, like the hours of a long run
+    pub fn with_delta_width(mut self, delta_width: usize) -> Self {
+        self.delta_width = delta_width;
+        self.place_columns();
+        self
+    }
+
+    /// Makes room for two deltas like "(+0:03 | -0:01)" in the total column
+    pub fn with_dual_delta(mut self, dual_delta: bool) -> Self {
+        self.dual_delta = dual_delta;
+        self.place_columns();
+        self
+    }
+
+    fn place_columns(&mut self) {
+        // Columns are separated by " | "
+        let mut x = 1;
+        for i in 0..self.columns.len() {
+            self.columns[i].1 = x;
+            x += self.column_width(self.columns[i].0) as i32 + 3;
+        }
+        self.block_width = (x - 2) as u16;
+        self.width = self.block_width * self.blocks as u16 + 3 * (self.blocks as u16 - 1);
+    }
+
+    /// Rows the table takes up on screen
+    pub fn visible_rows(&self, rows: usize) -> usize {
+        rows.min(self.block_rows)
+    }
+
+    /// Where a row of the table ends up, as the x offset of its copy and the screen row
+    pub fn place(&self, row: usize) -> (i32, i32) {
+        let block = row / self.block_rows;
+        let x = block as i32 * (self.block_width as i32 + 3);
+        (x, (row % self.block_rows) as i32 + 3)
+    }
+
+    pub fn column_width(&self, column: Column) -> usize {
+        match column {
+            Column::Name => 7,
+            Column::Comparison | Column::Gold | Column::Median => self.time_width,
+            // A time, a space and a delta like "(+1:23)"
+            Column::Total if self.dual_delta => self.time_width + 1 + 2 * self.delta_width + 1,
+            Column::Total | Column::Segment => self.time_width + 1 + self.delta_width,
+        }
+    }
+
+    pub fn header(&self) -> String {
+        // On the comparison column, or the deltas of the current times without it
+        let approximate = self.migrated.contains(&self.comparison);
+        let other_route = self.other_route.contains(&self.comparison);
+        let marked = if self.columns.iter().any(|&(c, _)| c == Column::Comparison) {
+            Column::Comparison
+        } else {
+            Column::Total
+        };
+        let titles = self
+            .columns
+            .iter()
+            .map(|&(column, _)| {
+                let title = match column {
+                    Column::Name => "section".to_owned(),
+                    Column::Comparison => self.comparison.short_name().to_owned(),
+                    Column::Total => "current".to_owned(),
+                    Column::Segment => "section".to_owned(),
+                    Column::Gold => "gold".to_owned(),
+                    Column::Median => "med".to_owned(),
+                };
+                let title = if approximate && column == marked {
+                    title + "~"
+                } else {
+                    title
+                };
+                let title = if other_route && column == marked {
+                    title + "*"
+                } else {
+                    title
+                };
+                format!("{:width$}", title, width = self.column_width(column))
+            })
+            .collect::<Vec<_>>();
+
+        let block = format!(" {}", titles.join(" | "));
+        vec![block; self.blocks].join("   ")
+    }
+
+    pub fn divider(&self) -> String {
+        let dashes = self
+            .columns
+            .iter()
+            .map(|&(column, _)| "-".repeat(self.column_width(column) + 2))
+            .collect::<Vec<_>>();
+
+        // Line up with the header, which starts with a space and has no trailing one
+        let mut divider = dashes.join("|");
+        divider.replace_range(0..1, " ");
+        divider.pop();
+        vec![divider; self.blocks].join("   ")
+    }
+}
+
+/// Renders text in digits that are five rows tall
+pub fn big_text(text: &str) -> [String; 5] {
+    let mut rows: [String; 5] = Default::default();
+    for c in text.chars() {
+        let glyph: [&str; 5] = match c {
+            '0' => ["███", "█ █", "█ █", "█ █", "███"],
+            '1' => ["  █", "  █", "  █", "  █", "  █"],
+            '2' => ["███", "  █", "███", "█  ", "███"],
+            '3' => ["███", "  █", "███", "  █", "███"],
+            '4' => ["█ █", "█ █", "███", "  █", "  █"],
+            '5' => ["███", "█  ", "███", "  █", "███"],
+            '6' => ["███", "█  ", "███", "█ █", "███"],
+            '7' => ["███", "  █", "  █", "  █", "  █"],
+            '8' => ["███", "█ █", "███", "█ █", "███"],
+            '9' => ["███", "█ █", "███", "  █", "███"],
+            ':' => [" ", "█", " ", "█", " "],
+            '.' => [" ", " ", " ", " ", "█"],
+            _ => ["   "; 5],
+        };
+        for (row, part) in rows.iter_mut().zip(glyph) {
+            row.push_str(part);
+            row.push(' ');
+        }
+    }
+
+    rows
+}
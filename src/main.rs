@@ -1,22 +1,51 @@
+mod announce;
+mod bridge;
+mod completions;
+mod export;
+mod filter;
+mod focus;
+mod goal;
+mod index;
+mod layout;
+mod report;
+mod script;
+mod settings;
+mod share;
+mod sheet;
+mod stats;
+mod store;
+#[cfg(test)]
+mod tests;
+mod theme;
+
 use anyhow::{anyhow, bail, ensure, Context, Result};
 use clap::{Parser, Subcommand};
+use console_engine::crossterm::event::{KeyEventKind, KeyModifiers};
 use console_engine::crossterm::terminal;
 use console_engine::pixel::pxl_bg;
-use nix::sys::signal::{self, Signal};
-use nix::unistd::Pid;
+use nix::errno::Errno;
+use nix::fcntl::{flock, FlockArg};
 use regex::Regex;
+use std::collections::{BTreeMap, VecDeque};
 use std::fs::{self, File};
-use std::io::{self, BufRead, BufReader, BufWriter, Write};
-use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, RwLock};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard, OnceLock, RwLock};
 use std::time::{Duration, Instant};
 
 use console_engine::{Color, ConsoleEngine, KeyCode};
-use rodio::source::SineWave;
-use rodio::{Sink, Source};
+use filter::RunFilter;
+use layout::{Column, TableLayout};
+use rodio::source::{Amplify, Buffered, SineWave, TakeDuration};
+use rodio::{Decoder, Sink, Source};
 use serde::{Deserialize, Serialize};
-use signal_hook::{consts::SIGUSR1, iterator::Signals};
+use settings::{ColorMode, Comparison, Settings, SettingsOverrides};
+use signal_hook::consts::{SIGINT, SIGTERM, SIGUSR1, SIGUSR2};
+use signal_hook::iterator::Signals;
+use stats::HistoricalRun;
+use store::Storage;
+use theme::Theme;
 
 const FG: Color = Color::Rgb {
     r: 0xf3,
@@ -43,6 +72,11 @@ const GOLD: Color = Color::Rgb {
     g: 0xd5,
     b: 0x66,
 };
+const PURPLE: Color = Color::Rgb {
+    r: 0xc5,
+    g: 0x86,
+    b: 0xc0,
+};
 const BG: Color = Color::Rgb {
     r: 0x09,
     g: 0x09,
@@ -57,8 +91,198 @@ struct GameConfig {
     directory_name: String,
 
     full_game_name: String,
+    /// Only used when exporting, e.g. "Any%"
+    category: Option<String>,
+    /// The route or strats the runs follow, like "new skip v2". Noted in every saved run and
+    /// in the comparisons, which are apples to oranges once it changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    route: Option<String>,
     bridge_script: Option<PathBuf>,
     sections: Vec<String>,
+
+    /// Whether the runs are kept in files or in the game's `speedy.db`, changed with `speedy
+    /// migrate`
+    #[serde(default, skip_serializing_if = "Storage::is_default")]
+    storage: Storage,
+
+    /// What the bridge calls the end of each section, sent as `split <trigger>`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    triggers: Vec<String>,
+
+    /// Drop splits from the bridge that name a trigger other than the expected one
+    #[serde(default)]
+    ignore_mismatched_triggers: bool,
+
+    /// Environment variables for the bridge script
+    #[serde(default, skip_serializing_if = "bridge::Config::is_empty")]
+    bridge: bridge::Config,
+
+    /// Whether the bridge keeps running after a finished run, is stopped, or is stopped and
+    /// started again for the next run
+    #[serde(default)]
+    bridge_after_finish: bridge::AfterFinish,
+
+    /// Let a `split` from the bridge start a run, instead of only `start`
+    #[serde(default)]
+    auto_start: bool,
+
+    /// How late the bridge's start fires after the run really began, found by `speedy
+    /// calibrate`. Runs started by the bridge count from that much earlier.
+    #[serde(default)]
+    start_trigger_offset_ms: i64,
+
+    /// Show each section's gold segment in an extra column
+    #[serde(default)]
+    sum_of_best_column: bool,
+
+    /// Show each section's median segment in an extra column of the full layout
+    #[serde(default)]
+    median_column: bool,
+
+    /// Show the delta to the sum of best next to the delta of every split
+    #[serde(default)]
+    dual_delta: bool,
+
+    /// Sections whose names stand out in the table, e.g. the ones with a lot of RNG
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    highlight: Vec<String>,
+
+    /// Play a warning tone when a highlighted section becomes the active one
+    #[serde(default)]
+    highlight_tone: bool,
+
+    /// Audio played on splits instead of the beep
+    #[serde(default)]
+    sounds: SoundsConfig,
+
+    /// Comparisons to switch between as the run progresses, replaces the comparison setting
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    comparison_schedule: Vec<ScheduledComparison>,
+
+    /// Segment times to beat, like `boss = "2:00"`, independent of any comparison
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    targets: BTreeMap<String, String>,
+
+    /// Overrides the settings file for this game
+    #[serde(flatten)]
+    settings: SettingsOverrides,
+
+    /// Keys of config.toml that speedy doesn't know, shown before the run
+    #[serde(skip)]
+    warnings: Vec<String>,
+}
+
+/// The keys of config.toml besides the settings, serde can't reject unknown keys next to
+/// the flattened settings
+const CONFIG_KEYS: &[&str] = &[
+    "version",
+    "full_game_name",
+    "category",
+    "route",
+    "bridge_script",
+    "sections",
+    "storage",
+    "triggers",
+    "ignore_mismatched_triggers",
+    "bridge",
+    "bridge_after_finish",
+    "auto_start",
+    "start_trigger_offset_ms",
+    "sum_of_best_column",
+    "median_column",
+    "dual_delta",
+    "highlight",
+    "highlight_tone",
+    "sounds",
+    "comparison_schedule",
+    "targets",
+];
+
+impl GameConfig {
+    /// The target segment time of a section, if one is set
+    fn target(&self, section: usize) -> Option<u32> {
+        let target = self.targets.get(&self.sections[section])?;
+        parse_time(target).ok()
+    }
+}
+
+/// The `[sounds]` table of a game's config
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct SoundsConfig {
+    /// Clips for the split ending a section, by section name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    sections: BTreeMap<String, SectionClips>,
+    /// Command that is given the delta of each split to say, like `espeak`. Needs the `tts`
+    /// feature.
+    speak_command: Option<String>,
+    /// Raise the split beep when ahead of the comparison and lower it when behind
+    delta_pitch: Option<DeltaPitch>,
+    /// No sounds at all, the audio device is never opened
+    #[serde(default)]
+    silent: bool,
+    /// Seconds the audio device stays open after the last sound, 30 unless set
+    close_after: Option<u64>,
+}
+
+/// The `[sounds.delta_pitch]` table, how far the split beep moves away from 440 Hz
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct DeltaPitch {
+    /// Semitones up when ahead and down when behind
+    #[serde(default = "DeltaPitch::default_semitones")]
+    semitones: f32,
+    /// Seconds ahead or behind for the full range, smaller deltas move the pitch less. The
+    /// pitch only tells ahead from behind if unset.
+    full_range_at: Option<f32>,
+}
+
+impl DeltaPitch {
+    fn default_semitones() -> f32 {
+        5.0
+    }
+
+    /// Pitch step of the split beep for a delta in milliseconds, negative when ahead, from
+    /// `-PITCH_STEPS` when behind by the full range to `PITCH_STEPS` when ahead by it
+    fn step(&self, delta: i64) -> i64 {
+        let share = match self.full_range_at {
+            Some(seconds) => (delta.unsigned_abs() as f32 / 1000.0 / seconds).min(1.0),
+            None => 1.0,
+        };
+        -delta.signum() * (share * PITCH_STEPS as f32).round() as i64
+    }
+
+    /// Frequency of the split beep at a pitch step
+    fn frequency(&self, step: i64) -> f32 {
+        let semitones = step as f32 / PITCH_STEPS as f32 * self.semitones;
+        440.0 * 2f32.powf(semitones / 12.0)
+    }
+}
+
+/// Pitch steps of the split beep each way, so the pitched beeps can be built up front
+const PITCH_STEPS: i64 = 8;
+
+/// Audio files for how a section went, each falling back to the beep if missing
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct SectionClips {
+    gold: Option<PathBuf>,
+    gaining: Option<PathBuf>,
+    losing: Option<PathBuf>,
+}
+
+/// How a section went compared to the golds and the active comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SplitKind {
+    Gold,
+    /// Faster than the comparison's segment
+    Gaining,
+    Losing,
+}
+
+/// One entry of a game's comparison schedule
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ScheduledComparison {
+    /// Last section this comparison is used for, None for the rest of the run
+    until_section: Option<String>,
+    compare: Comparison,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -67,558 +291,3781 @@ struct Section {
     time: u32,
 }
 
+/// Extra information about a dated run, stored as `<run name>.toml` next to it when runs are
+/// kept in files
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct RunMetadata {
+    /// The run's golds were kept out of the sum of best
+    #[serde(default)]
+    golds_suppressed: bool,
+    /// The comparison the deltas were measured against during the run
+    comparison: Option<Comparison>,
+    /// Final time of that comparison at the time of the run
+    comparison_time: Option<u32>,
+    /// Taken every few minutes during the run, to check the timing afterwards
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    drift_samples: Vec<DriftSample>,
+    /// Changes of the system clock noticed during the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    clock_jumps: Vec<ClockJump>,
+    /// Version of speedy that timed the run
+    speedy_version: Option<String>,
+    /// Version of the game's config at the time of the run
+    config_version: Option<u32>,
+    /// Machine the run was timed on
+    hostname: Option<String>,
+    /// Whether a bridge script was running
+    bridge: Option<bool>,
+    /// Informal points inside sections, they don't change any split
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    checkpoints: Vec<Checkpoint>,
+    /// Wall clock time of the start signal
+    started_at: Option<chrono::DateTime<chrono::Local>>,
+    /// Wall clock time of every split, to line the run up with a recording
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    split_dates: Vec<chrono::DateTime<chrono::Local>>,
+    /// The game's category when the run was timed
+    category: Option<String>,
+    /// The game's route when the run was timed, or when the PB or sum of best were written
+    route: Option<String>,
+    /// Written by hand, e.g. "new route for the boss"
+    note: Option<String>,
+    /// When and how a section migration changed the splits of the run, like
+    /// "2024-05-01: removed Boss". Only kept for the PB and sum of best, whose deltas are
+    /// approximate afterwards.
+    migration: Option<String>,
+    /// Sections that took 0ms, split in the same millisecond as the section before. They
+    /// are kept as timed but never count as golds.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    instant_splits: Vec<String>,
+    /// Offsets applied by `speedy retime` when the timing rules changed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    retimes: Vec<Retime>,
+}
+
+/// A change of the run's times by `offset` milliseconds, as if its timer started that much
+/// earlier
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct Retime {
+    date: chrono::NaiveDate,
+    offset: i64,
+}
+
+/// A point reached inside a section, recorded with `k` or a `checkpoint` line of the bridge
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Checkpoint {
+    /// Index of the section it was reached in
+    section: usize,
+    name: String,
+    /// Time into the run in milliseconds
+    time: u32,
+}
+
+impl RunMetadata {
+    /// A line about where the run was timed, None for runs from before this was recorded
+    fn environment(&self) -> Option<String> {
+        let version = self.speedy_version.as_ref()?;
+        let mut line = format!("Timed with speedy {}", version);
+        if let Some(config_version) = self.config_version {
+            line += &format!(", config version {}", config_version);
+        }
+        if let Some(hostname) = &self.hostname {
+            line += &format!(" on {}", hostname);
+        }
+        match self.bridge {
+            Some(true) => line += ", bridge active",
+            Some(false) => line += ", no bridge",
+            None => {}
+        }
+        Some(line)
+    }
+}
+
+/// The wall clock moved by `jump` milliseconds more than the timer, `at` milliseconds into
+/// the run. Negative if it went backwards.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct ClockJump {
+    at: u32,
+    jump: i64,
+}
+
+/// Differences between the wall clock and the timer below this are ignored, in milliseconds
+const CLOCK_JUMP_THRESHOLD: i64 = 2000;
+
+/// How far apart a key press and the bridge's start may be to count as one calibration
+/// attempt, in milliseconds
+const CALIBRATION_WINDOW_MS: u64 = 5000;
+
+/// The run's timer next to the wall clock at one point of a run, both in milliseconds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct DriftSample {
+    monotonic: u32,
+    wall_clock: i64,
+}
+
+/// How often a practice run asks which window has the focus
+const FOCUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a drift sample is taken
+const DRIFT_SAMPLE_INTERVAL: u32 = 10 * 60 * 1000;
+
+/// Golds that improve the sum of best by more than this share are only kept after asking
+const LARGE_GOLD_IMPROVEMENT: f64 = 0.05;
+
+/// Past runs count towards the likely finish when they left the last split section within
+/// this share of the run's time there, or within the minimum below
+const FINISH_RANGE_TOLERANCE: f64 = 0.05;
+const FINISH_RANGE_MIN_TOLERANCE: u32 = 2000;
+
+/// One game of a relay, with its own comparisons
 #[derive(Debug, Clone)]
-struct RunApp {
+struct RelayLeg {
     config: GameConfig,
-    current_sections: Vec<Section>,
+    /// The game's own settings, with its config in place of the relay's
+    settings: Settings,
     pb_sections: Option<Vec<Section>>,
     sum_of_best_sections: Option<Vec<Section>>,
-    start_time: Instant,
-    start_date: chrono::DateTime<chrono::Local>,
-    running: bool,
-    bridge_error: bool,
+    /// Index of the game's first section in the combined run
+    first_section: usize,
 }
 
-impl RunApp {
-    fn handle_signal(app: &RwLock<Self>, sink: &Sink, sig: i32) -> Result<()> {
-        if sig != SIGUSR1 {
-            return Ok(());
-        }
+/// Base directory of all games, set once from `--data-dir` or the platform default
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
 
-        let app = &mut app.write().expect("RwLock not poisoned");
+fn data_dir() -> Result<&'static Path> {
+    if let Some(dir) = DATA_DIR.get() {
+        return Ok(dir);
+    }
+    let dirs = directories::ProjectDirs::from("", "", "speedy")
+        .ok_or(anyhow!("No home directory found"))?;
+    Ok(DATA_DIR.get_or_init(|| dirs.data_dir().to_owned()))
+}
 
-        if !app.running && app.current_sections.len() == 0 {
-            app.running = true;
-            app.start_time = Instant::now();
-            app.start_date = chrono::Local::now();
+/// Base directories of the logs and caches of all games. `--data-dir` sets them to the data
+/// directory, so everything stays in one place.
+static STATE_DIR: OnceLock<PathBuf> = OnceLock::new();
+static CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
 
-            let name = app.config.sections[0].clone();
-            app.current_sections.push(Section { name, time: 0 });
+/// Where logs go, which can be lost without losing any runs. Falls back to the data directory
+/// on platforms without a state directory.
+fn state_dir() -> Result<&'static Path> {
+    if let Some(dir) = STATE_DIR.get() {
+        return Ok(dir);
+    }
+    let dirs = directories::ProjectDirs::from("", "", "speedy")
+        .ok_or(anyhow!("No home directory found"))?;
+    let dir = dirs.state_dir().unwrap_or(dirs.data_dir()).to_owned();
+    Ok(STATE_DIR.get_or_init(|| dir))
+}
 
-            let source = SineWave::new(1.5 * 440.0)
-                .take_duration(Duration::from_secs_f32(0.1))
-                .amplify(0.20);
-            sink.append(source.clone());
+/// Where caches go, which are rebuilt when missing
+fn cache_dir() -> Result<&'static Path> {
+    if let Some(dir) = CACHE_DIR.get() {
+        return Ok(dir);
+    }
+    let dirs = directories::ProjectDirs::from("", "", "speedy")
+        .ok_or(anyhow!("No home directory found"))?;
+    Ok(CACHE_DIR.get_or_init(|| dirs.cache_dir().to_owned()))
+}
 
-            return Ok(());
-        }
+fn is_game_path(game: &str) -> bool {
+    game.contains('/') || game.contains(std::path::MAIN_SEPARATOR)
+}
 
-        if !app.running {
-            return Ok(());
-        }
+/// Directory of a game. A name is looked up in the data directory, anything with a path
+/// separator is taken as the game directory itself. Names that would point at the data
+/// directory or above it, like `..`, are refused, as are game directories that are links to
+/// somewhere else.
+fn game_dir(game: &str) -> Result<PathBuf> {
+    if is_game_path(game) {
+        return Ok(PathBuf::from(game));
+    }
+    ensure!(
+        !matches!(game, "" | "." | ".."),
+        "{:?} is not a game name, use ./{} for a game directory given by its path",
+        game,
+        game
+    );
+    let dir = data_dir()?.join(game);
+    ensure_inside(data_dir()?, &dir).with_context(|| {
+        format!(
+            "{} is not a game of the data directory, use ./{} for a game directory given by \
+             its path",
+            game, game
+        )
+    })?;
+    Ok(dir)
+}
 
-        app.update_current_time();
+/// Where a path really leads, with links and `..` resolved. The part of it that doesn't
+/// exist yet is taken as it is, but may not contain `..`.
+fn resolve_path(path: &Path) -> Result<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        let name = existing
+            .file_name()
+            .with_context(|| format!("{} can't be resolved", path.display()))?;
+        missing.push(name);
+        existing = existing.parent().unwrap_or(Path::new("."));
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", path.display()))?;
+    resolved.extend(missing.into_iter().rev());
+    Ok(resolved)
+}
 
-        let source = SineWave::new(440.0)
-            .take_duration(Duration::from_secs_f32(0.1))
-            .amplify(0.20);
-        sink.append(source.clone());
+/// Refuses a path that resolves to somewhere outside of `base`
+fn ensure_inside(base: &Path, path: &Path) -> Result<()> {
+    let (base, resolved) = (resolve_path(base)?, resolve_path(path)?);
+    ensure!(
+        resolved.starts_with(&base),
+        "{} leads to {}, outside of {}",
+        path.display(),
+        resolved.display(),
+        base.display()
+    );
+    Ok(())
+}
 
-        if app.current_sections.len() >= app.config.sections.len() {
-            app.running = false;
-            // Run finished
-            app.save()?;
+/// Checks a name that speedy creates a file or directory from. Only letters, digits, `.`,
+/// `_`, `+` and `-` are allowed and it can't start with a dot, so it never leaves the
+/// directory it is created in.
+fn validate_name(what: &str, name: &str) -> Result<()> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "._+-".contains(c);
+    ensure!(
+        !name.is_empty() && !name.starts_with('.') && name.chars().all(allowed),
+        "{:?} can't be used as a {} name, only letters, digits, '.', '_', '+' and '-' are \
+         allowed and it can't start with a dot",
+        name,
+        what
+    );
+    Ok(())
+}
 
-            let source = SineWave::new(0.5 * 440.0)
-                .take_duration(Duration::from_secs_f32(0.5))
-                .amplify(0.20);
-            sink.append(source.clone());
+/// Path of one of a game's logs in the state directory, or in the game directory if the game
+/// was given as a path. A log still in the game directory from older versions is moved over.
+fn log_path(game: &str, log: &str) -> Result<PathBuf> {
+    let old = game_dir(game)?.join(log);
+    if is_game_path(game) {
+        return Ok(old);
+    }
 
-            return Ok(());
+    let dir = state_dir()?.join(game);
+    let path = dir.join(log);
+    if path != old && old.exists() && !path.exists() {
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        // A rename can't cross file systems
+        if fs::rename(&old, &path).is_err() {
+            fs::copy(&old, &path).with_context(|| {
+                format!("Failed to move {} to {}", old.display(), dir.display())
+            })?;
+            fs::remove_file(&old)?;
         }
+    }
+    Ok(path)
+}
 
-        let name = app.config.sections[app.current_sections.len()].clone();
-        let time = app.start_time.elapsed().as_millis() as u32;
-        app.current_sections.push(Section { name, time });
+/// Path of one of a game's caches, like `log_path` but in the cache directory
+fn cache_path(game: &str, cache: &str) -> Result<PathBuf> {
+    if is_game_path(game) {
+        return Ok(game_dir(game)?.join(cache));
+    }
+    Ok(cache_dir()?.join(game).join(cache))
+}
 
-        Ok(())
+/// Opens a log of a game for appending, creating its directory if needed
+fn open_log(game: &str, log: &str) -> Result<File> {
+    let path = log_path(game, log)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
     }
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))
+}
 
-    fn spawn_signal_handler(app: Arc<RwLock<Self>>) -> Result<()> {
-        let mut signals = Signals::new(&[SIGUSR1])?;
-        let (stream, audio_stream_handle) = rodio::OutputStream::try_default()?;
-        let sink = Sink::try_new(&audio_stream_handle)?;
+/// Where the timer is between and during runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunState {
+    /// Waiting for a start signal
+    Idle,
+    /// A start arrived and waits out `confirm_start_ms` before the run begins
+    Armed,
+    Running,
+    /// The last section was split. A reset leads back to idle.
+    Finished {
+        pb: bool,
+    },
+}
 
-        // Keep stream alive forever
-        Box::leak(Box::new(stream));
+/// Something that happened during a session, kept for the debug overlay and `session.log`
+#[derive(Debug, Clone)]
+struct SessionEvent {
+    date: chrono::DateTime<chrono::Local>,
+    kind: EventKind,
+    /// Index of the section it happened in
+    section: usize,
+}
 
-        std::thread::spawn(move || {
-            for sig in signals.forever() {
-                Self::handle_signal(&app, &sink, sig)?;
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventKind {
+    Start,
+    Split,
+    Finish,
+    Reset,
+}
 
-            Ok::<_, anyhow::Error>(())
-        });
+/// How often the accessible mode looks for changes of the run
+const ACCESSIBLE_POLL: Duration = Duration::from_millis(50);
 
-        Ok(())
-    }
+/// Narrowest terminal the UI starts in, enough for the minimal view's section, time and delta
+const MINIMAL_WIDTH: u16 = 20;
 
-    fn spawn_bridge_handler(app: Arc<RwLock<Self>>) -> Result<Option<Child>> {
-        let script = app.read().unwrap().config.bridge_script.clone();
-        if let Some(script) = script {
-            let child = Command::new(script)
-                .stdout(std::io::stderr())
-                .spawn()
-                .unwrap();
-            return Ok(Some(child));
-        }
+/// Why the UI stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum UiExit {
+    Quit,
+    IdleTimeout,
+    /// SIGTERM or SIGINT
+    Shutdown,
+    /// Another game was picked with G, the session goes on with it
+    SwitchGame(String),
+}
+
+/// The state of the run the accessible mode last announced
+struct Announced {
+    state: RunState,
+    /// Sections split so far
+    split: usize,
+    toast: Option<Instant>,
+    error: Option<String>,
+    pending_golds: bool,
+}
 
-        Ok(None)
+impl Announced {
+    fn of(app: &RunApp) -> Self {
+        Self {
+            state: app.state,
+            split: match app.state {
+                RunState::Running => app.current_sections.len().saturating_sub(1),
+                RunState::Finished { .. } => app.current_sections.len(),
+                RunState::Idle | RunState::Armed => 0,
+            },
+            toast: app.toast.as_ref().map(|(at, _, _)| *at),
+            error: app.error.clone(),
+            pending_golds: app.pending_golds.is_some(),
+        }
     }
+}
 
-    fn launch_ui(app: &RwLock<Self>) -> Result<()> {
-        let size = terminal::size()?;
-        ensure!(size.0 >= 49);
-        ensure!(size.1 >= app.read().unwrap().config.sections.len() as u16 + 3);
-        let mut engine = ConsoleEngine::init(size.0 as u32, size.1 as u32, 10)?;
-        loop {
-            engine.wait_frame();
+/// What was done in one game of a session, printed when the session switched games
+#[derive(Debug, Clone)]
+struct SessionGame {
+    name: String,
+    finished: usize,
+    resets: usize,
+    /// Splits that arrived after the run was finished, e.g. from a bridge sending too many
+    spurious: usize,
+}
 
-            let app = &mut app.write().expect("RwLock not poisoned");
-            app.update_current_time();
+type Beep = Buffered<Amplify<TakeDuration<SineWave>>>;
 
-            if app.bridge_error {
-                bail!("Bridge error!");
-            }
+fn beep(frequency: f32, seconds: f32) -> Beep {
+    SineWave::new(frequency)
+        .take_duration(Duration::from_secs_f32(seconds))
+        .amplify(0.20)
+        .buffered()
+}
+type Clip = Buffered<Decoder<BufReader<File>>>;
 
-            engine.fill(pxl_bg(' ', BG));
-            engine.print_fbg(
-                0,
-                0,
-                &format!(" speedy: {}", app.config.full_game_name),
-                FG,
-                BG,
-            );
-            engine.print_fbg(
-                0,
-                1,
-                " section | best  | current       | section      ",
-                FG,
-                BG,
-            );
-            engine.print_fbg(
-                0,
-                2,
-                " --------|-------|---------------|--------------",
-                FG,
-                BG,
-            );
-            for (i, section_name) in app.config.sections.iter().enumerate() {
-                //01234567890123456789012345678901234567890123456
-                // section | best  | current       | section
-                // --------|-------|---------------|--------------
-                // name    | --:-- | --:-- (--:--) | --:-- (--:--)
-                let name_x = 1;
-                let best_x = 11;
-                let total_x = 19;
-                let deltat_x = 25;
-                let section_x = 35;
-                let deltas_x = 41;
-
-                let y = i as i32 + 3;
-
-                engine.print_fbg(name_x, y, &section_name, FG, BG);
-                engine.print_fbg(best_x - 2, y, "|", FG, BG);
-                engine.print_fbg(best_x, y, &app.pb_total_time(i), FG, BG);
-                engine.print_fbg(total_x - 2, y, "|", FG, BG);
-                app.current_total_time(i, &mut engine, total_x, y)?;
-                {
-                    app.delta_total_time(i, &mut engine, deltat_x, y)?;
-                }
-                engine.print_fbg(section_x - 2, y, "|", FG, BG);
-                app.current_section_time(i, &mut engine, section_x, y)?;
-                app.delta_section_time(i, &mut engine, deltas_x, y)?;
-            }
-            engine.draw();
+/// The steps of saving a finished run that are done, see [`RunApp::save`]
+#[derive(Debug, Clone, Default)]
+struct SaveProgress {
+    /// The dated run file, once it is written
+    run: Option<String>,
+    pb: bool,
+    golds: bool,
+    /// Of every game of a relay
+    legs: Vec<SaveProgress>,
+    done: bool,
+}
 
-            if engine.is_key_pressed(KeyCode::Char('q')) {
-                break; // exits app
-            }
-        }
+/// How long saving a finished run waits before trying again
+const SAVE_RETRY_DELAY: Duration = Duration::from_millis(500);
 
-        Ok(())
-    }
+/// Saving a run taking longer than this holds up the UI enough to suggest `async_save`
+const SLOW_SAVE: Duration = Duration::from_millis(250);
 
-    fn update_current_time(&mut self) {
-        if !self.running {
-            return;
-        }
+/// More queued sounds than this are skipped, so a flood of signals can't build a backlog
+const MAX_QUEUED_SOUNDS: usize = 3;
 
-        if self.current_sections.len() > self.config.sections.len() {
-            return;
-        }
+/// How long the audio device stays open after the last sound without `close_after`
+const DEFAULT_CLOSE_AFTER: Duration = Duration::from_secs(30);
 
-        self.current_sections.last_mut().unwrap().time =
-            self.start_time.elapsed().as_millis() as u32;
-    }
+type Sound = Box<dyn Source<Item = f32> + Send>;
 
-    fn current_total_time(
-        &self,
-        section: usize,
-        engine: &mut ConsoleEngine,
-        x: i32,
-        y: i32,
-    ) -> Result<()> {
-        if let Some(s) = self.current_sections.get(section) {
-            engine.print_fbg(x, y, &self.time_to_string(0, Some(s.time)), FG, BG);
-            return Ok(());
-        }
+/// Plays sounds on a thread of its own, which opens the default audio output with the first
+/// sound and closes it again once no sound was played for a while. The device stays free
+/// for other programs between runs, and if it can't be opened the sound is dropped.
+struct Player {
+    /// None when the game's sounds are silent
+    sender: Option<mpsc::Sender<Sound>>,
+}
 
-        if let Some(s) = &self.sum_of_best_sections {
-            engine.print_fbg(
-                x,
-                y,
-                &self.time_to_string(
-                    0,
-                    Some((s[section].time as i32 + self.loss_so_far()) as u32),
-                ),
-                GREY,
-                BG,
-            );
-            return Ok(());
-        }
+impl Player {
+    /// `warn` is told when the audio output can't be opened, once until it works again
+    fn spawn(close_after: Duration, warn: impl Fn(String) + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel::<Sound>();
+        std::thread::spawn(move || {
+            let mut output: Option<(rodio::OutputStream, Sink)> = None;
+            let mut failed = false;
+            loop {
+                let sound = match &output {
+                    Some(_) => match receiver.recv_timeout(close_after) {
+                        Ok(sound) => sound,
+                        Err(mpsc::RecvTimeoutError::Timeout) => {
+                            output = None;
+                            continue;
+                        }
+                        Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                    },
+                    None => match receiver.recv() {
+                        Ok(sound) => sound,
+                        Err(_) => return,
+                    },
+                };
+                if output.is_none() {
+                    let opened = rodio::OutputStream::try_default()
+                        .map_err(anyhow::Error::from)
+                        .and_then(|(stream, handle)| Ok((stream, Sink::try_new(&handle)?)));
+                    match opened {
+                        Ok(opened) => {
+                            output = Some(opened);
+                            failed = false;
+                        }
+                        Err(e) => {
+                            if !failed {
+                                warn(format!("No sound, the audio output failed: {}", e));
+                            }
+                            failed = true;
+                            continue;
+                        }
+                    }
+                }
+                if let Some((_, sink)) = &output {
+                    if sink.len() < MAX_QUEUED_SOUNDS {
+                        sink.append(sound);
+                    }
+                }
+            }
+        });
 
-        return Ok(());
+        Self {
+            sender: Some(sender),
+        }
     }
 
-    fn pb_total_time(&self, section: usize) -> String {
-        self.fixed_time_to_string(self.pb_sections.as_ref().map(|s| s[section].time))
+    fn silent() -> Self {
+        Self { sender: None }
     }
+}
 
-    fn current_section_time(
-        &self,
-        section: usize,
-        engine: &mut ConsoleEngine,
-        x: i32,
-        y: i32,
-    ) -> Result<()> {
-        let sob_section;
-        if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
-            if section == 0 {
-                sob_section = Some(sum_of_best_sections[section].time);
-            } else {
-                sob_section = Some(
-                    sum_of_best_sections[section].time - sum_of_best_sections[section - 1].time,
-                );
-            }
-        } else {
-            sob_section = None;
-        }
+/// The beeps and clips are built once and cloned for every signal
+struct Sounds {
+    /// The game of the clips
+    game: String,
+    player: Player,
+    start: Beep,
+    split: Beep,
+    finish: Beep,
+    /// A highlighted section begins
+    warning: Beep,
+    /// The configured clips by section index, decoded up front so splits never wait for it
+    clips: BTreeMap<(usize, SplitKind), Clip>,
+    delta_pitch: Option<DeltaPitch>,
+    /// The split beep at every pitch step, from `-PITCH_STEPS`, empty without a delta pitch
+    pitched: Vec<Beep>,
+    #[cfg(feature = "tts")]
+    speak_command: Option<String>,
+}
 
-        if let Some(c) = self.current_sections.get(section).map(|s| s.time) {
-            let last_time;
-            if section == 0 {
-                last_time = 0;
-            } else {
-                last_time = self.current_sections[section - 1].time
+impl Sounds {
+    fn new(config: &GameConfig, warn: impl Fn(String) + Send + 'static) -> Result<Self> {
+        let mut clips = BTreeMap::new();
+        for (name, section_clips) in &config.sounds.sections {
+            let section = config.sections.iter().position(|s| s == name);
+            let section = section.context("Sounds for a section that doesn't exist")?;
+            let kinds = [
+                (SplitKind::Gold, &section_clips.gold),
+                (SplitKind::Gaining, &section_clips.gaining),
+                (SplitKind::Losing, &section_clips.losing),
+            ];
+            for (kind, path) in kinds {
+                let Some(path) = path else {
+                    continue;
+                };
+                let file = File::open(path)
+                    .with_context(|| format!("Failed to open the sound {}", path.display()))?;
+                let clip = Decoder::new(BufReader::new(file))
+                    .with_context(|| format!("Failed to decode the sound {}", path.display()))?;
+                clips.insert((section, kind), clip.buffered());
             }
-            let time = c - last_time;
-            engine.print_fbg(
-                x,
-                y,
-                &self.time_to_string(section, Some(time)),
-                if section < self.current_sections.len() - 1 && Some(time) < sob_section {
-                    GOLD
-                } else {
-                    FG
-                },
-                BG,
-            );
-            return Ok(());
         }
 
-        if let Some(s) = sob_section {
-            engine.print_fbg(x, y, &self.time_to_string(0, Some(s)), GREY, BG);
-            return Ok(());
-        }
+        let player = if config.sounds.silent {
+            Player::silent()
+        } else {
+            let close_after = config.sounds.close_after.map(Duration::from_secs);
+            Player::spawn(close_after.unwrap_or(DEFAULT_CLOSE_AFTER), warn)
+        };
 
-        // Print nothing
-        Ok(())
+        Ok(Self {
+            game: config.directory_name.clone(),
+            player,
+            start: beep(1.5 * 440.0, 0.1),
+            split: beep(440.0, 0.1),
+            finish: beep(0.5 * 440.0, 0.5),
+            warning: beep(2.0 * 440.0, 0.05),
+            clips,
+            delta_pitch: config.sounds.delta_pitch,
+            pitched: config
+                .sounds
+                .delta_pitch
+                .map(|pitch| {
+                    (-PITCH_STEPS..=PITCH_STEPS)
+                        .map(|step| beep(pitch.frequency(step), 0.1))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            #[cfg(feature = "tts")]
+            speak_command: config.sounds.speak_command.clone(),
+        })
     }
 
-    fn last_loss(&self) -> i32 {
-        if self.current_sections.len() <= 1 {
-            return 0;
+    fn play<S>(&self, sound: &S)
+    where
+        S: Source + Clone + Send + 'static,
+        S::Item: rodio::Sample + Send,
+        f32: rodio::cpal::FromSample<S::Item>,
+    {
+        if let Some(sender) = &self.player.sender {
+            let _ = sender.send(Box::new(sound.clone().convert_samples()));
         }
+    }
 
-        let current = self.current_sections[self.current_sections.len() - 2].time;
-        if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
-            let sob = sum_of_best_sections[self.current_sections.len() - 2].time;
-
-            return current as i32 - sob as i32;
+    /// Plays the section's clip for how it went, or the split beep if it has none. The beep
+    /// is pitched by the delta with `[sounds.delta_pitch]`.
+    fn play_split(&self, section: usize, kind: Option<SplitKind>, delta: Option<i64>) {
+        if let Some(clip) = kind.and_then(|kind| self.clips.get(&(section, kind))) {
+            return self.play(clip);
         }
+        match self.delta_pitch.zip(delta) {
+            Some((pitch, delta)) => {
+                self.play(&self.pitched[(pitch.step(delta) + PITCH_STEPS) as usize])
+            }
+            None => self.play(&self.split),
+        }
+    }
 
-        return 0;
+    /// Has the speak command say something, without waiting for it
+    #[cfg(feature = "tts")]
+    fn speak(&self, text: String) {
+        let Some(command) = self.speak_command.clone() else {
+            return;
+        };
+        std::thread::spawn(move || {
+            let mut words = command.split_whitespace();
+            let Some(program) = words.next() else {
+                return;
+            };
+            let _ = std::process::Command::new(program)
+                .args(words)
+                .arg(text)
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status();
+        });
     }
 
-    fn loss_so_far(&self) -> i32 {
-        if self.current_sections.len() == 0 {
-            return 0;
-        }
+    #[cfg(not(feature = "tts"))]
+    fn speak(&self, _text: String) {}
+}
 
-        if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
-            let c = self.current_sections.last().unwrap().time;
-            let s_c = sum_of_best_sections[self.current_sections.len() - 1].time;
-            let last_loss = self.last_loss();
-            if c > (s_c as i32 + last_loss) as u32 {
-                return c as i32 - s_c as i32;
-            } else {
-                return last_loss;
-            }
-        }
-        0
+#[derive(Debug, Clone)]
+struct RunApp {
+    config: GameConfig,
+    settings: Settings,
+    current_sections: Vec<Section>,
+    pb_sections: Option<Vec<Section>>,
+    sum_of_best_sections: Option<Vec<Section>>,
+    average_sections: Option<Vec<Section>>,
+    best_exit_sections: Option<Vec<Section>>,
+    median_sections: Option<Vec<Section>>,
+    /// The saved runs, for the likely finish
+    history: Vec<HistoricalRun>,
+    start_time: Instant,
+    start_date: chrono::DateTime<chrono::Local>,
+    state: RunState,
+    bridge_error: bool,
+    /// Shown in the status line, e.g. when saving failed
+    error: Option<String>,
+    /// A bridge script was started for this run
+    bridge_active: bool,
+    /// The bridge process, stopped and restarted as `bridge_after_finish` says
+    bridge: Option<bridge::Status>,
+    /// Set by SIGTERM and SIGINT, the UI exits once it sees it
+    shutdown: bool,
+    /// How often runs were reset in each section, empty unless shown
+    reset_counts: Vec<usize>,
+    drift_samples: Vec<DriftSample>,
+    clock_jumps: Vec<ClockJump>,
+    checkpoints: Vec<Checkpoint>,
+    /// Wall clock time of every split of the run
+    split_dates: Vec<chrono::DateTime<chrono::Local>>,
+    /// The latest events of the session, older ones are moved to `session.log`
+    events: VecDeque<SessionEvent>,
+    /// The timer and the wall clock when the clock was last checked
+    last_clock_check: Option<(u32, chrono::DateTime<chrono::Local>)>,
+    /// Shown in the status line when nothing more important is, e.g. a new PB
+    notice: Option<String>,
+    /// Set when the bridge split on a trigger other than the expected one
+    trigger_warning: Option<String>,
+    /// Set when the triggers the bridge listed in its `capabilities` don't match the config
+    capabilities_warning: Option<String>,
+    /// Versions of pb.run and sum_of_best.run when they were last loaded
+    comparison_versions: [Option<String>; 2],
+    /// Shown in the status line for a few seconds, e.g. after the comparisons were reloaded
+    toast: Option<(Instant, String, Color)>,
+    /// Settings that get in each other's way, found before the run
+    settings_warnings: Vec<String>,
+    /// The PB this run replaced, to be compared against after the run
+    previous_pb: Option<Vec<Section>>,
+    /// Colors replacing the default palette
+    theme: Theme,
+    /// A run raced in `against` mode, whose position is shown below the table
+    ghost: Option<Vec<Section>>,
+    /// Final times shown below the table. Unlike the comparisons they change as soon as a
+    /// run is saved.
+    pb_total: Option<u32>,
+    sum_of_best_total: Option<u32>,
+    /// Don't let this run's golds into the sum of best
+    no_gold: bool,
+    /// Name of the dated run file once the run is saved
+    saved_run: Option<String>,
+    /// What saving the finished run has written so far
+    save_progress: SaveProgress,
+    /// Writes a finished run, [`Self::save`] unless a test makes saving fail
+    saver: fn(&mut Self) -> Result<()>,
+    /// A sum of best that improved suspiciously much and waits for confirmation
+    pending_golds: Option<Vec<Section>>,
+    /// Sections of the pending golds that beat their old gold by more than `gold_guard`
+    implausible_golds: Vec<usize>,
+    /// Keep golds without asking, for runs without the UI
+    accept_golds: bool,
+    /// Keys other than unlock and quit are ignored during a run
+    locked: bool,
+    /// A practice run, the only kind that pauses while the game isn't focused. It never
+    /// becomes the PB.
+    practice: bool,
+    /// Time into the run the game lost the focus at, while it's in the background
+    unfocused_at: Option<u32>,
+    /// Time into the run a practice run is paused at, and why
+    pause: Option<(u32, String)>,
+    /// The games making up the run if this is a relay
+    relay: Option<Vec<RelayLeg>>,
+    /// Replaces the system clock while a script plays the run
+    clock: Option<script::Clock>,
+    /// The command line settings, kept for the games switched to
+    overrides: SettingsOverrides,
+    /// Every game of the session so far, this one last
+    session: Vec<SessionGame>,
+    /// Comparisons changed by a section migration, their deltas are approximate
+    migrated: Vec<Comparison>,
+    /// Comparisons recorded under another route than the config's
+    other_route: Vec<Comparison>,
+    /// The next start comes from the bridge and is moved back by `start_trigger_offset_ms`
+    trigger_start: bool,
+    /// What the session is for, shown in the title and counted as the runs happen
+    goal: Option<goal::Goal>,
+    /// Announce the run as plain text instead of drawing the UI
+    accessible: Option<announce::Verbosity>,
+    /// Where past runs as far at the last split finished, found once per split
+    finish_range: Option<(u32, u32)>,
+    /// Wall clock time a race starts the run at, until it does or the run is started by hand
+    race_start: Option<chrono::DateTime<chrono::Local>>,
+    /// The game of the category the bridge asked for, switched to by the UI loop
+    category_switch: Option<String>,
+    /// The race start that is being signaled, the run counts from it
+    race_started: Option<chrono::DateTime<chrono::Local>>,
+    /// A copy of the finished run is being saved in the background
+    saving: bool,
+    /// `async_save` was suggested after a slow save, which happens once per session
+    slow_save_noted: bool,
+    /// What the history says about the last split, found once per split
+    pace: Option<stats::Pace>,
+}
+
+impl RunApp {
+    /// Applies a signal to the run. Without audio nothing is played and a finished run is
+    /// saved right away, as there is no UI the disk could hold up.
+    fn handle_signal(app_lock: &Arc<RwLock<Self>>, audio: Option<&Sounds>, sig: i32) -> Result<()> {
+        let app = &mut app_lock.write().expect("RwLock not poisoned");
+        Self::apply_signal(app_lock, app, audio, sig)
     }
 
-    fn delta_total_time(
-        &self,
-        section: usize,
-        engine: &mut ConsoleEngine,
-        x: i32,
-        y: i32,
+    /// [`Self::handle_signal`] with the lock already taken, by `app`
+    fn apply_signal(
+        app_lock: &Arc<RwLock<Self>>,
+        app: &mut Self,
+        audio: Option<&Sounds>,
+        sig: i32,
     ) -> Result<()> {
-        if let (Some(c), Some(pb_sections)) =
-            (self.current_sections.get(section), &self.pb_sections)
-        {
-            let p = &pb_sections[section];
-            let delta = c.time as i32 - p.time as i32;
+        if sig == SIGTERM || sig == SIGINT {
+            app.shutdown = true;
+            return Ok(());
+        }
+        if sig == SIGUSR2 {
+            return app.reset();
+        }
+        if sig != SIGUSR1 {
+            return Ok(());
+        }
 
-            if section == self.current_sections.len() - 1 {
-                if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
-                    let s_c = sum_of_best_sections[section].time;
-                    if c.time < (s_c as i32 + self.loss_so_far()) as u32 {
-                        engine.print_fbg(
-                            x,
-                            y,
-                            &("/".to_owned()
-                                + &self.time_to_string(
-                                    section,
-                                    Some((s_c as i32 + self.loss_so_far()) as u32),
-                                )),
-                            GREY,
-                            BG,
-                        );
-                        return Ok(());
-                    }
+        app.confirm_armed_start();
+        // Whoever splits is playing again
+        app.resume();
+        match app.state {
+            RunState::Idle => {
+                // Also the instant an armed start begins at once it's confirmed
+                (app.start_time, app.start_date) = app.start_instants();
+                if app.settings.confirm_start_ms > 0 {
+                    app.state = RunState::Armed;
+                } else {
+                    app.begin_run();
                 }
-            }
 
-            engine.print_fbg(
-                x,
-                y,
-                &self.delta_time_to_string(section, Some(delta)),
-                if delta < 0 { BLUE } else { RED },
-                BG,
-            );
+                if let Some(sounds) = audio {
+                    sounds.play(&sounds.start);
+                }
 
-            return Ok(());
+                return Ok(());
+            }
+            // Only a reset leaves these
+            RunState::Armed => return Ok(()),
+            RunState::Finished { .. } => {
+                if let Some(game) = app.session.last_mut() {
+                    game.spurious += 1;
+                }
+                return Ok(());
+            }
+            RunState::Running if app.split_restarts() => {
+                app.restart_run();
+                if let Some(sounds) = audio {
+                    sounds.play(&sounds.start);
+                }
+                return Ok(());
+            }
+            RunState::Running => {}
         }
 
-        // Print nothing
-        Ok(())
-    }
+        // The split instant becomes the end of the active section. Two splits in the same
+        // millisecond leave a section of 0ms, which is noted with the run and never a gold.
+        let now = app.elapsed();
+        app.current_sections.last_mut().unwrap().time = now;
+        let date = app.date();
+        app.split_dates.push(date);
 
-    fn delta_section_time(
-        &self,
-        section: usize,
-        engine: &mut ConsoleEngine,
-        x: i32,
-        y: i32,
-    ) -> Result<()> {
-        if self.current_sections.len() < section + 1 {
-            // Print nothing
-            return Ok(());
+        if let Some(sounds) = audio {
+            let section = app.current_sections.len() - 1;
+            let delta = app.split_delta(section);
+            sounds.play_split(section, app.split_kind(section), delta);
+            if let Some(text) = app.spoken_delta(section) {
+                sounds.speak(text);
+            }
         }
 
-        if let Some(pb_sections) = &self.pb_sections {
-            let pb_c = pb_sections[section].time;
-            let pb_l = if section == 0 {
-                0
-            } else {
-                pb_sections[section - 1].time
-            };
-            let c_c = self.current_sections[section].time;
-            let c_l = if section == 0 {
-                0
-            } else {
-                self.current_sections[section - 1].time
+        if app.current_sections.len() >= app.config.sections.len() {
+            app.record_event(EventKind::Finish);
+            app.write_live_log("finish");
+            app.take_drift_sample(now);
+            let pb = !app.practice
+                && app
+                    .pb_sections
+                    .as_ref()
+                    .and_then(|pb| pb.last())
+                    .is_none_or(|pb| now < pb.time);
+            app.state = RunState::Finished { pb };
+            app.track_goal();
+            if let Some(bridge) = &app.bridge {
+                if app.config.bridge_after_finish != bridge::AfterFinish::Keep {
+                    bridge.stop();
+                }
+            }
+            // Run finished
+            let Some(sounds) = audio else {
+                return (app.saver)(app);
             };
+            sounds.play(&sounds.finish);
 
-            let section_time = c_c - c_l;
-            let pb_section_time = pb_c - pb_l;
-            let delta = section_time as i32 - pb_section_time as i32;
-
-            if section == self.current_sections.len() - 1 {
-                if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
-                    let s_c = sum_of_best_sections[section].time;
-                    let s_l = if section == 0 {
-                        0
-                    } else {
-                        sum_of_best_sections[section - 1].time
-                    };
-
-                    let sum_of_best_time = s_c - s_l;
-                    if section_time < sum_of_best_time {
-                        engine.print_fbg(
-                            x,
-                            y,
-                            &("/".to_owned()
-                                + &self.time_to_string(section, Some(sum_of_best_time))),
-                            GREY,
-                            BG,
-                        );
-                        return Ok(());
+            // Saving waits for the lock to be released, so the disk can't hold up the signal
+            let app_lock = Arc::clone(app_lock);
+            if app.settings.async_save {
+                std::thread::spawn(move || Self::save_in_background(&app_lock));
+                return Ok(());
+            }
+            std::thread::spawn(move || {
+                for attempt in 0..2 {
+                    if attempt > 0 {
+                        std::thread::sleep(SAVE_RETRY_DELAY);
+                    }
+                    let app = &mut app_lock.write().expect("RwLock not poisoned");
+                    // A shutdown may have saved it already
+                    if app.saved_run.is_some() || app.try_save() {
+                        return;
                     }
                 }
-            }
-
-            engine.print_fbg(
-                x,
-                y,
-                &self.delta_time_to_string(section, Some(delta)),
-                if delta < 0 { BLUE } else { RED },
-                BG,
-            );
+            });
 
             return Ok(());
-        };
-
-        // Print nothing
-        Ok(())
-    }
+        }
 
-    fn time_to_string(&self, section: usize, time: Option<u32>) -> String {
-        if let Some(t) = time {
-            format!("{:>2}:{:02}", t / 60000, (t / 1000) % 60)
-        } else {
-            if section < self.current_sections.len() - 1 {
-                "--:--".to_owned()
-            } else {
-                "     ".to_owned()
+        app.record_event(EventKind::Split);
+        app.write_live_log("split");
+        let name = app.config.sections[app.current_sections.len()].clone();
+        if let Some(sounds) = audio {
+            if app.config.highlight_tone && app.config.highlight.contains(&name) {
+                sounds.play(&sounds.warning);
             }
         }
-    }
+        app.current_sections.push(Section { name, time: now });
+        app.finish_range = app.finish_range();
+        app.pace = app.pace();
+        app.track_goal();
 
-    fn fixed_time_to_string(&self, time: Option<u32>) -> String {
-        if let Some(t) = time {
-            format!("{:>2}:{:02}", t / 60000, (t / 1000) % 60)
-        } else {
-            "--:--".to_owned()
-        }
+        Ok(())
     }
 
-    fn delta_time_to_string(&self, section: usize, time: Option<i32>) -> String {
-        if let Some(t) = time {
-            if t < 0 {
-                let t = -t;
-                format!("(-{}:{:02})", t / 60000, (t / 1000) % 60)
-            } else {
-                format!("(+{}:{:02})", t / 60000, (t / 1000) % 60)
+    /// Builds the sounds of the session's game, which show playback errors as toasts
+    fn new_sounds(app: &Arc<RwLock<Self>>) -> Result<Sounds> {
+        let warn = {
+            let app = Arc::clone(app);
+            move |text| {
+                let app = &mut app.write().expect("RwLock not poisoned");
+                app.toast = Some((Instant::now(), text, RED));
             }
-        } else {
-            if section < self.current_sections.len() - 1 {
-                "(--:--)".to_owned()
-            } else {
-                "       ".to_owned()
+        };
+        Sounds::new(&app.read().unwrap().config, warn)
+    }
+
+    /// The sounds of the session's game, rebuilt after a switch to another game. The signal
+    /// handler and the bridge both lock them before the run, always in that order.
+    fn current_sounds<'a>(
+        app: &Arc<RwLock<Self>>,
+        sounds: &'a Mutex<Sounds>,
+    ) -> MutexGuard<'a, Sounds> {
+        let mut sounds = sounds.lock().expect("Mutex not poisoned");
+        let game = app.read().unwrap().config.directory_name.clone();
+        if sounds.game != game {
+            match Self::new_sounds(app) {
+                Ok(new) => *sounds = new,
+                Err(e) => {
+                    app.write().expect("RwLock not poisoned").error = Some(format!("{:#}", e));
+                    sounds.game = game;
+                }
             }
         }
+        sounds
     }
 
-    fn prepare_run(config: GameConfig) -> Result<Self> {
-        let sum_of_best = load_run(&config.directory_name, "sum_of_best.run")?;
+    fn spawn_signal_handler(app: Arc<RwLock<Self>>, sounds: Arc<Mutex<Sounds>>) -> Result<()> {
+        let mut signals = Signals::new([SIGUSR1, SIGUSR2, SIGTERM, SIGINT])?;
 
-        if let Some(sum_of_best) = &sum_of_best {
-            ensure!(config.sections.len() == sum_of_best.len());
-            for i in 0..config.sections.len() {
-                ensure!(config.sections[i] == sum_of_best[i].name);
+        std::thread::spawn(move || {
+            let mut last_interrupt: Option<Instant> = None;
+            for sig in signals.forever() {
+                let sounds = Self::current_sounds(&app, &sounds);
+                if sig == SIGINT {
+                    // A second interrupt skips saving, in case the graceful shutdown hangs
+                    if last_interrupt.is_some_and(|t| t.elapsed() < Duration::from_secs(1)) {
+                        let _ = terminal::disable_raw_mode();
+                        eprintln!("Interrupted twice, quitting without saving");
+                        std::process::exit(130);
+                    }
+                    last_interrupt = Some(Instant::now());
+                }
+                if let Err(e) = Self::handle_signal(&app, Some(&sounds), sig) {
+                    app.write().expect("RwLock not poisoned").error = Some(format!("{:#}", e));
+                }
             }
-        }
+        });
 
-        Ok(Self {
-            config,
-            current_sections: Vec::new(),
-            pb_sections: None,
-            sum_of_best_sections: sum_of_best,
-            start_time: Instant::now(),
-            start_date: chrono::Local::now(),
-            running: false,
-            bridge_error: false,
-        })
+        Ok(())
     }
 
-    fn set_pb(&mut self, pb: Vec<Section>) -> Result<()> {
-        ensure!(self.config.sections.len() == pb.len());
-        for i in 0..self.config.sections.len() {
-            ensure!(self.config.sections[i] == pb[i].name);
+    /// Asks the `focus_backend` of the current game's settings which window has the focus,
+    /// for a practice run. Other runs never pause, so nothing is asked for them.
+    fn spawn_focus_watcher(app_lock: Arc<RwLock<Self>>) {
+        if !app_lock.read().unwrap().practice {
+            return;
         }
 
-        self.pb_sections = Some(pb);
-
-        Ok(())
+        std::thread::spawn(move || loop {
+            std::thread::sleep(FOCUS_POLL_INTERVAL);
+            let (backend, pattern) = {
+                let app = app_lock.read().unwrap();
+                let pattern = app.settings.focus_window.as_ref();
+                let pattern = pattern.unwrap_or(&app.config.full_game_name).clone();
+                (app.settings.focus_backend, pattern)
+            };
+            let Some(mut source) = focus::source(backend) else {
+                continue;
+            };
+            let focused = source.focused_window();
+            let app = &mut app_lock.write().expect("RwLock not poisoned");
+            match focused {
+                Ok(window) => {
+                    let focused = window.is_some_and(|w| w.matches(&pattern));
+                    app.focus_changed(focused, source.name());
+                }
+                Err(e) => app.error = Some(format!("Checking the focus failed: {:#}", e)),
+            }
+        });
     }
 
-    fn save(&self) -> Result<()> {
-        let name = self.start_date.format("%Y-%m-%dT%H:%M:%S.run").to_string();
-        save_run(&self.config.directory_name, &name, &self.current_sections)?;
+    /// Starts the bridge script. Besides sending signals, it can print `start`, `split`,
+    /// `split <trigger>`, `checkpoint [name]`, `capabilities ...` and `category <name>`
+    /// lines, everything else it prints is passed on to stderr. Lines ending in a sequence
+    /// number like `split #42` are acknowledged and ignored when sent again.
+    fn spawn_bridge_handler(
+        app_lock: Arc<RwLock<Self>>,
+        sounds: Arc<Mutex<Sounds>>,
+    ) -> Result<Option<bridge::Status>> {
+        let config = app_lock.read().unwrap().config.clone();
+        let Some(script) = &config.bridge_script else {
+            return Ok(None);
+        };
+        let game = config.directory_name.clone();
 
-        let new_pb;
-        if let Some(pb) = &self.pb_sections {
-            ensure!(pb.len() == self.current_sections.len());
-            for i in 0..pb.len() {
-                ensure!(pb[i].name == self.current_sections[i].name);
+        let app = Arc::clone(&app_lock);
+        let status = bridge::run(&config, script, move |line| {
+            // Lines still buffered when the session switched games
+            if app.read().unwrap().config.directory_name != game {
+                return false;
+            }
+            let sounds = Self::current_sounds(&app, &sounds);
+            if let Err(e) = Self::bridge_message(&app, Some(&sounds), bridge::parse(line)) {
+                app.write().expect("RwLock not poisoned").error = Some(format!("{:#}", e));
             }
+            true
+        })?;
+        let mut app = app_lock.write().unwrap();
+        app.bridge_active = true;
+        app.bridge = Some(status.clone());
 
-            new_pb = self
-                .current_sections
-                .last()
-                .context("empty current run")?
-                .time
-                < pb.last().context("empty pb run")?.time;
-        } else {
-            new_pb = true;
+        Ok(Some(status))
+    }
+
+    /// Applies a line of the bridge to the run. Starts and splits are handled like signals,
+    /// under the same lock as the checks before them.
+    fn bridge_message(
+        app_lock: &Arc<RwLock<Self>>,
+        audio: Option<&Sounds>,
+        message: bridge::Message,
+    ) -> Result<()> {
+        match message {
+            bridge::Message::Start => Self::bridge_start(app_lock, audio),
+            bridge::Message::Split(trigger) => Self::bridge_split(app_lock, audio, trigger),
+            bridge::Message::Checkpoint(name) => {
+                app_lock
+                    .write()
+                    .expect("RwLock not poisoned")
+                    .checkpoint(name);
+                Ok(())
+            }
+            bridge::Message::Capabilities(triggers) => {
+                let app = &mut app_lock.write().expect("RwLock not poisoned");
+                if !app.config.triggers.is_empty() {
+                    app.capabilities_warning = bridge::check_capabilities(&app.config, &triggers);
+                }
+                Ok(())
+            }
+            bridge::Message::Category(name) => {
+                app_lock
+                    .write()
+                    .expect("RwLock not poisoned")
+                    .request_category(name);
+                Ok(())
+            }
+            bridge::Message::Other(line) => {
+                eprintln!("{}", line);
+                Ok(())
+            }
         }
+    }
 
-        if new_pb {
-            save_run(
-                &self.config.directory_name,
-                "pb.run",
-                &self.current_sections,
-            )?;
+    /// What happens every frame besides drawing, also without the UI
+    fn tick(&mut self) {
+        self.start_race();
+        self.confirm_armed_start();
+        if let Err(e) = self.watch_comparisons() {
+            self.error = Some(format!("Reloading the comparisons failed: {:#}", e));
+        }
+        let elapsed = self.elapsed();
+        let samples_due = elapsed / DRIFT_SAMPLE_INTERVAL;
+        if self.is_running() && self.drift_samples.len() < samples_due as usize {
+            self.take_drift_sample(elapsed);
+        }
+        if self.is_running() && self.pause.is_none() {
+            self.check_clock();
         }
+    }
 
-        let mut new_sob = Vec::new();
-        if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
-            let mut new_sum_of_best = 0;
-            for i in 0..self.current_sections.len() {
-                let mut section_time = self.current_sections[i].time;
-                let mut sob_time = sum_of_best_sections[i].time;
-                if i > 0 {
-                    section_time -= self.current_sections[i - 1].time;
-                    sob_time -= sum_of_best_sections[i - 1].time;
-                }
+    /// Runs without the UI for screen readers. A line of plain text is printed whenever the
+    /// run changes, and commands are read from `input`, the lines of stdin.
+    fn run_accessible(
+        app_lock: &RwLock<Self>,
+        verbosity: announce::Verbosity,
+        input: &mpsc::Receiver<String>,
+    ) -> Result<UiExit> {
+        let mut seen = {
+            let app = app_lock.read().expect("RwLock not poisoned");
+            let mut ready = format!("Ready, {}", app.config.full_game_name);
+            if let Some(category) = &app.config.category {
+                ready += &format!(" {}", category);
+            }
+            ready += &format!(", {} sections", app.config.sections.len());
+            if verbosity >= announce::Verbosity::Normal {
+                ready += &format!(", compared to the {}", app.active_comparison().name());
+            }
+            println!("{}", ready);
+            for warning in app
+                .capabilities_warning
+                .iter()
+                .chain(&app.settings_warnings)
+            {
+                println!("{}", warning);
+            }
+            if let Some(at) = app.race_start {
+                println!("The race starts at {}", at.format("%H:%M:%S"));
+            }
+            if verbosity >= announce::Verbosity::Full {
+                println!("{}", announce::HELP);
+            }
+            Announced::of(&app)
+        };
+        let mut last_activity = Instant::now();
+        loop {
+            std::thread::sleep(ACCESSIBLE_POLL);
+            let app = &mut app_lock.write().expect("RwLock not poisoned");
+            app.tick();
+            if app.bridge_error {
+                bail!("Bridge error!");
+            }
+            if app.shutdown {
+                return Ok(UiExit::Shutdown);
+            }
+            if let Some(game) = app.pending_category_switch() {
+                return Ok(UiExit::SwitchGame(game));
+            }
 
-                if sob_time < section_time {
-                    new_sum_of_best += sob_time;
-                } else {
-                    new_sum_of_best += section_time;
+            while let Ok(command) = input.try_recv() {
+                last_activity = Instant::now();
+                let locked = app.input_locked();
+                match command.as_str() {
+                    "t" => println!("{}", app.spoken_status()),
+                    "r" if !locked => app.reset()?,
+                    "k" if !locked => app.checkpoint(None),
+                    "s" if matches!(app.state, RunState::Finished { .. }) => {
+                        if app.saved_run.is_none() && !app.saving && app.try_save() {
+                            println!("Saved");
+                        }
+                    }
+                    "y" | "n" if app.pending_golds.is_some() => {
+                        app.resolve_pending_golds(command == "y")?;
+                        println!("Golds {}", if command == "y" { "kept" } else { "dropped" });
+                    }
+                    "q" if !locked => return Ok(UiExit::Quit),
+                    "r" | "k" | "q" => println!("Locked while the run is active"),
+                    "" => {}
+                    _ => println!("{}", announce::HELP),
                 }
-                new_sob.push(Section {
-                    name: self.current_sections[i].name.clone(),
-                    time: new_sum_of_best,
-                });
             }
-        } else {
-            new_sob = self.current_sections.clone();
-        }
-
-        save_run(&self.config.directory_name, "sum_of_best.run", &new_sob)?;
+            if app.is_running() {
+                last_activity = Instant::now();
+            }
+            let idle_timeout = Duration::from_secs(app.settings.idle_timeout as u64 * 60 * 60);
+            if app.settings.idle_timeout > 0 && last_activity.elapsed() >= idle_timeout {
+                return Ok(UiExit::IdleTimeout);
+            }
 
-        Ok(())
+            for line in app.announcements(&seen, verbosity) {
+                println!("{}", line);
+            }
+            seen = Announced::of(app);
+        }
     }
-}
-
-fn min_sec_mil_to_millis(min: u32, sec: u32, mil: u32) -> u32 {
-    (min * 60 + sec) * 1000 + mil
-}
+
+    /// What changed since the last announcement, as lines to print
+    fn announcements(&self, seen: &Announced, verbosity: announce::Verbosity) -> Vec<String> {
+        let mut lines = Vec::new();
+        let was_running = matches!(seen.state, RunState::Running | RunState::Finished { .. });
+        match self.state {
+            RunState::Idle if seen.state == RunState::Running => lines.push("Reset".to_owned()),
+            RunState::Idle if seen.state != RunState::Idle => {
+                lines.push("Ready for the next run".to_owned())
+            }
+            RunState::Armed if seen.state != RunState::Armed => {
+                lines.push("Start armed, r cancels it".to_owned())
+            }
+            _ => {}
+        }
+        if self.is_running() && !was_running {
+            let mut line = "Started".to_owned();
+            if verbosity >= announce::Verbosity::Normal {
+                line += &format!(", {}", self.config.sections[0]);
+            }
+            lines.push(line);
+        }
+
+        // Sections split since, the last one may have finished the run
+        let split = match self.state {
+            RunState::Running => self.current_sections.len().saturating_sub(1),
+            RunState::Finished { .. } => self.current_sections.len(),
+            RunState::Idle | RunState::Armed => 0,
+        };
+        let seen_split = if was_running { seen.split } else { 0 };
+        let segments = stats::segment_times(&self.current_sections);
+        for (section, &segment) in segments.iter().enumerate().take(split).skip(seen_split) {
+            if let RunState::Finished { pb } = self.state {
+                if section + 1 == self.config.sections.len() {
+                    lines.push(announce::finish(
+                        self.current_sections[section].time,
+                        pb,
+                        self.split_delta(section),
+                        self.golds_this_run(),
+                        verbosity,
+                    ));
+                    continue;
+                }
+            }
+            lines.push(announce::split(
+                &announce::Split {
+                    number: section + 1,
+                    name: &self.config.sections[section],
+                    time: self.current_sections[section].time,
+                    segment,
+                    delta: self.split_delta(section),
+                    gold: self.is_gold(section),
+                    next: self.config.sections.get(section + 1).map(String::as_str),
+                },
+                verbosity,
+            ));
+        }
+
+        if let Some((at, text, _)) = &self.toast {
+            if seen.toast != Some(*at) {
+                lines.push(text.clone());
+            }
+        }
+        if self.error.is_some() && self.error != seen.error {
+            lines.push(format!(
+                "Error: {}",
+                self.error.as_deref().unwrap_or_default()
+            ));
+        }
+        if self.pending_golds.is_some() && !seen.pending_golds {
+            let names = self
+                .implausible_golds
+                .iter()
+                .map(|&section| self.config.sections[section].as_str())
+                .collect::<Vec<_>>();
+            lines.push(format!(
+                "Unusually fast golds in {}, y keeps them, n drops them",
+                names.join(", ")
+            ));
+        }
+        lines
+    }
+
+    /// The active section and the time, for the `t` command of the accessible mode
+    fn spoken_status(&self) -> String {
+        match self.state {
+            RunState::Running => {
+                let section = self.current_sections.len() - 1;
+                let mut status = format!(
+                    "Section {}, {}, {}",
+                    section + 1,
+                    self.config.sections[section],
+                    announce::duration(self.elapsed())
+                );
+                if let Some((_, reason)) = &self.pause {
+                    status += &format!(", {}", reason);
+                }
+                status
+            }
+            RunState::Finished { .. } => {
+                let time = self.current_sections.last().map_or(0, |s| s.time);
+                format!("Finished in {}", announce::duration(time))
+            }
+            RunState::Idle | RunState::Armed => "Waiting for the start".to_owned(),
+        }
+    }
+
+    fn launch_ui(app: &RwLock<Self>) -> Result<UiExit> {
+        let size = terminal::size()?;
+        let (mut layout, mut minimal) = {
+            let app = app.read().unwrap();
+            let mut layout = app.fit_layout(size);
+            layout.comparison = app.active_comparison();
+            let minimal = !app.fits(&layout, size);
+            (layout, minimal)
+        };
+        ensure!(
+            size.0 >= MINIMAL_WIDTH && size.1 >= 1,
+            "The terminal needs to be at least {} columns wide",
+            MINIMAL_WIDTH
+        );
+        let mut engine = ConsoleEngine::init(size.0 as u32, size.1 as u32, 10)?;
+        // Quitting a locked run takes a second press of q
+        let mut quit_requested = false;
+        // Sections can be picked with the arrow keys while no run is active
+        let mut cursor: Option<usize> = None;
+        let mut detail: Option<Vec<String>> = None;
+        let mut show_debug = false;
+        let mut last_activity = Instant::now();
+        let mut comparison_switched_at: Option<Instant> = None;
+        // Games to switch to and the one under the cursor, while the picker is open
+        let mut picker: Option<(Vec<String>, usize)> = None;
+        // Why switching games was refused, shown for a few seconds
+        let mut switch_refused: Option<(Instant, &str)> = None;
+        loop {
+            engine.wait_frame();
+
+            // Drawing only reads, so splits don't have to wait for it
+            let app_lock = app;
+            let app = app_lock.read().expect("RwLock not poisoned");
+
+            if let Some((width, height)) = engine.get_resize() {
+                engine.resize(width as u32, height as u32);
+                let comparison = layout.comparison;
+                layout = app.fit_layout((width, height));
+                layout.comparison = comparison;
+                minimal = !app.fits(&layout, (width, height));
+            }
+
+            let any_key_pressed = [
+                KeyCode::Up,
+                KeyCode::Down,
+                KeyCode::Left,
+                KeyCode::Right,
+                KeyCode::Enter,
+                KeyCode::Esc,
+                KeyCode::Char('c'),
+                KeyCode::Char('k'),
+                KeyCode::Char('l'),
+                KeyCode::Char('y'),
+                KeyCode::Char('n'),
+                KeyCode::Char('q'),
+                KeyCode::Char('r'),
+                KeyCode::Char('s'),
+                KeyCode::Char('G'),
+            ]
+            .into_iter()
+            .any(|key| engine.is_key_pressed(key));
+            // Terminals report G with or without the shift modifier
+            let switch_pressed = engine.is_key_pressed(KeyCode::Char('G'))
+                || engine.is_key_pressed_with_modifier(
+                    KeyCode::Char('G'),
+                    KeyModifiers::SHIFT,
+                    KeyEventKind::Press,
+                );
+            let any_key_pressed = any_key_pressed || switch_pressed;
+            if app.is_running() || any_key_pressed {
+                last_activity = Instant::now();
+            }
+            let idle_timeout = Duration::from_secs(app.settings.idle_timeout as u64 * 60 * 60);
+            if app.settings.idle_timeout > 0 && last_activity.elapsed() >= idle_timeout {
+                return Ok(UiExit::IdleTimeout);
+            }
+
+            if app.bridge_error {
+                bail!("Bridge error!");
+            }
+            if app.shutdown {
+                return Ok(UiExit::Shutdown);
+            }
+
+            let comparison = app.active_comparison();
+            if comparison != layout.comparison {
+                layout.comparison = comparison;
+                comparison_switched_at = Some(Instant::now());
+            }
+
+            engine.fill(pxl_bg(' ', BG));
+            if minimal {
+                app.draw_minimal(&mut engine, &layout)?;
+            } else {
+                engine.print_fbg(
+                    0,
+                    0,
+                    &format!(" speedy: {}", app.config.full_game_name),
+                    FG,
+                    BG,
+                );
+                let mut x = app.config.full_game_name.chars().count() as i32 + 10;
+                if let Some(bridge) = &app.bridge {
+                    if bridge.state() != bridge::State::Running {
+                        let text = format!("(bridge {})", bridge_state_name(bridge.state()));
+                        engine.print_fbg(x, 0, &text, GREY, BG);
+                        x += text.chars().count() as i32 + 1;
+                    }
+                }
+                if let Some(goal) = &app.goal {
+                    let (text, color) = if goal.is_done() {
+                        (format!("* {} *", goal.status()), GOLD)
+                    } else {
+                        (format!("goal: {}", goal.status()), GREY)
+                    };
+                    // Leaves room for the golds of the run on the right
+                    let room = (layout.width as i32 - x - 10).max(0) as usize;
+                    let text: String = text.chars().take(room).collect();
+                    engine.print_fbg(x, 0, &text, color, BG);
+                }
+                if app.locked {
+                    engine.print_fbg(layout.width as i32 - 8, 0, "[locked]", GREY, BG);
+                }
+                if matches!(app.state, RunState::Running | RunState::Finished { .. }) {
+                    let golds = format!("golds: {}", app.golds_this_run());
+                    let right = layout.width as i32 - if app.locked { 9 } else { 0 };
+                    engine.print_fbg(right - golds.len() as i32, 0, &golds, GOLD, BG);
+                }
+                engine.print_fbg(0, 1, &layout.header(), FG, BG);
+                engine.print_fbg(0, 2, &layout.divider(), FG, BG);
+                for leg in app.relay.iter().flatten().skip(1) {
+                    let title = format!(" -- {} ", leg.config.full_game_name);
+                    let line = format!("{:-<width$}", title, width = layout.block_width as usize);
+                    let (x, y) = layout.place(app.table_row(leg.first_section) - 1);
+                    engine.print_fbg(x, y, &line, GREY, BG);
+                }
+                for (i, section_name) in app.config.sections.iter().enumerate() {
+                    // section | pb    | current       | section       | gold
+                    // --------|-------|---------------|---------------|-------
+                    // name    | --:-- | --:-- (--:--) | --:-- (--:--) | --:--
+                    let (block_x, y) = layout.place(app.table_row(i));
+                    // Deltas follow their time after a space
+                    let delta_offset = layout.time_width as i32 + 1;
+
+                    for (n, &(column, x)) in layout.columns.iter().enumerate() {
+                        let x = block_x + x;
+                        if n > 0 {
+                            engine.print_fbg(x - 2, y, "|", FG, BG);
+                        }
+                        match column {
+                            Column::Name if cursor == Some(i) && !app.is_running() => {
+                                engine.print_fbg(x, y, section_name, BG, FG)
+                            }
+                            Column::Name => {
+                                let color = if layout.highlighted[i] { PURPLE } else { FG };
+                                engine.print_fbg(x, y, section_name, color, BG);
+                                let resets = app.reset_counts.iter().sum::<usize>();
+                                if !app.is_running() && resets > 0 {
+                                    let percent = app.reset_counts[i] * 100 / resets;
+                                    let text = format!("{:>3}%", percent);
+                                    let right = x + layout.column_width(column) as i32;
+                                    engine.print_fbg(right - text.len() as i32, y, &text, GREY, BG);
+                                }
+                            }
+                            Column::Comparison => {
+                                engine.print_fbg(x, y, &app.comparison_total_time(i), FG, BG)
+                            }
+                            Column::Total => {
+                                app.current_total_time(i, &mut engine, x, y)?;
+                                let dual = layout.dual_delta;
+                                app.delta_total_time(i, &mut engine, x + delta_offset, y, dual)?;
+                            }
+                            Column::Segment => {
+                                app.current_section_time(i, &mut engine, x, y)?;
+                                app.delta_section_time(i, &mut engine, x + delta_offset, y)?;
+                                if let Some(met) = app.target_met(i) {
+                                    let x = x + layout.column_width(column) as i32;
+                                    let (mark, color) =
+                                        if met { ("✓", BLUE) } else { ("✗", RED) };
+                                    engine.print_fbg(x, y, mark, color, BG);
+                                }
+                            }
+                            Column::Gold => engine.print_fbg(
+                                x,
+                                y,
+                                &app.fixed_time_to_string(app.sob_section_time(i)),
+                                if app.is_active_section(i) { FG } else { GREY },
+                                BG,
+                            ),
+                            Column::Median => engine.print_fbg(
+                                x,
+                                y,
+                                &app.fixed_time_to_string(app.median_section_time(i)),
+                                if app.is_active_section(i) { FG } else { GREY },
+                                BG,
+                            ),
+                        }
+                    }
+                }
+                let references_y = layout.visible_rows(app.table_rows()) as i32 + 3;
+                for (row, line) in app.reference_lines().iter().enumerate() {
+                    engine.print_fbg(1, references_y + row as i32, line, GREY, BG);
+                }
+                let status_y = references_y + app.reference_lines().len() as i32;
+                if let Some(error) = &app.error {
+                    engine.print_fbg(1, status_y, error, RED, BG);
+                } else if let Some(warning) = app.clock_warning() {
+                    engine.print_fbg(1, status_y, &warning, RED, BG);
+                } else if let Some(warning) = &app.trigger_warning {
+                    engine.print_fbg(1, status_y, warning, RED, BG);
+                } else if app.state == RunState::Armed {
+                    let window = app.settings.confirm_start_ms;
+                    let left = window.saturating_sub(app.elapsed());
+                    engine.print_fbg(
+                        1,
+                        status_y,
+                        &format!("Starting in {:.1}s, esc to cancel", left as f64 / 1000.0),
+                        BLUE,
+                        BG,
+                    );
+                } else if quit_requested {
+                    engine.print_fbg(
+                        1,
+                        status_y,
+                        "The run is locked, press q again to quit or l to unlock",
+                        RED,
+                        BG,
+                    );
+                } else if let Some((_, reason)) =
+                    switch_refused.filter(|(t, _)| t.elapsed() < Duration::from_secs(3))
+                {
+                    engine.print_fbg(1, status_y, reason, RED, BG);
+                } else if comparison_switched_at
+                    .is_some_and(|t| t.elapsed() < Duration::from_secs(3))
+                {
+                    engine.print_fbg(
+                        1,
+                        status_y,
+                        &format!("Now comparing against {}", comparison.name()),
+                        BLUE,
+                        BG,
+                    );
+                } else if let Some((_, message, color)) = app
+                    .toast
+                    .as_ref()
+                    .filter(|(t, _, _)| t.elapsed() < Duration::from_secs(3))
+                {
+                    engine.print_fbg(1, status_y, message, *color, BG);
+                } else if let Some(new_sob) = &app.pending_golds {
+                    let improvement = app.sum_of_best_sections.as_ref().and_then(|s| s.last());
+                    let improvement = improvement.map_or(0, |old| {
+                        old.time as i32 - new_sob.last().map_or(0, |new| new.time as i32)
+                    });
+                    let question = if app.implausible_golds.is_empty() {
+                        format!(
+                            "Sum of best improved by {}, keep the golds? [y/n]",
+                            app.fixed_time_to_string(Some(improvement as u32))
+                                .trim_start()
+                        )
+                    } else {
+                        let names = app
+                            .implausible_golds
+                            .iter()
+                            .map(|&i| app.config.sections[i].as_str())
+                            .collect::<Vec<_>>();
+                        format!(
+                            "{} beat their golds by over {}s, keep the golds? [y/n]",
+                            names.join(", "),
+                            app.settings.gold_guard
+                        )
+                    };
+                    engine.print_fbg(1, status_y, &question, GOLD, BG);
+                } else if let Some((banner, color)) = app.state_banner() {
+                    engine.print_fbg(1, status_y, &banner, color, BG);
+                }
+                if app.settings.big_timer {
+                    let elapsed = app
+                        .current_sections
+                        .len()
+                        .checked_sub(1)
+                        .and_then(|i| app.section_end_time(i))
+                        .unwrap_or(0);
+                    let text = app.fixed_time_to_string(Some(elapsed));
+                    let color = if app.is_running() { FG } else { GREY };
+                    let top = status_y + 1;
+                    for (row, line) in layout::big_text(text.trim_start()).iter().enumerate() {
+                        engine.print_fbg(1, top + row as i32, line, color, BG);
+                    }
+                }
+            }
+            if let Some(lines) = &detail {
+                draw_overlay(&mut engine, 1, lines);
+            }
+            if let Some((games, selected)) = &picker {
+                let mut lines = vec![
+                    "Switch to which game? enter to switch, esc to stay".to_owned(),
+                    String::new(),
+                ];
+                for (i, game) in games.iter().enumerate() {
+                    let marker = if i == *selected { ">" } else { " " };
+                    let current = if *game == app.config.directory_name {
+                        " (current)"
+                    } else {
+                        ""
+                    };
+                    lines.push(format!("{} {}{}", marker, game, current));
+                }
+                draw_overlay(&mut engine, 1, &lines);
+            }
+            if show_debug {
+                draw_overlay(&mut engine, 1, &app.debug_lines());
+            }
+            if app.settings.color == ColorMode::Mono {
+                // The highlight of the cursor is lost without colors
+                if let Some(section) = cursor.filter(|_| !app.is_running() && !minimal) {
+                    let (x, y) = layout.place(app.table_row(section));
+                    engine.print(x, y, ">");
+                }
+            }
+            recolor(&mut engine, &app.theme, app.settings.color);
+            engine.draw();
+
+            let running = app.is_running();
+            drop(app);
+            let app = &mut app_lock.write().expect("RwLock not poisoned");
+
+            app.tick();
+            if let Some(game) = app.pending_category_switch() {
+                return Ok(UiExit::SwitchGame(game));
+            }
+            if app.state == RunState::Armed && engine.is_key_pressed(KeyCode::Esc) {
+                app.state = RunState::Idle;
+            }
+            if engine.is_key_pressed(KeyCode::F(12)) {
+                show_debug = !show_debug;
+            }
+            if engine.is_key_pressed(KeyCode::Char('k')) && !app.input_locked() {
+                app.checkpoint(None);
+            }
+            if engine.is_key_pressed(KeyCode::Char('r')) && !app.input_locked() {
+                app.reset()?;
+            }
+            let unsaved = matches!(app.state, RunState::Finished { .. })
+                && app.saved_run.is_none()
+                && !app.saving;
+            if engine.is_key_pressed(KeyCode::Char('s')) && unsaved {
+                app.try_save();
+            }
+
+            if running {
+                cursor = None;
+                detail = None;
+                picker = None;
+            } else if let Some((games, selected)) = &mut picker {
+                if engine.is_key_pressed(KeyCode::Down) {
+                    *selected = (*selected + 1).min(games.len() - 1);
+                } else if engine.is_key_pressed(KeyCode::Up) {
+                    *selected = selected.saturating_sub(1);
+                } else if engine.is_key_pressed(KeyCode::Esc) {
+                    picker = None;
+                } else if engine.is_key_pressed(KeyCode::Enter) {
+                    let game = games[*selected].clone();
+                    picker = None;
+                    if game != app.config.directory_name {
+                        return Ok(UiExit::SwitchGame(game));
+                    }
+                }
+            } else if detail.is_some() {
+                if engine.is_key_pressed(KeyCode::Enter) || engine.is_key_pressed(KeyCode::Esc) {
+                    detail = None;
+                }
+            } else if engine.is_key_pressed(KeyCode::Down) {
+                let last = app.config.sections.len() - 1;
+                cursor = Some(cursor.map_or(0, |c| (c + 1).min(last)));
+            } else if engine.is_key_pressed(KeyCode::Up) {
+                cursor = Some(cursor.map_or(0, |c| c.saturating_sub(1)));
+            } else if engine.is_key_pressed(KeyCode::Right) && layout.blocks > 1 {
+                // Same row in the next copy of the table
+                let last = app.config.sections.len() - 1;
+                cursor = Some(cursor.map_or(0, |c| (c + layout.block_rows).min(last)));
+            } else if engine.is_key_pressed(KeyCode::Left) && layout.blocks > 1 {
+                cursor = Some(cursor.map_or(0, |c| c.saturating_sub(layout.block_rows)));
+            } else if engine.is_key_pressed(KeyCode::Enter) {
+                if let Some(section) = cursor {
+                    detail = Some(app.section_detail(section)?);
+                }
+            }
+
+            let input_locked = app.input_locked();
+            if switch_pressed && picker.is_none() && !input_locked {
+                match app.switch_blocker() {
+                    Some(reason) => switch_refused = Some((Instant::now(), reason)),
+                    None => {
+                        let games = list_games()?;
+                        let selected = games
+                            .iter()
+                            .position(|game| *game == app.config.directory_name)
+                            .unwrap_or(0);
+                        if !games.is_empty() {
+                            picker = Some((games, selected));
+                        }
+                    }
+                }
+            }
+            if engine.is_key_pressed(KeyCode::Char('c')) && !input_locked {
+                app.settings.comparison_column = !app.settings.comparison_column;
+                let size = (engine.get_width() as u16, engine.get_height() as u16);
+                let mut toggled = app.fit_layout(size);
+                toggled.comparison = layout.comparison;
+                if app.fits(&toggled, size) {
+                    layout = toggled;
+                } else {
+                    // Too narrow to show the column again
+                    app.settings.comparison_column = !app.settings.comparison_column;
+                }
+            }
+            if engine.is_key_pressed(KeyCode::Char('l')) {
+                app.locked = !app.locked;
+                quit_requested = false;
+            }
+            if !input_locked {
+                quit_requested = false;
+            }
+
+            if app.pending_golds.is_some() && !input_locked {
+                if engine.is_key_pressed(KeyCode::Char('y')) {
+                    app.resolve_pending_golds(true)?;
+                } else if engine.is_key_pressed(KeyCode::Char('n')) {
+                    app.resolve_pending_golds(false)?;
+                }
+            }
+
+            if engine.is_key_pressed(KeyCode::Char('q')) {
+                if !input_locked || quit_requested {
+                    break; // exits app
+                }
+                quit_requested = true;
+            }
+        }
+
+        Ok(UiExit::Quit)
+    }
+
+    /// Lines of the detail pane of a section, with statistics from the run history
+    fn section_detail(&self, section: usize) -> Result<Vec<String>> {
+        let history = load_history(&self.config)?;
+        let time = |t: Option<u32>| self.fixed_time_to_string(t).trim_start().to_owned();
+        let pb_segment = self
+            .pb_sections
+            .as_ref()
+            .map(|pb| stats::segment_times(pb)[section]);
+
+        let mut lines = vec![
+            self.config.sections[section].clone(),
+            String::new(),
+            format!("gold     {}", time(self.sob_section_time(section))),
+            format!("pb       {}", time(pb_segment)),
+        ];
+        if let Some(section_stats) = stats::section_stats(&history, section, 20) {
+            let date = |run: &str| run.split('T').next().unwrap_or(run).to_owned();
+            lines.extend([
+                format!("mean     {}", time(Some(section_stats.mean))),
+                format!("median   {}", time(Some(section_stats.median))),
+                format!("recent   {}", stats::sparkline(&section_stats.recent)),
+                format!(
+                    "best     {} on {}",
+                    time(Some(section_stats.best.time)),
+                    date(&section_stats.best.run)
+                ),
+                format!(
+                    "worst    {} on {}",
+                    time(Some(section_stats.worst.time)),
+                    date(&section_stats.worst.run)
+                ),
+            ]);
+        } else {
+            lines.push("No history yet".to_owned());
+        }
+
+        Ok(lines)
+    }
+
+    /// Row of a section in the table. Every relay game after the first gets a separator row.
+    fn table_row(&self, section: usize) -> usize {
+        let separators = self.relay.iter().flatten().skip(1);
+        let separators = separators
+            .filter(|leg| leg.first_section <= section)
+            .count();
+        section + separators
+    }
+
+    /// The table layout for a terminal size, with the highlighted sections looked up once
+    fn fit_layout(&self, size: (u16, u16)) -> TableLayout {
+        let mut layout = self.fit_table(size);
+        layout.migrated = self.migrated.clone();
+        layout.other_route = self.other_route.clone();
+        layout.highlighted = self
+            .config
+            .sections
+            .iter()
+            .map(|section| self.config.highlight.contains(section))
+            .collect();
+        layout
+    }
+
+    /// The configured layout, or the sections spread over two or three copies of the table
+    /// side by side if they don't fit below each other but the terminal is wide enough, or
+    /// the compact layout if that fits. The configured one if none of them fit, the UI falls
+    /// back to the minimal view then.
+    fn fit_table(&self, (width, height): (u16, u16)) -> TableLayout {
+        let delta_width = self.delta_width();
+        let layout = TableLayout::new(
+            &self.settings,
+            self.config.sum_of_best_column,
+            self.config.median_column,
+        )
+        .with_delta_width(delta_width)
+        .with_dual_delta(self.config.dual_delta);
+        if self.fits(&layout, (width, height)) {
+            return layout;
+        }
+
+        let available = height.saturating_sub(self.ui_height(&layout) - self.table_rows() as u16);
+        let blocks = self.table_rows().div_ceil(available.max(1) as usize);
+        if available > 0 && (2..=3).contains(&blocks) {
+            let spread = TableLayout::multi_column(&self.settings, self.table_rows(), blocks)
+                .with_delta_width(delta_width);
+            if self.fits(&spread, (width, height)) {
+                return spread;
+            }
+        }
+
+        let compact_settings = Settings {
+            layout: settings::Layout::Compact,
+            ..self.settings.clone()
+        };
+        let compact = TableLayout::new(&compact_settings, false, false)
+            .with_delta_width(delta_width)
+            .with_dual_delta(self.config.dual_delta);
+        if self.fits(&compact, (width, height)) {
+            return compact;
+        }
+
+        layout
+    }
+
+    /// Width of the widest delta the loaded comparisons allow for. A delta is taken to be at
+    /// most as long as the longest comparison, but there is always room for one under a
+    /// minute and for the placeholder.
+    fn delta_width(&self) -> usize {
+        let longest = [
+            &self.pb_sections,
+            &self.sum_of_best_sections,
+            &self.average_sections,
+            &self.best_exit_sections,
+            &self.median_sections,
+        ]
+        .into_iter()
+        .filter_map(|comparison| Some(comparison.as_ref()?.last()?.time))
+        .max()
+        .unwrap_or(0);
+        [59_999, longest.min(i32::MAX as u32) as i32]
+            .map(|delta| self.delta_time_to_string(0, Some(delta)).len())
+            .into_iter()
+            // The placeholder of a skipped split
+            .chain([self.missing_time().len() + 2])
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn fits(&self, layout: &TableLayout, (width, height): (u16, u16)) -> bool {
+        layout.width <= width && self.ui_height(layout) <= height
+    }
+
+    /// Draws the active section with its time and delta, and the big timer below them if
+    /// there is room. Used when the terminal is too small for any table.
+    fn draw_minimal(&self, engine: &mut ConsoleEngine, layout: &TableLayout) -> Result<()> {
+        let section = self.current_sections.len().saturating_sub(1);
+        let name = &self.config.sections[section];
+        let time_x = 1 + name.chars().count() as i32 + 1;
+        engine.print_fbg(1, 0, name, FG, BG);
+        self.current_total_time(section, engine, time_x, 0)?;
+        let delta_x = time_x + layout.time_width as i32 + 1;
+        self.delta_total_time(section, engine, delta_x, 0, false)?;
+
+        let elapsed = self.section_end_time(section).unwrap_or(0);
+        let text = self.fixed_time_to_string(Some(elapsed));
+        let big = layout::big_text(text.trim_start());
+        let fits = big[0].chars().count() < engine.get_width() as usize;
+        if fits && engine.get_height() > big.len() as u32 {
+            let color = if self.is_running() { FG } else { GREY };
+            for (row, line) in big.iter().enumerate() {
+                engine.print_fbg(1, 1 + row as i32, line, color, BG);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Terminal rows needed for the UI with a layout
+    fn ui_height(&self, layout: &TableLayout) -> u16 {
+        // Title, header and divider above the sections, the reference lines and a status line
+        // below them
+        let references = self.reference_lines().len() as u16;
+        let mut height = layout.visible_rows(self.table_rows()) as u16 + 4 + references;
+        if self.settings.big_timer {
+            height += 5;
+        }
+        height
+    }
+
+    /// Number of rows the sections and relay separators take up
+    fn table_rows(&self) -> usize {
+        let separators = self.relay.as_ref().map_or(0, |legs| legs.len() - 1);
+        self.config.sections.len() + separators
+    }
+
+    fn is_running(&self) -> bool {
+        self.state == RunState::Running
+    }
+
+    /// Why the session can't switch to another game right now
+    fn switch_blocker(&self) -> Option<&'static str> {
+        if self.relay.is_some() || self.ghost.is_some() {
+            return Some("Only runs of a single game can switch games");
+        }
+        match self.state {
+            RunState::Armed | RunState::Running => {
+                Some("Finish or reset the run before switching games")
+            }
+            RunState::Finished { .. } if self.saving => Some("The run is still being saved"),
+            RunState::Finished { .. } if self.saved_run.is_none() => {
+                Some("Save the run with s before switching games")
+            }
+            _ if self.pending_golds.is_some() => {
+                Some("Keep or drop the golds before switching games")
+            }
+            _ => None,
+        }
+    }
+
+    /// The lock only applies while a run is active
+    fn input_locked(&self) -> bool {
+        self.locked && self.is_running()
+    }
+
+    fn is_active_section(&self, section: usize) -> bool {
+        self.is_running() && section + 1 == self.current_sections.len()
+    }
+
+    /// What the status line says when nothing more pressing is shown
+    fn state_banner(&self) -> Option<(String, Color)> {
+        match self.state {
+            RunState::Idle => {
+                let mut banner = match self.race_start {
+                    Some(at) => {
+                        let left = (at - self.date()).num_milliseconds().max(0) as u32;
+                        format!(
+                            "Race starts at {}, in {}",
+                            at.format("%H:%M:%S"),
+                            self.fixed_time_to_string(Some(left)).trim_start()
+                        )
+                    }
+                    None => "Ready, waiting for the start signal".to_owned(),
+                };
+                let offset = self.config.start_trigger_offset_ms;
+                if offset != 0 && self.config.bridge_script.is_some() {
+                    banner += &format!(
+                        ". Bridge starts count from {}ms {}",
+                        offset.abs(),
+                        if offset > 0 { "earlier" } else { "later" }
+                    );
+                }
+                if self.migrated.contains(&self.active_comparison()) {
+                    banner += &format!(
+                        ". The {} was migrated to new sections, its deltas are approximate",
+                        self.active_comparison().name()
+                    );
+                }
+                if self.other_route.contains(&self.active_comparison()) {
+                    banner += &format!(
+                        ". The {} was run on another route",
+                        self.active_comparison().name()
+                    );
+                }
+                let warnings: Vec<&str> = self
+                    .capabilities_warning
+                    .iter()
+                    .chain(&self.settings_warnings)
+                    .map(String::as_str)
+                    .collect();
+                if warnings.is_empty() {
+                    Some((banner, GREY))
+                } else {
+                    Some((format!("{}. {}", banner, warnings.join(". ")), RED))
+                }
+            }
+            RunState::Armed => None,
+            RunState::Running => {
+                let mut parts = Vec::new();
+                if let Some(checkpoint) = self.latest_checkpoint() {
+                    let section_start = self.current_sections.len().checked_sub(2);
+                    let section_start = section_start.map_or(0, |i| self.current_sections[i].time);
+                    let offset = self.fixed_time_to_string(Some(checkpoint.time - section_start));
+                    parts.push(format!("{} at +{}", checkpoint.name, offset.trim_start()));
+                }
+                if let Some(trigger) = self.expected_trigger() {
+                    parts.push(format!("Waiting for {}", trigger));
+                }
+                if let Some((_, reason)) = &self.pause {
+                    parts.insert(0, reason.clone());
+                    return Some((parts.join(", "), BLUE));
+                }
+                (!parts.is_empty()).then(|| (parts.join(", "), GREY))
+            }
+            RunState::Finished { pb } => {
+                let time = self.current_sections.last().map(|s| s.time);
+                let time = match self.golds_this_run() {
+                    0 => self.fixed_time_to_string(time).trim_start().to_owned(),
+                    1 => format!(
+                        "{} with a gold",
+                        self.fixed_time_to_string(time).trim_start()
+                    ),
+                    golds => format!(
+                        "{} with {} golds",
+                        self.fixed_time_to_string(time).trim_start(),
+                        golds
+                    ),
+                };
+                let banner = match (&self.notice, pb) {
+                    (Some(notice), true) => format!("Finished, {}, r for a new run", notice),
+                    (None, true) => {
+                        format!("Finished in {}, new PB, r for a new run", time)
+                    }
+                    (_, false) => format!("Finished in {}, r for a new run", time),
+                };
+                Some((banner, if pb { GOLD } else { FG }))
+            }
+        }
+    }
+
+    /// The trigger that ends the active section, if the config names triggers
+    fn expected_trigger(&self) -> Option<&str> {
+        if !self.is_running() {
+            return None;
+        }
+        let section = self.current_sections.len().checked_sub(1)?;
+        self.config.triggers.get(section).map(String::as_str)
+    }
+
+    /// Handles a named split from the bridge. A trigger other than the expected one means the
+    /// bridge and the splits are out of sync, which is shown and may drop the split.
+    fn bridge_split(
+        app_lock: &Arc<RwLock<Self>>,
+        audio: Option<&Sounds>,
+        trigger: Option<&str>,
+    ) -> Result<()> {
+        let app = &mut app_lock.write().expect("RwLock not poisoned");
+        // A split left over from an earlier run must not start a new one
+        if app.state == RunState::Idle && !app.config.auto_start {
+            app.trigger_warning = Some("Bridge split before the run started, ignored".into());
+            return Ok(());
+        }
+        match (trigger, app.expected_trigger()) {
+            (Some(trigger), Some(expected)) if trigger != expected => {
+                let ignored = app.config.ignore_mismatched_triggers;
+                app.trigger_warning = Some(bridge::mismatch(trigger, expected, ignored));
+                if app.config.ignore_mismatched_triggers {
+                    return Ok(());
+                }
+            }
+            (Some(_), Some(_)) => app.trigger_warning = None,
+            _ => {}
+        }
+
+        // Splits the same way as SIGUSR1, without letting go of the lock in between
+        app.trigger_start = app.state == RunState::Idle;
+        Self::apply_signal(app_lock, app, audio, SIGUSR1)
+    }
+
+    /// Asks the UI loop to switch to the game of another category, only before a run
+    fn request_category(&mut self, name: &str) {
+        let current = self.config.category.as_deref();
+        if current.is_some_and(|category| category.eq_ignore_ascii_case(name)) {
+            return;
+        }
+        if self.state != RunState::Idle {
+            let text = format!("The bridge asked for {} during the run, ignored", name);
+            self.toast = Some((Instant::now(), text, RED));
+            return;
+        }
+        match category_game(&self.config, name) {
+            Ok(Some(game)) => self.category_switch = Some(game),
+            Ok(None) => {
+                let text = format!(
+                    "The bridge asked for {}, but no game of {} has that category",
+                    name, self.config.full_game_name
+                );
+                self.toast = Some((Instant::now(), text, RED));
+            }
+            Err(e) => self.error = Some(format!("Looking up the category {}: {:#}", name, e)),
+        }
+    }
+
+    /// The game the bridge's category asks for, as long as no run started in the meantime.
+    /// It stays set for run_app to tell a switch by the bridge apart.
+    fn pending_category_switch(&mut self) -> Option<String> {
+        if self.category_switch.is_some() && self.state != RunState::Idle {
+            self.category_switch = None;
+            let text = "The run started before the category could be switched".to_owned();
+            self.toast = Some((Instant::now(), text, RED));
+        }
+        self.category_switch.clone()
+    }
+
+    /// Starts the run once the wall clock reaches the race start, timed from that instant.
+    /// A start by hand during the countdown cancels it.
+    fn start_race(&mut self) {
+        let Some(at) = self.race_start else {
+            return;
+        };
+        if self.state != RunState::Idle {
+            self.race_start = None;
+            let text = "Started by hand, the race start is cancelled".to_owned();
+            self.toast = Some((Instant::now(), text, BLUE));
+            return;
+        }
+        if self.date() >= at {
+            self.race_start = None;
+            self.race_started = Some(at);
+            let _ = signal_hook::low_level::raise(SIGUSR1);
+        }
+    }
+
+    /// Handles a start from the bridge, which unlike SIGUSR1 never splits an active run. It
+    /// only restarts one that just began.
+    fn bridge_start(app_lock: &Arc<RwLock<Self>>, audio: Option<&Sounds>) -> Result<()> {
+        let app = &mut app_lock.write().expect("RwLock not poisoned");
+        if app.in_restart_grace() {
+            app.trigger_start = true;
+            app.restart_run();
+            if let Some(sounds) = audio {
+                sounds.play(&sounds.start);
+            }
+            return Ok(());
+        }
+        if app.state != RunState::Idle {
+            return Ok(());
+        }
+        app.trigger_start = true;
+        Self::apply_signal(app_lock, app, audio, SIGUSR1)
+    }
+
+    /// Records a checkpoint in the active section, numbered if it has no name
+    fn checkpoint(&mut self, name: Option<&str>) {
+        if !self.is_running() {
+            return;
+        }
+        let section = self.current_sections.len() - 1;
+        let name = name.map_or_else(
+            || {
+                let n = self
+                    .checkpoints
+                    .iter()
+                    .filter(|c| c.section == section)
+                    .count();
+                format!("Checkpoint {}", n + 1)
+            },
+            str::to_owned,
+        );
+        let time = self.elapsed();
+        self.checkpoints.push(Checkpoint {
+            section,
+            name,
+            time,
+        });
+    }
+
+    /// The last checkpoint, if it was reached in the active section
+    fn latest_checkpoint(&self) -> Option<&Checkpoint> {
+        let section = self.current_sections.len().checked_sub(1)?;
+        self.checkpoints.last().filter(|c| c.section == section)
+    }
+
+    /// Whether a start now would restart the run, because it comes so soon after the start
+    /// that the start most likely fired twice
+    fn in_restart_grace(&self) -> bool {
+        self.is_running()
+            && self.current_sections.len() == 1
+            && self.elapsed() < self.settings.restart_grace_ms
+    }
+
+    /// Whether a split now would restart the run as well. A split this early may be real, so
+    /// it only restarts within half the gold of the first section, and never without a gold.
+    fn split_restarts(&self) -> bool {
+        let half_gold = self
+            .sum_of_best_sections
+            .as_ref()
+            .and_then(|sob| sob.first())
+            .map_or(0, |gold| gold.time / 2);
+        self.in_restart_grace() && self.elapsed() < half_gold
+    }
+
+    /// Starts the active run over from now, as the same attempt
+    fn restart_run(&mut self) {
+        (self.start_time, self.start_date) = self.start_instants();
+        self.current_sections[0].time = 0;
+        self.drift_samples.clear();
+        self.clock_jumps.clear();
+        self.checkpoints.clear();
+        self.last_clock_check = None;
+        self.unfocused_at = None;
+        self.pause = None;
+        self.toast = Some((Instant::now(), "Restarted".to_owned(), BLUE));
+        self.write_live_log("start");
+    }
+
+    /// When the run that starts now began. A start from the bridge fires late by the
+    /// calibrated offset, any other start is taken as it comes.
+    fn start_instants(&mut self) -> (Instant, chrono::DateTime<chrono::Local>) {
+        let (now, date) = (self.now(), self.date());
+        if let Some(at) = self.race_started.take() {
+            let late = (date - at).to_std().unwrap_or_default();
+            return (now.checked_sub(late).unwrap_or(now), at);
+        }
+        if !std::mem::take(&mut self.trigger_start) {
+            return (now, date);
+        }
+        let offset = self.config.start_trigger_offset_ms;
+        let latency = Duration::from_millis(offset.unsigned_abs());
+        let instant = if offset >= 0 {
+            now.checked_sub(latency).unwrap_or(now)
+        } else {
+            now + latency
+        };
+        (instant, date - chrono::Duration::milliseconds(offset))
+    }
+
+    fn begin_run(&mut self) {
+        self.state = RunState::Running;
+        self.trigger_warning = None;
+        if self.settings.lock_on_start {
+            self.locked = true;
+        }
+
+        let name = self.config.sections[0].clone();
+        self.current_sections.push(Section { name, time: 0 });
+        self.record_event(EventKind::Start);
+        self.write_live_log("start");
+        self.track_goal();
+    }
+
+    /// Counts the start or the last split of the run towards the session's goal, with a
+    /// toast when that reaches it
+    fn track_goal(&mut self) {
+        let split = match self.state {
+            RunState::Finished { .. } => self.current_sections.len().checked_sub(1),
+            _ => self.current_sections.len().checked_sub(2),
+        };
+        let gold = split.filter(|&section| self.is_gold(section));
+        let Some(goal) = &mut self.goal else {
+            return;
+        };
+        let mut done = false;
+        if let Some(section) = gold {
+            done |= goal.gold(&self.config.sections[section]);
+        }
+        match self.state {
+            RunState::Finished { pb } => done |= goal.finished(pb),
+            _ => {
+                if let Some(section) = self.current_sections.last() {
+                    done |= goal.reached(&section.name);
+                }
+            }
+        }
+        if done {
+            let text = format!("Goal reached: {}", goal.text);
+            self.toast = Some((Instant::now(), text, GOLD));
+        }
+    }
+
+    /// Adds a line about the active run to `current.log`, which every start empties. It is
+    /// written as the run happens, for tools that follow it and to find the splits of a run
+    /// that speedy didn't live to save.
+    fn write_live_log(&mut self, event: &str) {
+        let Some(section) = self.current_sections.last() else {
+            return;
+        };
+        let index = self.current_sections.len() - 1;
+        // The active section has no end yet when the run is abandoned
+        let time = if matches!(event, "reset" | "quit") {
+            self.elapsed()
+        } else {
+            section.time
+        };
+        let delta = matches!(event, "split" | "finish")
+            .then(|| self.split_delta(index))
+            .flatten()
+            .map_or_else(|| "-".to_owned(), format_offset);
+        let line = format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.date().format("%Y-%m-%dT%H:%M:%S%.3f"),
+            event,
+            section.name,
+            format_run_time(time),
+            delta
+        );
+        if let Err(e) = append_live_log(&self.config.directory_name, &line, event == "start") {
+            self.error = Some(format!("Writing current.log failed: {:#}", e));
+        }
+    }
+
+    /// Adds an event in the active section to the history, moving the oldest ones to
+    /// `session.log` once there are more than the `event_history` setting allows
+    fn record_event(&mut self, kind: EventKind) {
+        if let Some(game) = self.session.last_mut() {
+            match kind {
+                EventKind::Finish => game.finished += 1,
+                EventKind::Reset => game.resets += 1,
+                EventKind::Start | EventKind::Split => {}
+            }
+        }
+        self.events.push_back(SessionEvent {
+            date: self.date(),
+            kind,
+            section: self.current_sections.len().saturating_sub(1),
+        });
+        while self.events.len() > self.settings.event_history {
+            let Some(event) = self.events.pop_front() else {
+                break;
+            };
+            if let Err(e) = append_session_log(&self.config.directory_name, &event) {
+                self.error = Some(format!("Writing session.log failed: {:#}", e));
+            }
+        }
+    }
+
+    /// Lines of the debug overlay, to spot state that keeps growing in long sessions
+    fn debug_lines(&self) -> Vec<String> {
+        vec![
+            "Debug".to_owned(),
+            String::new(),
+            format!(
+                "Events buffered  {}/{}",
+                self.events.len(),
+                self.settings.event_history
+            ),
+            format!("Sections         {}", self.current_sections.len()),
+            format!("Drift samples    {}", self.drift_samples.len()),
+            format!("Clock jumps      {}", self.clock_jumps.len()),
+            format!("Reset counts     {}", self.reset_counts.len()),
+            format!(
+                "Bridge           {}",
+                self.bridge
+                    .as_ref()
+                    .map_or("none", |bridge| bridge_state_name(bridge.state()))
+            ),
+        ]
+    }
+
+    /// Begins an armed run once its confirmation window passed without a cancel.
+    /// The run counts from the original start signal, so no time is lost.
+    fn confirm_armed_start(&mut self) {
+        if self.state == RunState::Armed && self.elapsed() >= self.settings.confirm_start_ms {
+            self.begin_run();
+        }
+    }
+
+    /// Records the timer's elapsed time next to the wall clock's
+    fn take_drift_sample(&mut self, monotonic: u32) {
+        let wall_clock = (self.date() - self.start_date).num_milliseconds();
+        self.drift_samples.push(DriftSample {
+            monotonic,
+            wall_clock,
+        });
+    }
+
+    /// Notices when the wall clock moved differently than the timer since the last check
+    fn check_clock(&mut self) {
+        let elapsed = self.elapsed();
+        let now = self.date();
+        if let Some((last_elapsed, last_now)) = self.last_clock_check {
+            let wall_clock = (now - last_now).num_milliseconds();
+            let jump = wall_clock - (elapsed - last_elapsed) as i64;
+            if jump.abs() > CLOCK_JUMP_THRESHOLD {
+                self.clock_jumps.push(ClockJump { at: elapsed, jump });
+            }
+        }
+        self.last_clock_check = Some((elapsed, now));
+    }
+
+    /// Warns about the last clock change of the run
+    fn clock_warning(&self) -> Option<String> {
+        let last = self.clock_jumps.last()?;
+        let by = format_run_time(last.jump.unsigned_abs() as u32);
+        Some(if last.jump < 0 {
+            format!(
+                "The system clock went back by {}, run files may be out of order",
+                by
+            )
+        } else {
+            format!("The system clock jumped ahead by {}", by)
+        })
+    }
+
+    fn now(&self) -> Instant {
+        self.clock
+            .as_ref()
+            .map_or_else(Instant::now, script::Clock::instant)
+    }
+
+    fn date(&self) -> chrono::DateTime<chrono::Local> {
+        self.clock
+            .as_ref()
+            .map_or_else(chrono::Local::now, script::Clock::date)
+    }
+
+    /// Time since the run started, which stands still while a practice run is paused
+    fn elapsed(&self) -> u32 {
+        match &self.pause {
+            Some((at, _)) => *at,
+            None => self.unpaused_elapsed(),
+        }
+    }
+
+    fn unpaused_elapsed(&self) -> u32 {
+        self.now()
+            .saturating_duration_since(self.start_time)
+            .as_millis() as u32
+    }
+
+    /// Notes whether the game's window has the focus, as told by `source`. A practice run
+    /// is paused once the game was in the background for the `focus_pause_ms` setting,
+    /// from when it lost the focus but not before the last split. Other runs never pause.
+    fn focus_changed(&mut self, focused: bool, source: &str) {
+        if !self.practice || !self.is_running() || focused {
+            self.resume();
+            return;
+        }
+        let elapsed = self.unpaused_elapsed();
+        let since = *self.unfocused_at.get_or_insert(elapsed);
+        if self.pause.is_none() && elapsed - since >= self.settings.focus_pause_ms {
+            let last_split = self.current_sections.iter().rev().nth(1);
+            let at = since.max(last_split.map_or(0, |s| s.time));
+            let reason = format!("Paused, the game lost the focus ({})", source);
+            self.pause = Some((at, reason));
+        }
+    }
+
+    /// Continues a paused practice run, leaving out the time it was paused
+    fn resume(&mut self) {
+        self.unfocused_at = None;
+        let Some((at, _)) = self.pause.take() else {
+            return;
+        };
+        let paused = self.unpaused_elapsed().saturating_sub(at);
+        self.start_time += Duration::from_millis(paused as u64);
+        self.start_date += chrono::Duration::milliseconds(paused as i64);
+        // The wall clock went on without the timer
+        self.last_clock_check = None;
+    }
+
+    /// Cumulative time of a section. The active section's is computed live, the others are
+    /// the instants they were split at.
+    fn section_end_time(&self, section: usize) -> Option<u32> {
+        if self.is_active_section(section) {
+            Some(self.elapsed())
+        } else {
+            self.current_sections.get(section).map(|s| s.time)
+        }
+    }
+
+    fn current_total_time(
+        &self,
+        section: usize,
+        engine: &mut ConsoleEngine,
+        x: i32,
+        y: i32,
+    ) -> Result<()> {
+        if let Some(time) = self.section_end_time(section) {
+            engine.print_fbg(x, y, &self.time_to_string(0, Some(time)), FG, BG);
+            return Ok(());
+        }
+
+        if let Some(s) = &self.sum_of_best_sections {
+            engine.print_fbg(
+                x,
+                y,
+                &self.time_to_string(
+                    0,
+                    Some((s[section].time as i32 + self.loss_so_far()) as u32),
+                ),
+                GREY,
+                BG,
+            );
+            return Ok(());
+        }
+
+        Ok(())
+    }
+
+    /// The comparison for the current section, following the game's schedule if it has one
+    fn active_comparison(&self) -> Comparison {
+        let section = self.current_sections.len().saturating_sub(1);
+        self.config
+            .comparison_schedule
+            .iter()
+            .find(|entry| match &entry.until_section {
+                Some(name) => self.config.sections.iter().position(|s| s == name) >= Some(section),
+                None => true,
+            })
+            .map_or(self.settings.comparison, |entry| entry.compare)
+    }
+
+    /// The run selected by the active comparison
+    fn comparison_sections(&self) -> Option<&Vec<Section>> {
+        match self.active_comparison() {
+            Comparison::Pb => self.pb_sections.as_ref(),
+            Comparison::SumOfBest => self.sum_of_best_sections.as_ref(),
+            Comparison::Average => self.average_sections.as_ref(),
+            Comparison::BestExit => self.best_exit_sections.as_ref(),
+            Comparison::Median => self.median_sections.as_ref(),
+        }
+    }
+
+    fn comparison_total_time(&self, section: usize) -> String {
+        self.fixed_time_to_string(self.comparison_sections().map(|s| s[section].time))
+    }
+
+    fn current_section_time(
+        &self,
+        section: usize,
+        engine: &mut ConsoleEngine,
+        x: i32,
+        y: i32,
+    ) -> Result<()> {
+        let sob_section = self.sob_section_time(section);
+
+        if let Some(c) = self.section_end_time(section) {
+            let last_time = if section == 0 {
+                0
+            } else {
+                self.current_sections[section - 1].time
+            };
+            let time = c - last_time;
+            let gold = section < self.current_sections.len() - 1 && Some(time) < sob_section;
+            engine.print_fbg(
+                x,
+                y,
+                &self.time_to_string(section, Some(time)),
+                if gold { GOLD } else { FG },
+                BG,
+            );
+            if gold && self.settings.color == ColorMode::Mono {
+                // In the space between the time and its delta
+                let width = self.missing_time().len() as i32;
+                engine.print(x + width, y, "*");
+            }
+            return Ok(());
+        }
+
+        if let Some(s) = sob_section {
+            engine.print_fbg(x, y, &self.time_to_string(0, Some(s)), GREY, BG);
+            return Ok(());
+        }
+
+        // Print nothing
+        Ok(())
+    }
+
+    /// Whether a completed section beat its target. None while it runs or without a target.
+    fn target_met(&self, section: usize) -> Option<bool> {
+        let target = self.config.target(section)?;
+        if section >= self.current_sections.len() || self.is_active_section(section) {
+            return None;
+        }
+
+        let segment = stats::segment_times(&self.current_sections)[section];
+        Some(segment <= target)
+    }
+
+    /// The gold segment of a section, converted from the cumulative sum of best
+    fn sob_section_time(&self, section: usize) -> Option<u32> {
+        self.sum_of_best_sections
+            .as_ref()
+            .map(|sum_of_best_sections| {
+                if section == 0 {
+                    sum_of_best_sections[section].time
+                } else {
+                    sum_of_best_sections[section].time - sum_of_best_sections[section - 1].time
+                }
+            })
+    }
+
+    fn median_section_time(&self, section: usize) -> Option<u32> {
+        let median_sections = self.median_sections.as_ref()?;
+        Some(stats::segment_times(median_sections)[section])
+    }
+
+    fn last_loss(&self) -> i32 {
+        if self.current_sections.len() <= 1 {
+            return 0;
+        }
+
+        let current = self.current_sections[self.current_sections.len() - 2].time;
+        if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
+            let sob = sum_of_best_sections[self.current_sections.len() - 2].time;
+
+            return current as i32 - sob as i32;
+        }
+
+        0
+    }
+
+    /// The final time if the rest of the run matches the sum of best, like the predicted
+    /// times of the sections ahead
+    fn projected_time(&self) -> Option<u32> {
+        let sum_of_best = self.sum_of_best_sections.as_ref()?.last()?.time;
+        Some((sum_of_best as i32 + self.loss_so_far()) as u32)
+    }
+
+    /// The middle half of where past runs that were about as far at the last split finished,
+    /// None before the first split or with too few of them
+    fn finish_range(&self) -> Option<(u32, u32)> {
+        let section = self.current_sections.len().checked_sub(2)?;
+        let time = self.current_sections[section].time;
+        let tolerance =
+            ((time as f64 * FINISH_RANGE_TOLERANCE) as u32).max(FINISH_RANGE_MIN_TOLERANCE);
+        stats::finish_range(
+            self.config.sections.len(),
+            &self.history,
+            section,
+            time,
+            tolerance,
+        )
+    }
+
+    /// How the last split compares to the history, None before the first split or with the
+    /// `pace_notes` setting off
+    fn pace(&self) -> Option<stats::Pace> {
+        if !self.settings.pace_notes {
+            return None;
+        }
+        let section = self.current_sections.len().checked_sub(2)?;
+        stats::pace(
+            self.config.sections.len(),
+            &self.history,
+            self.pb_sections.as_deref(),
+            section,
+            self.current_sections[section].time,
+        )
+    }
+
+    /// The split section beat the sum of best from before the run
+    fn is_gold(&self, section: usize) -> bool {
+        let segment = stats::segment_times(&self.current_sections)[section];
+        self.sum_of_best_sections
+            .as_ref()
+            .is_some_and(|sob| stats::beats_gold(segment, stats::segment_times(sob)[section]))
+    }
+
+    /// Golds among the split sections of the run
+    fn golds_this_run(&self) -> usize {
+        let split = match self.state {
+            RunState::Running => self.current_sections.len() - 1,
+            RunState::Finished { .. } => self.current_sections.len(),
+            RunState::Idle | RunState::Armed => 0,
+        };
+        (0..split).filter(|&i| self.is_gold(i)).count()
+    }
+
+    /// How a split section went, None without a gold or comparison to tell
+    fn split_kind(&self, section: usize) -> Option<SplitKind> {
+        if self.is_gold(section) {
+            return Some(SplitKind::Gold);
+        }
+        let segment = stats::segment_times(&self.current_sections)[section];
+        let comparison = stats::segment_times(self.comparison_sections()?)[section];
+        Some(if segment <= comparison {
+            SplitKind::Gaining
+        } else {
+            SplitKind::Losing
+        })
+    }
+
+    /// How far ahead (negative) or behind a split section was at its split, in milliseconds
+    fn split_delta(&self, section: usize) -> Option<i64> {
+        let comparison = self.comparison_sections()?.get(section)?.time;
+        Some(self.current_sections.get(section)?.time as i64 - comparison as i64)
+    }
+
+    /// The delta of a split section as words, like "plus 3.2 seconds"
+    fn spoken_delta(&self, section: usize) -> Option<String> {
+        let delta = self.split_delta(section)?;
+        Some(format!(
+            "{} {:.1} seconds",
+            if delta <= 0 { "minus" } else { "plus" },
+            delta.unsigned_abs() as f64 / 1000.0
+        ))
+    }
+
+    /// Where the run likely finishes, the likely finish range or else the projection from
+    /// the sum of best as a range of one time
+    fn expected_finish(&self) -> Option<(u32, u32)> {
+        self.finish_range
+            .or_else(|| self.projected_time().map(|time| (time, time)))
+    }
+
+    /// Wall clock time of the expected finish, like "22:47" or "22:47–23:05", with "+1d"
+    /// when it is on a later day than now
+    fn eta(&self) -> Option<String> {
+        let (low, high) = self.expected_finish()?;
+        let clock = |time: u32| {
+            let eta = self.start_date + chrono::Duration::milliseconds(time as i64);
+            match (eta.date_naive() - self.date().date_naive()).num_days() {
+                0 => eta.format("%H:%M").to_string(),
+                days => format!("{} +{}d", eta.format("%H:%M"), days),
+            }
+        };
+        let (low, high) = (clock(low), clock(high));
+        Some(if low == high {
+            low
+        } else {
+            format!("{}–{}", low, high)
+        })
+    }
+
+    /// Lines pinned below the table, so a screenshot of it explains itself
+    fn reference_lines(&self) -> Vec<String> {
+        let time = |time: Option<u32>| self.fixed_time_to_string(time).trim_start().to_owned();
+        let mut pb = format!("Personal Best — {}", time(self.pb_total));
+        if self.is_running() {
+            match self.finish_range {
+                Some((low, high)) => {
+                    pb += &format!(
+                        "   Likely finish — {}–{}",
+                        time(Some(low)),
+                        time(Some(high))
+                    )
+                }
+                None => pb += &format!("   Projected — {}", time(self.projected_time())),
+            }
+            if let Some(eta) = self.eta() {
+                pb += &format!("   ETA {}", eta);
+            }
+        }
+        let mut sum_of_best = format!("Sum of Best — {}", time(self.sum_of_best_total));
+        match self.pace.filter(|_| self.is_running()) {
+            Some(stats::Pace::BestEver) => sum_of_best += "   Best pace ever",
+            Some(stats::Pace::PbUnrecoverable) => {
+                sum_of_best += "   PB historically unrecoverable from here"
+            }
+            None => {}
+        }
+        let mut lines = vec![pb, sum_of_best];
+        if let Some(ghost) = &self.ghost {
+            lines.push(self.ghost_line(ghost));
+        }
+        lines
+    }
+
+    /// Where the ghost is at the current time, interpolated between its splits, and how far
+    /// ahead or behind its pace this run is
+    fn ghost_line(&self, ghost: &[Section]) -> String {
+        if !self.is_running() {
+            return "Ghost — waiting for the start".to_owned();
+        }
+        let now = self.elapsed();
+        let ghost_position = match ghost.iter().position(|s| s.time > now) {
+            Some(g) => {
+                let start = g.checked_sub(1).map_or(0, |i| ghost[i].time);
+                let percent = (now - start) as u64 * 100 / (ghost[g].time - start).max(1) as u64;
+                format!("{} ({}%)", ghost[g].name, percent)
+            }
+            None => "finished".to_owned(),
+        };
+
+        // Behind by at least the time since the ghost left the active section, otherwise
+        // the difference at the last split still holds
+        let active = self.current_sections.len() - 1;
+        let at_last_split = active.checked_sub(1).and_then(|i| {
+            let ghost_split = ghost.get(i)?.time;
+            Some(self.current_sections[i].time as i64 - ghost_split as i64)
+        });
+        let since_ghost_left = ghost.get(active).map(|s| now as i64 - s.time as i64);
+        let delta = match (at_last_split.unwrap_or(0), since_ghost_left) {
+            (split, Some(left)) => split.max(left),
+            (split, None) => split,
+        };
+        let pace = match delta {
+            0 => "even".to_owned(),
+            d if d < 0 => format!("{} ahead", format_run_time(d.unsigned_abs() as u32)),
+            d => format!("{} behind", format_run_time(d as u32)),
+        };
+
+        format!("Ghost — {}, {}", ghost_position, pace)
+    }
+
+    fn loss_so_far(&self) -> i32 {
+        if self.current_sections.is_empty() {
+            return 0;
+        }
+
+        if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
+            let c = self
+                .section_end_time(self.current_sections.len() - 1)
+                .unwrap();
+            let s_c = sum_of_best_sections[self.current_sections.len() - 1].time;
+            let last_loss = self.last_loss();
+            if c > (s_c as i32 + last_loss) as u32 {
+                return c as i32 - s_c as i32;
+            } else {
+                return last_loss;
+            }
+        }
+        0
+    }
+
+    /// The delta of a section's total time to the comparison, and with `dual` also the one
+    /// to the sum of best once the section is split, like "(+0:03 | -0:01)"
+    fn delta_total_time(
+        &self,
+        section: usize,
+        engine: &mut ConsoleEngine,
+        x: i32,
+        y: i32,
+        dual: bool,
+    ) -> Result<()> {
+        if let (Some(c), Some(pb_sections)) =
+            (self.section_end_time(section), self.comparison_sections())
+        {
+            let p = &pb_sections[section];
+            let delta = c as i32 - p.time as i32;
+
+            if section == self.current_sections.len() - 1 {
+                if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
+                    let s_c = sum_of_best_sections[section].time;
+                    if c < (s_c as i32 + self.loss_so_far()) as u32 {
+                        engine.print_fbg(
+                            x,
+                            y,
+                            &("/".to_owned()
+                                + &self.time_to_string(
+                                    section,
+                                    Some((s_c as i32 + self.loss_so_far()) as u32),
+                                )),
+                            GREY,
+                            BG,
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+
+            let color = if delta < 0 { BLUE } else { RED };
+            let text = self.delta_time_to_string(section, Some(delta));
+            let split = !self.is_active_section(section);
+            let sob = self.sum_of_best_sections.as_ref().filter(|_| dual && split);
+            let Some(sob) = sob else {
+                engine.print_fbg(x, y, &text, color, BG);
+                return Ok(());
+            };
+
+            // Both inside one pair of parentheses
+            let sob_delta = c as i32 - sob[section].time as i32;
+            let sob_text = self.delta_time_to_string(section, Some(sob_delta));
+            let first = format!("{} | ", text.trim_end_matches(')'));
+            engine.print_fbg(x, y, &first, color, BG);
+            engine.print_fbg(
+                x + first.len() as i32,
+                y,
+                sob_text.trim_start_matches('('),
+                if sob_delta < 0 { GOLD } else { GREY },
+                BG,
+            );
+
+            return Ok(());
+        }
+
+        // Print nothing
+        Ok(())
+    }
+
+    fn delta_section_time(
+        &self,
+        section: usize,
+        engine: &mut ConsoleEngine,
+        x: i32,
+        y: i32,
+    ) -> Result<()> {
+        if self.current_sections.len() < section + 1 {
+            // Print nothing
+            return Ok(());
+        }
+
+        if let Some(pb_sections) = self.comparison_sections() {
+            let pb_c = pb_sections[section].time;
+            let pb_l = if section == 0 {
+                0
+            } else {
+                pb_sections[section - 1].time
+            };
+            let c_c = self.section_end_time(section).unwrap();
+            let c_l = if section == 0 {
+                0
+            } else {
+                self.current_sections[section - 1].time
+            };
+
+            let section_time = c_c - c_l;
+            let pb_section_time = pb_c - pb_l;
+            let delta = section_time as i32 - pb_section_time as i32;
+
+            if self.is_active_section(section) {
+                if let Some(sum_of_best_sections) = &self.sum_of_best_sections {
+                    let s_c = sum_of_best_sections[section].time;
+                    let s_l = if section == 0 {
+                        0
+                    } else {
+                        sum_of_best_sections[section - 1].time
+                    };
+
+                    let sum_of_best_time = s_c - s_l;
+                    if section_time < sum_of_best_time {
+                        engine.print_fbg(
+                            x,
+                            y,
+                            &("/".to_owned()
+                                + &self.time_to_string(section, Some(sum_of_best_time))),
+                            GREY,
+                            BG,
+                        );
+                        return Ok(());
+                    }
+                }
+
+                // The live delta only appears once time is actually being lost
+                if delta <= 0 {
+                    return Ok(());
+                }
+            }
+
+            engine.print_fbg(
+                x,
+                y,
+                &self.delta_time_to_string(section, Some(delta)),
+                if delta < 0 { BLUE } else { RED },
+                BG,
+            );
+
+            return Ok(());
+        };
+
+        // Print nothing
+        Ok(())
+    }
+
+    /// Decimal places of a time, as many as the precision setting asks for
+    fn fraction_to_string(&self, t: u32) -> String {
+        let precision = self.settings.precision as u32;
+        if precision == 0 {
+            return String::new();
+        }
+
+        format!(
+            ".{:0width$}",
+            (t % 1000) / 10u32.pow(3 - precision),
+            width = precision as usize
+        )
+    }
+
+    /// Placeholder for a time that isn't known, as wide as a real one
+    fn missing_time(&self) -> String {
+        let precision = self.settings.precision as usize;
+        if precision == 0 {
+            "--:--".to_owned()
+        } else {
+            format!("--:--.{}", "-".repeat(precision))
+        }
+    }
+
+    fn time_to_string(&self, section: usize, time: Option<u32>) -> String {
+        if let Some(t) = time {
+            format!(
+                "{:>2}:{:02}{}",
+                t / 60000,
+                (t / 1000) % 60,
+                self.fraction_to_string(t)
+            )
+        } else {
+            if section < self.current_sections.len() - 1 {
+                self.missing_time()
+            } else {
+                " ".repeat(self.missing_time().len())
+            }
+        }
+    }
+
+    fn fixed_time_to_string(&self, time: Option<u32>) -> String {
+        if let Some(t) = time {
+            format!(
+                "{:>2}:{:02}{}",
+                t / 60000,
+                (t / 1000) % 60,
+                self.fraction_to_string(t)
+            )
+        } else {
+            self.missing_time()
+        }
+    }
+
+    /// A delta like "(+3.41s)" under a minute, "(+1:23)" under an hour and "(+1:02:03)"
+    /// above, or always like "(+1:23.41)" with the `fixed_deltas` setting
+    fn delta_time_to_string(&self, section: usize, time: Option<i32>) -> String {
+        if let Some(t) = time {
+            let sign = if t < 0 { '-' } else { '+' };
+            let t = t.unsigned_abs();
+            if self.settings.fixed_deltas {
+                format!(
+                    "({}{}:{:02}{})",
+                    sign,
+                    t / 60000,
+                    (t / 1000) % 60,
+                    self.fraction_to_string(t)
+                )
+            } else if t < 60_000 {
+                format!("({}{}{}s)", sign, t / 1000, self.fraction_to_string(t))
+            } else if t < 3_600_000 {
+                format!("({}{}:{:02})", sign, t / 60000, (t / 1000) % 60)
+            } else {
+                format!(
+                    "({}{}:{:02}:{:02})",
+                    sign,
+                    t / 3_600_000,
+                    (t / 60000) % 60,
+                    (t / 1000) % 60
+                )
+            }
+        } else {
+            if section < self.current_sections.len() - 1 {
+                format!("({})", self.missing_time())
+            } else {
+                " ".repeat(self.missing_time().len() + 2)
+            }
+        }
+    }
+
+    /// Loads the comparisons and resolves the effective settings for a run.
+    ///
+    /// Settings come from the settings file, then the game config, then `cli`.
+    fn prepare_run(config: GameConfig, cli: &SettingsOverrides) -> Result<Self> {
+        let global = settings::load_global()?;
+        let settings = Settings::resolve(&[&global, &config.settings, cli])?;
+
+        for entry in &config.comparison_schedule {
+            if let Some(name) = &entry.until_section {
+                ensure!(
+                    config.sections.contains(name),
+                    "The comparison schedule names {:?}, which is not a section",
+                    name
+                );
+            }
+        }
+
+        let theme = match &settings.theme {
+            Some(path) => Theme::load(path)?.0,
+            None => Theme::default(),
+        };
+        let (errors, mut settings_warnings) = theme.conflicts();
+        if !errors.is_empty() {
+            bail!("The theme doesn't work: {}", errors.join(", "));
+        }
+        settings_warnings.extend(config.warnings.iter().cloned());
+        if let Some(path) = interrupted_live_log(&config.directory_name)? {
+            settings_warnings.push(format!(
+                "The last run stopped without being saved, its splits are in {}",
+                path.display()
+            ));
+        }
+
+        let mut other_route = other_route_comparisons(&config)?;
+        if !other_route.is_empty() && archive_route(&config, &other_route)? {
+            other_route.clear();
+        }
+        let other_route = other_route
+            .into_iter()
+            .map(|(comparison, _, _)| comparison)
+            .collect();
+
+        let sum_of_best = load_comparison(&config, "sum_of_best.run")?;
+        let sum_of_best_total = sum_of_best.as_ref().and_then(|s| s.last()).map(|s| s.time);
+
+        let history = load_history(&config)?;
+        let mut average_sections = None;
+        let mut best_exit_sections = None;
+        let mut median_compared = false;
+        let comparisons = config
+            .comparison_schedule
+            .iter()
+            .map(|entry| entry.compare)
+            .chain([settings.comparison]);
+        for comparison in comparisons {
+            match comparison {
+                Comparison::Average => average_sections = average(&load_complete_runs(&config)?),
+                Comparison::BestExit => {
+                    best_exit_sections = stats::best_exits(config.sections.len(), &history)
+                        .into_iter()
+                        .zip(&config.sections)
+                        .map(|(exit, name)| {
+                            exit.map(|exit| Section {
+                                name: name.clone(),
+                                time: exit.time,
+                            })
+                        })
+                        .collect();
+                }
+                Comparison::Median => median_compared = true,
+                Comparison::Pb | Comparison::SumOfBest => {}
+            }
+        }
+        let mut median_sections = None;
+        if median_compared || config.median_column {
+            median_sections = median_run(&config, &history)?;
+        }
+
+        let migrated = migrated_comparisons(&config.directory_name)?
+            .into_iter()
+            .map(|(comparison, _, _)| comparison)
+            .collect();
+        let comparison_versions = comparison_versions(&config.directory_name)?;
+        let session = vec![SessionGame {
+            name: config.full_game_name.clone(),
+            finished: 0,
+            resets: 0,
+            spurious: 0,
+        }];
+
+        let mut reset_counts = Vec::new();
+        if settings.reset_percentages {
+            reset_counts = vec![0; config.sections.len()];
+            for reset in load_resets(&config.directory_name)? {
+                if let Some(count) = reset_counts.get_mut(reset.section) {
+                    *count += 1;
+                }
+            }
+        }
+
+        Ok(Self {
+            config,
+            settings,
+            current_sections: Vec::new(),
+            pb_sections: None,
+            sum_of_best_sections: sum_of_best,
+            average_sections,
+            best_exit_sections,
+            median_sections,
+            history,
+            start_time: Instant::now(),
+            start_date: chrono::Local::now(),
+            state: RunState::Idle,
+            bridge_error: false,
+            bridge_active: false,
+            bridge: None,
+            error: None,
+            shutdown: false,
+            reset_counts,
+            drift_samples: Vec::new(),
+            clock_jumps: Vec::new(),
+            checkpoints: Vec::new(),
+            split_dates: Vec::new(),
+            events: VecDeque::new(),
+            last_clock_check: None,
+            notice: None,
+            trigger_warning: None,
+            capabilities_warning: None,
+            comparison_versions,
+            toast: None,
+            settings_warnings,
+            previous_pb: None,
+            ghost: None,
+            theme,
+            pb_total: None,
+            sum_of_best_total,
+            no_gold: false,
+            saved_run: None,
+            save_progress: SaveProgress::default(),
+            saver: Self::save,
+            pending_golds: None,
+            implausible_golds: Vec::new(),
+            accept_golds: false,
+            locked: false,
+            practice: false,
+            unfocused_at: None,
+            pause: None,
+            relay: None,
+            clock: None,
+            overrides: cli.clone(),
+            session,
+            migrated,
+            other_route,
+            trigger_start: false,
+            goal: None,
+            accessible: None,
+            finish_range: None,
+            race_start: None,
+            race_started: None,
+            saving: false,
+            slow_save_noted: false,
+            pace: None,
+            category_switch: None,
+        })
+    }
+
+    /// Prepares a run of a game with its PB as the comparison
+    fn for_game(game: &str, no_gold: bool, cli: &SettingsOverrides) -> Result<Self> {
+        let config = load_config(game)?;
+        migrate_sections(&config)?;
+        let mut app = Self::prepare_run(config, cli)?;
+        app.no_gold = no_gold;
+
+        if let Some(pb) = load_comparison(&app.config, "pb.run")? {
+            app.set_pb(pb)?;
+        }
+
+        Ok(app)
+    }
+
+    /// A fresh run of another game with the same settings, continuing this session
+    fn switch_game(&self, game: &str) -> Result<Self> {
+        let mut app = Self::for_game(game, self.no_gold, &self.overrides)?;
+        app.practice = self.practice;
+        let mut session = self.session.clone();
+        session.append(&mut app.session);
+        app.session = session;
+        app.goal = self.goal.clone();
+
+        Ok(app)
+    }
+
+    fn set_goal(&mut self, goal: Option<String>) {
+        self.goal = goal.map(|goal| goal::Goal::parse(&goal, &self.config.sections));
+    }
+
+    fn set_pb(&mut self, pb: Vec<Section>) -> Result<()> {
+        validate_run(&self.config, &pb)?;
+
+        self.pb_total = pb.last().map(|s| s.time);
+        self.pb_sections = Some(pb);
+
+        Ok(())
+    }
+
+    /// Saves whatever is left unsaved before exiting. An active run is saved as an unfinished
+    /// dated run of its completed sections, which only counts towards the history.
+    fn save_on_shutdown(&mut self) -> Result<Option<String>> {
+        match self.state {
+            RunState::Running => {
+                self.write_live_log("quit");
+                self.state = RunState::Idle;
+                self.save_unfinished()
+            }
+            RunState::Finished { .. } if self.saved_run.is_none() => {
+                self.save()?;
+                Ok(self.saved_run.clone())
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Saves the completed sections of a run that ended early, if there are any
+    fn save_unfinished(&mut self) -> Result<Option<String>> {
+        let completed = &self.current_sections[..self.current_sections.len() - 1];
+        if completed.is_empty() {
+            return Ok(None);
+        }
+
+        let name = unique_run_name(&self.config.directory_name, &self.start_date)?;
+        save_run(&self.config.directory_name, &name, completed)?;
+        self.saved_run = Some(name.clone());
+        Ok(Some(name))
+    }
+
+    /// Cancels an armed start, abandons an active run or clears a finished one, so the next
+    /// start signal begins a new run. Abandoned runs are logged in `resets.log`.
+    fn reset(&mut self) -> Result<()> {
+        match self.state {
+            RunState::Idle => return Ok(()),
+            RunState::Armed => {
+                self.state = RunState::Idle;
+                return Ok(());
+            }
+            RunState::Running => {
+                self.record_event(EventKind::Reset);
+                self.write_live_log("reset");
+                let section = self.current_sections.len() - 1;
+                append_reset_log(
+                    &self.config.directory_name,
+                    &self.start_date,
+                    section,
+                    self.elapsed(),
+                )?;
+                if let Some(count) = self.reset_counts.get_mut(section) {
+                    *count += 1;
+                }
+                self.save_unfinished()?;
+            }
+            // The finished run isn't saved yet
+            RunState::Finished { .. } if self.saved_run.is_none() => return Ok(()),
+            RunState::Finished { .. } => {
+                if let Some(bridge) = &self.bridge {
+                    if self.config.bridge_after_finish == bridge::AfterFinish::RestartOnNewRun {
+                        bridge.restart();
+                    }
+                }
+            }
+        }
+        // Golds waiting for confirmation are dropped with the run they belong to
+        self.resolve_pending_golds(false)?;
+
+        // A finished run may have changed the comparisons
+        self.reload_comparisons()?;
+
+        self.state = RunState::Idle;
+        self.trigger_warning = None;
+        self.current_sections.clear();
+        self.drift_samples.clear();
+        self.clock_jumps.clear();
+        self.checkpoints.clear();
+        self.split_dates.clear();
+        self.last_clock_check = None;
+        self.unfocused_at = None;
+        self.pause = None;
+        self.saved_run = None;
+        self.save_progress = SaveProgress::default();
+        self.notice = None;
+        self.previous_pb = None;
+        self.finish_range = None;
+        self.pace = None;
+        Ok(())
+    }
+
+    /// Loads pb.run and sum_of_best.run again, leaving out the ones that don't match the
+    /// config. Returns why they were left out.
+    fn reload_comparisons(&mut self) -> Result<Vec<String>> {
+        let game = self.config.directory_name.clone();
+        self.comparison_versions = comparison_versions(&game)?;
+        let mut problems = Vec::new();
+        let mut load = |run: &str| {
+            let sections = load_run(&game, run).and_then(|sections| {
+                sections
+                    .map(|s| validate_run(&self.config, &s).map(|()| s))
+                    .transpose()
+            });
+            sections.unwrap_or_else(|e| {
+                problems.push(format!("{} is unusable: {:#}", run, e));
+                None
+            })
+        };
+        let pb = load("pb.run");
+        let sum_of_best = load("sum_of_best.run");
+        self.pb_sections = pb;
+        self.sum_of_best_sections = sum_of_best;
+        self.pb_total = self
+            .pb_sections
+            .as_ref()
+            .and_then(|s| s.last())
+            .map(|s| s.time);
+        self.sum_of_best_total = self
+            .sum_of_best_sections
+            .as_ref()
+            .and_then(|s| s.last())
+            .map(|s| s.time);
+        for leg in self.relay.iter_mut().flatten() {
+            let game = &leg.config.directory_name;
+            leg.pb_sections = load_run(game, "pb.run")?;
+            leg.sum_of_best_sections = load_run(game, "sum_of_best.run")?;
+        }
+
+        Ok(problems)
+    }
+
+    /// Reloads the comparisons when they were changed by hand or by another speedy. While a
+    /// run is active or finished the reload waits until it is reset, so the deltas don't
+    /// shift during an attempt.
+    fn watch_comparisons(&mut self) -> Result<()> {
+        if self.state != RunState::Idle
+            || comparison_versions(&self.config.directory_name)? == self.comparison_versions
+        {
+            return Ok(());
+        }
+
+        let problems = self.reload_comparisons()?;
+        self.toast = Some(if problems.is_empty() {
+            (Instant::now(), "Comparisons reloaded".to_owned(), BLUE)
+        } else {
+            let message = format!("Comparisons reloaded, {}", problems.join(", "));
+            (Instant::now(), message, RED)
+        });
+
+        Ok(())
+    }
+
+    /// Saves a finished run, showing why in the UI if it fails. The run stays in memory, so
+    /// saving can be retried with `s`.
+    fn try_save(&mut self) -> bool {
+        let started = Instant::now();
+        let result = (self.saver)(self);
+        if started.elapsed() > SLOW_SAVE && !self.settings.async_save && !self.slow_save_noted {
+            self.slow_save_noted = true;
+            let text = format!(
+                "Saving took {}ms, async_save = true in settings.toml saves in the background",
+                started.elapsed().as_millis()
+            );
+            self.toast = Some((Instant::now(), text, BLUE));
+        }
+        match result {
+            Ok(()) => {
+                self.error = None;
+                true
+            }
+            Err(e) => {
+                let dir = game_dir(&self.config.directory_name).map_or_else(
+                    |_| "the data directory".to_owned(),
+                    |d| d.display().to_string(),
+                );
+                self.error = Some(format!(
+                    "Saving the run in {} failed: {:#}, s to retry",
+                    dir, e
+                ));
+                false
+            }
+        }
+    }
+
+    /// Saves a copy of the finished run without holding the lock, so a slow data directory
+    /// can't hold up the UI, and takes over what saving changed once it's done. The dated run
+    /// and the comparisons are still written in order by the copy.
+    fn save_in_background(app_lock: &RwLock<Self>) {
+        let mut copy = {
+            let app = &mut app_lock.write().expect("RwLock not poisoned");
+            // A shutdown may have saved it already
+            if app.saved_run.is_some() || app.saving {
+                return;
+            }
+            let copy = app.clone();
+            app.saving = true;
+            copy
+        };
+        let saved = copy.try_save();
+
+        let app = &mut app_lock.write().expect("RwLock not poisoned");
+        app.saving = false;
+        app.error = copy.error;
+        // What was written before a failure isn't written again by the retry
+        app.save_progress = copy.save_progress;
+        app.history = copy.history;
+        if !saved {
+            return;
+        }
+        app.saved_run = copy.saved_run;
+        app.migrated = copy.migrated;
+        app.other_route = copy.other_route;
+        app.pb_total = copy.pb_total;
+        app.notice = copy.notice;
+        app.previous_pb = copy.previous_pb;
+        app.pending_golds = copy.pending_golds;
+        app.implausible_golds = copy.implausible_golds;
+        app.sum_of_best_total = copy.sum_of_best_total;
+        let text = format!("Saved {}", app.saved_run.as_deref().unwrap_or_default());
+        app.toast = Some((Instant::now(), text, BLUE));
+    }
+
+    /// Writes the dated run, the comparisons, the logs and the metadata of a finished run.
+    /// Each step is noted in `save_progress` once it is done, so a retry after a failure
+    /// continues with the step that failed instead of writing the run a second time.
+    fn save(&mut self) -> Result<()> {
+        // Once per run, however many times finishing it asked for a save. A run saved in the
+        // background is taken over when that is done.
+        if self.saved_run.is_some() || self.saving {
+            return Ok(());
+        }
+        let _lock = lock_game(&self.config.directory_name, true)?;
+        let name = match self.save_progress.run.clone() {
+            Some(name) => name,
+            None => {
+                let name = unique_run_name(&self.config.directory_name, &self.start_date)?;
+                save_run(&self.config.directory_name, &name, &self.current_sections)?;
+                self.save_progress.run = Some(name.clone());
+                self.history.push(HistoricalRun {
+                    name: name.clone(),
+                    sections: self.current_sections.clone(),
+                });
+                name
+            }
+        };
+
+        let new_pb;
+        if let Some(pb) = &self.pb_sections {
+            ensure!(
+                pb.len() == self.current_sections.len(),
+                "pb.run has {} sections, the run {}",
+                pb.len(),
+                self.current_sections.len()
+            );
+            for (pb_section, current) in pb.iter().zip(&self.current_sections) {
+                ensure!(
+                    pb_section.name == current.name,
+                    "pb.run has {} where the run has {}",
+                    pb_section.name,
+                    current.name
+                );
+            }
+
+            new_pb = self
+                .current_sections
+                .last()
+                .context("empty current run")?
+                .time
+                < pb.last().context("empty pb run")?.time;
+        } else {
+            new_pb = true;
+        }
+
+        if new_pb && !self.practice && !self.save_progress.pb {
+            save_run(
+                &self.config.directory_name,
+                "pb.run",
+                &self.current_sections,
+            )?;
+            set_migration(&self.config.directory_name, "pb.run", None)?;
+            self.migrated.retain(|&c| c != Comparison::Pb);
+            set_route(
+                &self.config.directory_name,
+                "pb.run",
+                self.config.route.clone(),
+            )?;
+            self.other_route.retain(|&c| c != Comparison::Pb);
+
+            let new_time = self.current_sections.last().map_or(0, |s| s.time);
+            let old_time = self
+                .pb_sections
+                .as_ref()
+                .and_then(|pb| pb.last())
+                .map(|s| s.time);
+            append_pb_log(&self.config.directory_name, &name, new_time, old_time)?;
+            self.pb_total = Some(new_time);
+            self.notice = Some(pb_message(new_time, old_time));
+            self.previous_pb = self.pb_sections.clone();
+            self.save_progress.pb = true;
+        }
+
+        let metadata = RunMetadata {
+            comparison: Some(self.active_comparison()),
+            comparison_time: self
+                .comparison_sections()
+                .and_then(|s| s.last())
+                .map(|s| s.time),
+            drift_samples: self.drift_samples.clone(),
+            clock_jumps: self.clock_jumps.clone(),
+            speedy_version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+            config_version: Some(self.config.version),
+            hostname: nix::unistd::gethostname()
+                .ok()
+                .and_then(|name| name.into_string().ok()),
+            bridge: Some(self.bridge_active),
+            checkpoints: self.checkpoints.clone(),
+            started_at: Some(self.start_date),
+            split_dates: self.split_dates.clone(),
+            category: self.config.category.clone(),
+            route: self.config.route.clone(),
+            instant_splits: stats::zero_segments(&self.current_sections)
+                .into_iter()
+                .map(|i| self.config.sections[i].clone())
+                .collect(),
+            golds_suppressed: self.no_gold,
+            ..Default::default()
+        };
+        if !self.save_progress.golds {
+            self.save_golds(&name)?;
+            self.save_progress.golds = true;
+        }
+        // A sum of best started on this route, golds from other routes keep it marked
+        if self.sum_of_best_sections.is_none() {
+            set_route(
+                &self.config.directory_name,
+                "sum_of_best.run",
+                self.config.route.clone(),
+            )?;
+        }
+
+        save_metadata(&self.config.directory_name, &name, &metadata)?;
+
+        let legs = self.relay.clone().unwrap_or_default();
+        self.save_progress
+            .legs
+            .resize(legs.len(), SaveProgress::default());
+        for (i, leg) in legs.iter().enumerate() {
+            if self.save_progress.legs[i].done {
+                continue;
+            }
+            let mut leg_app = self.leg_app(leg);
+            leg_app.save_progress = self.save_progress.legs[i].clone();
+            let saved = leg_app.save();
+            self.save_progress.legs[i] = leg_app.save_progress.clone();
+            saved?;
+            // Nobody is asked about a game inside a relay, so suspicious golds stay out
+            leg_app.resolve_pending_golds(false)?;
+        }
+
+        self.save_progress.done = true;
+        self.saved_run = Some(name);
+        Ok(())
+    }
+
+    /// Takes this run's golds into the sum of best, or leaves them waiting for a confirmation
+    /// when they look suspicious
+    fn save_golds(&mut self, name: &str) -> Result<()> {
+        let new_sob = self.rebuilt_sum_of_best();
+        let problem = self.sum_of_best_problem(&new_sob);
+        debug_assert!(problem.is_none(), "{:?}", problem);
+        if let Some(problem) = problem {
+            // The stored golds are kept, recompute-sob rebuilds them from every run
+            let text = format!("{}, sum_of_best.run is left as it was", problem);
+            self.toast = Some((Instant::now(), text, RED));
+            return Ok(());
+        }
+        if self.no_gold {
+            append_golds_log(&self.config, name, &[])?;
+        } else if !self.accept_golds
+            && !assume_yes()
+            && (self.is_large_gold_improvement(&new_sob) || {
+                self.implausible_golds = self.implausible_golds(name)?;
+                !self.implausible_golds.is_empty()
+            })
+        {
+            // Saved once the user confirms in the UI
+            self.pending_golds = Some(new_sob);
+        } else {
+            save_run(&self.config.directory_name, "sum_of_best.run", &new_sob)?;
+            self.sum_of_best_total = new_sob.last().map(|s| s.time);
+            append_golds_log(&self.config, name, &self.new_golds())?;
+        }
+        Ok(())
+    }
+
+    /// The part of this relay run that belongs to one game, timed from that game's start
+    fn leg_app(&self, leg: &RelayLeg) -> Self {
+        let end = leg.first_section + leg.config.sections.len();
+        let offset = match leg.first_section {
+            0 => 0,
+            i => self.current_sections[i - 1].time,
+        };
+        let current_sections = self.current_sections[leg.first_section..end]
+            .iter()
+            .map(|s| Section {
+                name: s.name.clone(),
+                time: s.time - offset,
+            })
+            .collect();
+        // The leg's part of what was noted during the run, timed from the leg's start
+        let leg_end = self.current_sections[end - 1].time;
+        let during_leg = |time: u32| offset <= time && time <= leg_end;
+        let checkpoints = self
+            .checkpoints
+            .iter()
+            .filter(|c| (leg.first_section..end).contains(&c.section))
+            .map(|c| Checkpoint {
+                section: c.section - leg.first_section,
+                name: c.name.clone(),
+                time: c.time - offset,
+            })
+            .collect();
+        let drift_samples = self
+            .drift_samples
+            .iter()
+            .filter(|s| during_leg(s.monotonic))
+            .map(|s| DriftSample {
+                monotonic: s.monotonic - offset,
+                wall_clock: s.wall_clock - offset as i64,
+            })
+            .collect();
+        let clock_jumps = self
+            .clock_jumps
+            .iter()
+            .filter(|j| during_leg(j.at))
+            .map(|j| ClockJump {
+                at: j.at - offset,
+                jump: j.jump,
+            })
+            .collect();
+
+        Self {
+            config: leg.config.clone(),
+            settings: leg.settings.clone(),
+            current_sections,
+            checkpoints,
+            drift_samples,
+            clock_jumps,
+            // The relay's history is of the combined run, the leg's saves look up their own
+            history: Vec::new(),
+            pb_sections: leg.pb_sections.clone(),
+            sum_of_best_sections: leg.sum_of_best_sections.clone(),
+            average_sections: None,
+            best_exit_sections: None,
+            median_sections: None,
+            start_date: self.start_date + chrono::Duration::milliseconds(offset as i64),
+            split_dates: self.split_dates[leg.first_section..end].to_vec(),
+            saved_run: None,
+            save_progress: SaveProgress::default(),
+            pending_golds: None,
+            relay: None,
+            ..self.clone()
+        }
+    }
+
+    fn is_large_gold_improvement(&self, new_sob: &[Section]) -> bool {
+        let old = self.sum_of_best_sections.as_ref().and_then(|s| s.last());
+        match (old, new_sob.last()) {
+            (Some(old), Some(new)) if new.time < old.time => {
+                (old.time - new.time) as f64 > old.time as f64 * LARGE_GOLD_IMPROVEMENT
+            }
+            _ => false,
+        }
+    }
+
+    /// Sections where this run beats the stored gold by more than the `gold_guard` setting
+    /// and every segment of the other saved runs, which is more likely a missed split than a
+    /// real gold
+    fn implausible_golds(&self, run: &str) -> Result<Vec<usize>> {
+        let guard = self.settings.gold_guard * 1000;
+        let Some(sum_of_best_sections) = &self.sum_of_best_sections else {
+            return Ok(Vec::new());
+        };
+        let golds = stats::segment_times(sum_of_best_sections);
+        let current = stats::segment_times(&self.current_sections);
+        let candidates: Vec<usize> = self
+            .new_golds()
+            .into_iter()
+            .filter(|&i| guard > 0 && golds[i].saturating_sub(current[i]) > guard)
+            .collect();
+        if candidates.is_empty() {
+            return Ok(candidates);
+        }
+
+        let history = load_history(&self.config)?;
+        let others = history.iter().filter(|other| other.name != run);
+        let segments: Vec<Vec<u32>> = others
+            .map(|other| stats::segment_times(&other.sections))
+            .collect();
+        Ok(candidates
+            .into_iter()
+            .filter(|&i| {
+                segments
+                    .iter()
+                    .all(|segments| segments.get(i).is_none_or(|&s| current[i] < s))
+            })
+            .collect())
+    }
+
+    /// Saves or drops the golds that waited for confirmation. Dropped golds are noted as
+    /// rejected in `golds.log`, the run keeps them for `recompute-sob`.
+    fn resolve_pending_golds(&mut self, keep: bool) -> Result<()> {
+        let new_sob = if let Some(new_sob) = self.pending_golds.take() {
+            new_sob
+        } else {
+            return Ok(());
+        };
+        let game = &self.config.directory_name;
+        let _lock = lock_game(game, true)?;
+
+        if keep {
+            save_run(game, "sum_of_best.run", &new_sob)?;
+            self.sum_of_best_total = new_sob.last().map(|s| s.time);
+        } else if let Some(run) = &self.saved_run {
+            let mut metadata = load_metadata(game, run)?;
+            metadata.golds_suppressed = true;
+            save_metadata(game, run, &metadata)?;
+        }
+        if let Some(run) = &self.saved_run {
+            if keep {
+                append_golds_log(&self.config, run, &self.new_golds())?;
+            } else {
+                append_rejected_golds_log(&self.config, run, &self.new_golds())?;
+            }
+        }
+        self.implausible_golds.clear();
+
+        Ok(())
+    }
+
+    /// Sections whose segment in this run beats the stored sum of best
+    fn new_golds(&self) -> Vec<usize> {
+        let current = stats::segment_times(&self.current_sections);
+        let golds = self
+            .sum_of_best_sections
+            .as_ref()
+            .map(|sob| stats::segment_times(sob));
+        (0..current.len())
+            .filter(|&i| {
+                golds.as_ref().map_or(current[i] > 0, |golds| {
+                    stats::beats_gold(current[i], golds[i])
+                })
+            })
+            .collect()
+    }
+
+    /// The sum of best including this run, made of the faster of each stored gold and this
+    /// run's segment. Sections this run didn't reach keep their stored gold.
+    fn rebuilt_sum_of_best(&self) -> Vec<Section> {
+        let current = stats::segment_times(&self.current_sections);
+        let golds = match &self.sum_of_best_sections {
+            Some(sum_of_best_sections) => stats::segment_times(sum_of_best_sections)
+                .into_iter()
+                .enumerate()
+                .map(|(i, gold)| {
+                    current
+                        .get(i)
+                        .map_or(gold, |&segment| stats::best_segment(gold, segment))
+                })
+                .collect(),
+            None => current,
+        };
+        stats::cumulative(&self.config.sections, &golds)
+    }
+
+    /// What is wrong with a rebuilt sum of best: it has to have every section, and no gold
+    /// may be slower than the stored one. Stored golds of 0ms are the exception, any real
+    /// segment replaces them.
+    fn sum_of_best_problem(&self, new_sob: &[Section]) -> Option<String> {
+        if new_sob.len() != self.config.sections.len() {
+            return Some(format!(
+                "The rebuilt sum of best has {} of {} sections",
+                new_sob.len(),
+                self.config.sections.len()
+            ));
+        }
+        let old = stats::segment_times(self.sum_of_best_sections.as_ref()?);
+        let new = stats::segment_times(new_sob);
+        let i = (0..old.len().min(new.len())).find(|&i| old[i] > 0 && new[i] > old[i])?;
+        Some(format!(
+            "The rebuilt sum of best has {} for {}, slower than the stored gold {}",
+            format_run_time(new[i]),
+            self.config.sections[i],
+            format_run_time(old[i])
+        ))
+    }
+}
+
+fn min_sec_mil_to_millis(min: u32, sec: u32, mil: u32) -> u32 {
+    (min * 60 + sec) * 1000 + mil
+}
 
 fn millis_to_min_sec_mil(millis: u32) -> (u32, u32, u32) {
     let min = millis / 60000;
@@ -627,128 +4074,2502 @@ fn millis_to_min_sec_mil(millis: u32) -> (u32, u32, u32) {
     (min, sec, mil)
 }
 
-fn load_config(game: &str) -> Result<GameConfig> {
-    let dirs = directories::ProjectDirs::from("", "", "speedy")
-        .ok_or(anyhow!("No home directory found"))?;
-    let data_dir = dirs.data_dir();
-    let game_dir = data_dir.join(game);
-    let config_path = game_dir.join("config.toml");
-    let config_str = fs::read_to_string(config_path)?;
-    let mut config: GameConfig = toml::from_str(&config_str)?;
-    config.directory_name = game.to_owned();
+/// Formats a final time, switching to hours for long runs: "12m03s", "26h12m03s"
+fn format_final_time(millis: u32) -> String {
+    let (min, sec, _mil) = millis_to_min_sec_mil(millis);
+    if min >= 60 {
+        format!("{}h{:02}m{:02}s", min / 60, min % 60, sec)
+    } else {
+        format!("{}m{:02}s", min, sec)
+    }
+}
+
+/// Parses a time written by hand: "45", "1:05" or "1:05.250"
+fn parse_time(time: &str) -> Result<u32> {
+    let invalid = || anyhow!("Invalid time {:?}, expected something like 1:05.250", time);
+
+    let (min, rest) = match time.split_once(':') {
+        Some((min, rest)) => (min.parse::<u32>().map_err(|_| invalid())?, rest),
+        None => (0, time),
+    };
+    let (sec, fraction) = rest.split_once('.').unwrap_or((rest, ""));
+    let sec = sec.parse::<u32>().map_err(|_| invalid())?;
+    ensure!(
+        fraction.len() <= 3 && fraction.chars().all(|c| c.is_ascii_digit()),
+        invalid()
+    );
+    let mil = format!("{:0<3}", fraction)
+        .parse::<u32>()
+        .map_err(|_| invalid())?;
+
+    Ok(min_sec_mil_to_millis(min, sec, mil))
+}
+
+/// Formats a time the way run files store it: "20m01.212s"
+fn format_run_time(millis: u32) -> String {
+    let (min, sec, mil) = millis_to_min_sec_mil(millis);
+    format!("{}m{:02}.{:03}s", min, sec, mil)
+}
+
+/// Formats a long total of milliseconds like "12h03m09s"
+fn format_duration(millis: u64) -> String {
+    let secs = millis / 1000;
+    format!("{}h{:02}m{:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Formats a time into a recording the way video chapters are written: "4:05" or "1:02:03"
+fn format_chapter_time(millis: i64) -> String {
+    let sign = if millis < 0 { "-" } else { "" };
+    let secs = millis.unsigned_abs() / 1000;
+    let (hours, min, sec) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}{}:{:02}:{:02}", sign, hours, min, sec)
+    } else {
+        format!("{}{:02}:{:02}", sign, min, sec)
+    }
+}
+
+/// Parses a local time like "2024-05-01T19:58:30", or "19:58:30" on the day of `day`
+fn parse_wall_time(
+    time: &str,
+    day: &chrono::DateTime<chrono::Local>,
+) -> Result<chrono::DateTime<chrono::Local>> {
+    let naive = chrono::NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| {
+            chrono::NaiveTime::parse_from_str(time, "%H:%M:%S")
+                .map(|t| day.date_naive().and_time(t))
+        })
+        .with_context(|| {
+            format!(
+                "{:?} is not a time like 2024-05-01T19:58:30 or 19:58:30",
+                time
+            )
+        })?;
+
+    naive
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .with_context(|| format!("{} doesn't exist in the local time zone", time))
+}
+
+/// Checks the keys of config.toml and its tables, see `settings::check_keys`
+fn config_key_warnings(table: &toml::Table) -> Result<Vec<String>> {
+    let known = [CONFIG_KEYS, settings::KEYS].concat();
+    let mut warnings = settings::check_keys(table, &known, "")?;
+    let tables = |key: &str| table.get(key).and_then(toml::Value::as_table);
+    if let Some(bridge) = tables("bridge") {
+        warnings.extend(settings::check_keys(bridge, &["env"], "bridge.")?);
+    }
+    if let Some(sounds) = tables("sounds") {
+        let known = [
+            "sections",
+            "speak_command",
+            "delta_pitch",
+            "silent",
+            "close_after",
+        ];
+        warnings.extend(settings::check_keys(sounds, &known, "sounds.")?);
+        if let Some(pitch) = sounds.get("delta_pitch").and_then(toml::Value::as_table) {
+            let known = ["semitones", "full_range_at"];
+            warnings.extend(settings::check_keys(pitch, &known, "sounds.delta_pitch.")?);
+        }
+        let sections = sounds.get("sections").and_then(toml::Value::as_table);
+        for (section, clips) in sections.into_iter().flatten() {
+            if let Some(clips) = clips.as_table() {
+                let prefix = format!("sounds.sections.{}.", section);
+                let known = ["gold", "gaining", "losing"];
+                warnings.extend(settings::check_keys(clips, &known, &prefix)?);
+            }
+        }
+    }
+    let schedule = table
+        .get("comparison_schedule")
+        .and_then(toml::Value::as_array);
+    for entry in schedule
+        .into_iter()
+        .flatten()
+        .filter_map(toml::Value::as_table)
+    {
+        let known = ["until_section", "compare"];
+        warnings.extend(settings::check_keys(entry, &known, "comparison_schedule.")?);
+    }
+    Ok(warnings)
+}
+
+fn load_config(game: &str) -> Result<GameConfig> {
+    let game_dir = game_dir(game)?;
+    let config_path = game_dir.join("config.toml");
+    let config_str = fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read {}", config_path.display()))?;
+    let parse_error = || format!("Failed to parse {}", config_path.display());
+    let table: toml::Table = toml::from_str(&config_str).with_context(parse_error)?;
+    let warnings = config_key_warnings(&table).with_context(parse_error)?;
+    let mut config: GameConfig = toml::from_str(&config_str).with_context(parse_error)?;
+    config.directory_name = game.to_owned();
+    config.warnings = warnings;
+
+    ensure!(config.sections.len() > 0);
+    for (section, target) in &config.targets {
+        ensure!(
+            config.sections.contains(section),
+            "{}: target for {:?}, which is not one of the sections {:?}",
+            game,
+            section,
+            config.sections
+        );
+        parse_time(target).with_context(|| format!("{}: target for {:?}", game, section))?;
+    }
+    ensure!(
+        config.triggers.is_empty() || config.triggers.len() == config.sections.len(),
+        "{}: {} triggers for {} sections",
+        game,
+        config.triggers.len(),
+        config.sections.len()
+    );
+    for section in config.sounds.sections.keys() {
+        ensure!(
+            config.sections.contains(section),
+            "{}: sounds for {:?}, which is not one of the sections {:?}",
+            game,
+            section,
+            config.sections
+        );
+    }
+    if let Some(pitch) = &config.sounds.delta_pitch {
+        ensure!(
+            pitch.semitones > 0.0 && pitch.semitones <= 24.0,
+            "{}: sounds.delta_pitch.semitones must be between 0 and 24, got {}",
+            game,
+            pitch.semitones
+        );
+        ensure!(
+            pitch.full_range_at.is_none_or(|seconds| seconds > 0.0),
+            "{}: sounds.delta_pitch.full_range_at must be more than 0 seconds",
+            game
+        );
+    }
+    #[cfg(not(feature = "tts"))]
+    ensure!(
+        config.sounds.speak_command.is_none(),
+        "{}: speak_command needs speedy built with the tts feature",
+        game
+    );
+    for section in &config.highlight {
+        ensure!(
+            config.sections.contains(section),
+            "{}: highlight of {:?}, which is not one of the sections {:?}",
+            game,
+            section,
+            config.sections
+        );
+    }
+
+    Ok(config)
+}
+
+fn load_all_configs() -> Result<Vec<GameConfig>> {
+    let data_dir = data_dir()?;
+    let mut results = Vec::new();
+    for game_dir in fs::read_dir(data_dir)? {
+        let game = game_dir?
+            .file_name()
+            .into_string()
+            .ok()
+            .context("Invalid OsString")?;
+        if let Ok(config) = load_config(&game) {
+            results.push(config);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Names of all game directories that contain a config, without parsing them
+fn list_games() -> Result<Vec<String>> {
+    let data_dir = data_dir()?;
+
+    let mut games = Vec::new();
+    for entry in fs::read_dir(data_dir)? {
+        let entry = entry?;
+        if entry.path().join("config.toml").is_file() {
+            if let Ok(game) = entry.file_name().into_string() {
+                games.push(game);
+            }
+        }
+    }
+    games.sort();
+
+    Ok(games)
+}
+
+/// The game with the same full name as `config` whose category is `name`, ignoring case
+fn category_game(config: &GameConfig, name: &str) -> Result<Option<String>> {
+    for game in list_games()? {
+        // Games whose config can't be read are no candidates
+        let Ok(other) = load_config(&game) else {
+            continue;
+        };
+        let category = other.category.as_deref().unwrap_or_default();
+        if other.full_game_name == config.full_game_name && category.eq_ignore_ascii_case(name) {
+            return Ok(Some(game));
+        }
+    }
+
+    Ok(None)
+}
+
+fn write_config(config: &GameConfig) -> Result<()> {
+    let game_dir = game_dir(&config.directory_name)?;
+
+    std::fs::create_dir_all(&game_dir)?;
+
+    let config_str = toml::to_string_pretty(config)?;
+    let config_path = game_dir.join("config.toml");
+    fs::write(config_path, &config_str)?;
+
+    Ok(())
+}
+
+fn load_run(game: &str, run: &str) -> Result<Option<Vec<Section>>> {
+    store::open(game)?.load_run(run)
+}
+
+fn load_run_file(file_path: &Path) -> Result<Option<Vec<Section>>> {
+    let file = if let Ok(file) = File::open(file_path) {
+        file
+    } else {
+        return Ok(None);
+    };
+
+    let file = BufReader::new(file);
+    let lines = file
+        .lines()
+        .collect::<io::Result<Vec<_>>>()
+        .context("Failed to read line in run file")?;
+
+    parse_run_lines(&lines).map(Some)
+}
+
+/// Parses the lines of a run file
+fn parse_run_lines(lines: &[impl AsRef<str>]) -> Result<Vec<Section>> {
+    // Lines look like this: "escape01: 20m01.212s
+    let re = Regex::new(r"^(.*): (\d*)m(\d{2})\.(\d{3})s$").unwrap();
+
+    let mut sections = Vec::new();
+    for line in lines {
+        let cap = re.captures(line.as_ref()).context("Invalid run file")?;
+
+        let section_name = cap[1].to_owned();
+        let section_time_ms = min_sec_mil_to_millis(
+            cap[2].parse().unwrap(),
+            cap[3].parse().unwrap(),
+            cap[4].parse().unwrap(),
+        );
+
+        sections.push(Section {
+            name: section_name,
+            time: section_time_ms,
+        });
+    }
+
+    Ok(sections)
+}
+
+fn save_run(game: &str, run: &str, sections: &[Section]) -> Result<()> {
+    store::open(game)?.save_run(run, sections)
+}
+
+/// The contents of a run file
+fn run_text(sections: &[Section]) -> String {
+    let mut run_str = String::new();
+    for section in sections {
+        run_str += &format!("{}: {}\n", section.name, format_run_time(section.time));
+    }
+    run_str
+}
+
+/// Writes a file next to the target and renames it over it, so readers see either the old
+/// or the new content but never a half written file
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+
+    let write = || -> io::Result<()> {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    };
+    if let Err(e) = write() {
+        // Don't leave a partial file behind on a full disk
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e).with_context(|| format!("Failed to write {}", path.display()));
+    }
+
+    Ok(())
+}
+
+/// A lock on a game's `.lock` file, released when dropped. Saving a run writes several files,
+/// which only happens under the exclusive lock, while read-only commands take the shared one.
+/// Holds no file on filesystems without flock support and for readers that can't create the
+/// file, where nothing is locked.
+struct GameLock {
+    _file: Option<File>,
+}
+
+fn lock_game(game: &str, exclusive: bool) -> Result<GameLock> {
+    let Some(file) = open_lock_file(game, exclusive)? else {
+        return Ok(GameLock { _file: None });
+    };
+    let arg = if exclusive {
+        FlockArg::LockExclusive
+    } else {
+        FlockArg::LockShared
+    };
+    let result = flock(file.as_raw_fd(), arg);
+    locked(file, result)
+}
+
+/// The shared lock for a read-only command, which says so when it waits for a save
+fn lock_for_reading(game: &str) -> Result<GameLock> {
+    let Some(file) = open_lock_file(game, false)? else {
+        return Ok(GameLock { _file: None });
+    };
+    let fd = file.as_raw_fd();
+    let result = match flock(fd, FlockArg::LockSharedNonblock) {
+        Err(Errno::EWOULDBLOCK) => {
+            eprintln!("timer is saving, retrying…");
+            flock(fd, FlockArg::LockShared)
+        }
+        result => result,
+    };
+    locked(file, result)
+}
+
+/// Opens the `.lock` file, creating it if needed. Readers go without a lock when it can't
+/// be created, like in a read-only data directory or for a game that doesn't exist, where
+/// loading the game tells what is wrong.
+fn open_lock_file(game: &str, exclusive: bool) -> Result<Option<File>> {
+    let path = game_dir(game)?.join(".lock");
+    match fs::OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => Ok(Some(file)),
+        Err(_) if !exclusive => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to open {}", path.display())),
+    }
+}
+
+fn locked(file: File, result: nix::Result<()>) -> Result<GameLock> {
+    match result {
+        Ok(()) => Ok(GameLock { _file: Some(file) }),
+        Err(Errno::ENOLCK | Errno::EOPNOTSUPP | Errno::ENOSYS) => Ok(GameLock { _file: None }),
+        Err(e) => Err(e).context("Failed to lock the game"),
+    }
+}
+
+/// Loads the metadata of a dated run. Runs without any get the defaults.
+fn load_metadata(game: &str, run: &str) -> Result<RunMetadata> {
+    match store::open(game)?.load_metadata(run)? {
+        Some(metadata_str) => Ok(toml::from_str(&metadata_str)?),
+        None => Ok(RunMetadata::default()),
+    }
+}
+
+fn save_metadata(game: &str, run: &str, metadata: &RunMetadata) -> Result<()> {
+    store::open(game)?.save_metadata(run, &toml::to_string_pretty(metadata)?)
+}
+
+/// Notes or clears the migration that changed a comparison's splits
+fn set_migration(game: &str, run: &str, migration: Option<String>) -> Result<()> {
+    let mut metadata = load_metadata(game, run)?;
+    if metadata.migration != migration {
+        metadata.migration = migration;
+        save_metadata(game, run, &metadata)?;
+    }
+
+    Ok(())
+}
+
+/// Notes the route a comparison was run on
+fn set_route(game: &str, run: &str, route: Option<String>) -> Result<()> {
+    let mut metadata = load_metadata(game, run)?;
+    if metadata.route != route {
+        metadata.route = route;
+        save_metadata(game, run, &metadata)?;
+    }
+
+    Ok(())
+}
+
+/// The comparison files of a game that were recorded under another route than the config's,
+/// with the route they were recorded under
+fn other_route_comparisons(
+    config: &GameConfig,
+) -> Result<Vec<(Comparison, &'static str, Option<String>)>> {
+    let game = &config.directory_name;
+    let mut other = Vec::new();
+    for (comparison, run) in [
+        (Comparison::Pb, "pb.run"),
+        (Comparison::SumOfBest, "sum_of_best.run"),
+    ] {
+        if store::open(game)?.version(run)?.is_none() {
+            continue;
+        }
+        let route = load_metadata(game, run)?.route;
+        if route != config.route {
+            other.push((comparison, run, route));
+        }
+    }
+
+    Ok(other)
+}
+
+/// Offers to move comparisons of another route to `routes/<route>/` in the game's directory,
+/// so the runs on the new route start fresh. True if they were moved.
+fn archive_route(
+    config: &GameConfig,
+    comparisons: &[(Comparison, &'static str, Option<String>)],
+) -> Result<bool> {
+    let store = store::open(&config.directory_name)?;
+    let mut moves = Vec::new();
+    for (_, run, route) in comparisons {
+        // Route names are free text, the directory gets a safe version of it
+        let directory: String = route
+            .as_deref()
+            .unwrap_or("unnamed")
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "._+-".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let directory = match directory.trim_start_matches('.') {
+            "" => "unnamed",
+            directory => directory,
+        };
+        let archived = format!("routes/{}/{}", directory, run);
+        ensure!(
+            store.version(&archived)?.is_none(),
+            "{} is already archived, move it away to archive the route again",
+            store.location(&archived).display()
+        );
+        moves.push((run.to_string(), archived));
+    }
+
+    let mut routes = comparisons
+        .iter()
+        .map(|(_, _, route)| route.as_deref().unwrap_or("no route"))
+        .collect::<Vec<_>>();
+    routes.dedup();
+    println!(
+        "The route is now {}, the comparisons were run on {}",
+        config.route.as_deref().unwrap_or("unset"),
+        routes.join(" and ")
+    );
+    let paths = moves
+        .iter()
+        .map(|(from, _)| store.location(from))
+        .collect::<Vec<_>>();
+    if !confirm(
+        &mut io::stdin().lock(),
+        "Archive them to start fresh on the new route",
+        &paths,
+        Danger::Low,
+    )? {
+        println!("Keeping them, their deltas are marked with *");
+        return Ok(false);
+    }
+
+    let _lock = lock_game(&config.directory_name, true)?;
+    for (from, to) in moves {
+        store.rename_run(&from, &to)?;
+    }
+
+    Ok(true)
+}
+
+/// The comparison files of a game that a section migration changed, with the migration
+fn migrated_comparisons(game: &str) -> Result<Vec<(Comparison, &'static str, String)>> {
+    let mut migrated = Vec::new();
+    for (comparison, run) in [
+        (Comparison::Pb, "pb.run"),
+        (Comparison::SumOfBest, "sum_of_best.run"),
+    ] {
+        if let Some(migration) = load_metadata(game, run)?.migration {
+            migrated.push((comparison, run, migration));
+        }
+    }
+
+    Ok(migrated)
+}
+
+/// Appends an event that no longer fits in memory to the game's `session.log`
+fn append_session_log(game: &str, event: &SessionEvent) -> Result<()> {
+    let line = format!(
+        "{}\t{:?}\t{}",
+        event.date.format("%Y-%m-%dT%H:%M:%S"),
+        event.kind,
+        event.section
+    );
+    store::open(game)?.append_log("session.log", &line)
+}
+
+/// Appends a line to the game's `current.log`, emptying it first with `rotate`. Each line is
+/// written right away, so it is there even if speedy dies next.
+fn append_live_log(game: &str, line: &str, rotate: bool) -> Result<()> {
+    if rotate {
+        let path = log_path(game, "current.log")?;
+        if path.exists() {
+            fs::write(&path, "").with_context(|| format!("Failed to empty {}", path.display()))?;
+        }
+    }
+    let mut file = open_log(game, "current.log")?;
+    writeln!(file, "{}", line)?;
+    file.sync_data()?;
+
+    Ok(())
+}
+
+/// The path of `current.log` if the run in it neither ended nor was saved, because speedy
+/// stopped in the middle of it
+fn interrupted_live_log(game: &str) -> Result<Option<PathBuf>> {
+    let path = log_path(game, "current.log")?;
+    let Ok(log) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let last_event = log.lines().last().and_then(|line| line.split('\t').nth(1));
+    Ok(matches!(last_event, Some("start" | "split")).then_some(path))
+}
+
+/// A line of a game's `resets.log`
+struct Reset {
+    /// Start of the reset run
+    date: String,
+    /// Index of the section that was active
+    section: usize,
+    elapsed: String,
+}
+
+/// Appends a reset to the game's `resets.log`
+fn append_reset_log(
+    game: &str,
+    start_date: &chrono::DateTime<chrono::Local>,
+    section: usize,
+    elapsed: u32,
+) -> Result<()> {
+    let line = format!(
+        "{}\t{}\t{}",
+        start_date.format("%Y-%m-%dT%H:%M:%S"),
+        section,
+        format_run_time(elapsed)
+    );
+    store::open(game)?.append_log("resets.log", &line)
+}
+
+/// For each reset of a run in `history`, the section it was abandoned in and the time spent
+/// there. Runs reset in their first section weren't saved, so those resets are only counted
+/// with `all_runs`, when the history isn't filtered.
+fn abandoned_sections(
+    resets: &[Reset],
+    history: &[HistoricalRun],
+    all_runs: bool,
+) -> Vec<(usize, u32)> {
+    resets
+        .iter()
+        .filter_map(|reset| {
+            // Stored like "0m12.345s"
+            let elapsed = reset.elapsed.trim_end_matches('s').replacen('m', ":", 1);
+            let elapsed = parse_time(&elapsed).ok()?;
+            if reset.section == 0 {
+                return all_runs.then_some((0, elapsed));
+            }
+            // The saved run has every section before the one it was reset in
+            let run = history.iter().find(|run| {
+                run.name.starts_with(&reset.date) && run.sections.len() == reset.section
+            })?;
+            let section_start = run.sections.last().map_or(0, |s| s.time);
+            Some((reset.section, elapsed.saturating_sub(section_start)))
+        })
+        .collect()
+}
+
+/// Reads the game's `resets.log`, skipping lines that can't be parsed
+fn load_resets(game: &str) -> Result<Vec<Reset>> {
+    let log = store::open(game)?.read_log("resets.log")?;
+
+    Ok(log
+        .iter()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(Reset {
+                date: fields.next()?.to_owned(),
+                section: fields.next()?.parse().ok()?,
+                elapsed: fields.next()?.to_owned(),
+            })
+        })
+        .collect())
+}
+
+/// Describes a new PB: "New PB: 13m47.120s (was 14m02.455s, -0m15.335s)"
+fn pb_message(new_time: u32, old_time: Option<u32>) -> String {
+    match old_time {
+        Some(old_time) => format!(
+            "New PB: {} (was {}, -{})",
+            format_run_time(new_time),
+            format_run_time(old_time),
+            format_run_time(old_time.saturating_sub(new_time))
+        ),
+        None => format!("New PB: {}", format_run_time(new_time)),
+    }
+}
+
+/// Adds a line to the game's `pb_history.log`: the run, its time, the old PB and the improvement
+fn append_pb_log(game: &str, run: &str, new_time: u32, old_time: Option<u32>) -> Result<()> {
+    let old = old_time.map_or_else(|| "-".to_owned(), format_run_time);
+    let improvement = old_time.map_or_else(
+        || "-".to_owned(),
+        |old_time| format!("-{}", format_run_time(old_time.saturating_sub(new_time))),
+    );
+    let line = format!(
+        "{}\t{}\t{}\t{}",
+        run,
+        format_run_time(new_time),
+        old,
+        improvement
+    );
+    store::open(game)?.append_log("pb_history.log", &line)
+}
+
+/// Notes which sections of a run went into the sum of best as golds, in `golds.log`. Runs
+/// without golds get a line too, so they can be told apart from runs older than the log.
+fn append_golds_log(config: &GameConfig, run: &str, golds: &[usize]) -> Result<()> {
+    let mut line = run.to_owned();
+    for &section in golds {
+        line += "\t";
+        line += &config.sections[section];
+    }
+    store::open(&config.directory_name)?.append_log("golds.log", &line)
+}
+
+/// Notes golds that were asked about and not kept in `golds.log`, marked `rejected:`
+fn append_rejected_golds_log(config: &GameConfig, run: &str, golds: &[usize]) -> Result<()> {
+    let mut line = run.to_owned();
+    for &section in golds {
+        line += "\trejected:";
+        line += &config.sections[section];
+    }
+    store::open(&config.directory_name)?.append_log("golds.log", &line)
+}
+
+/// The golds of each run in `golds.log` by run name, without the rejected ones
+fn load_golds_log(game: &str) -> Result<BTreeMap<String, Vec<String>>> {
+    read_golds_log(game, false)
+}
+
+/// The golds that were asked about and not kept, by run name
+fn load_rejected_golds(game: &str) -> Result<BTreeMap<String, Vec<String>>> {
+    read_golds_log(game, true)
+}
+
+fn read_golds_log(game: &str, rejected: bool) -> Result<BTreeMap<String, Vec<String>>> {
+    let log = store::open(game)?.read_log("golds.log")?;
+
+    Ok(log
+        .iter()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let run = fields.next().filter(|run| !run.is_empty())?;
+            let golds = fields.filter_map(|gold| match gold.strip_prefix("rejected:") {
+                Some(gold) => rejected.then_some(gold),
+                None => (!rejected).then_some(gold),
+            });
+            Some((run.to_owned(), golds.map(str::to_owned).collect()))
+        })
+        .collect())
+}
+
+/// The runs that became PB, oldest first, from `pb_history.log`
+fn load_pb_log(game: &str) -> Result<Vec<String>> {
+    let log = store::open(game)?.read_log("pb_history.log")?;
+
+    Ok(log
+        .iter()
+        .filter_map(|line| line.split('\t').next())
+        .filter(|run| !run.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Drops the lines about a run from a log that starts its lines with the run name
+fn remove_from_log(game: &str, log: &str, run: &str) -> Result<()> {
+    let store = store::open(game)?;
+    let lines = store.read_log(log)?;
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let kept: Vec<String> = lines
+        .into_iter()
+        .filter(|line| line.split('\t').next() != Some(run))
+        .collect();
+
+    store.write_log(log, &kept)
+}
+
+/// Lists the dated runs of a game, oldest first. Comparison files are left out.
+fn list_runs(game: &str) -> Result<Vec<String>> {
+    let mut runs = store::open(game)?.runs()?;
+    runs.retain(|name| {
+        name.ends_with(".run")
+            && !name.contains('/')
+            && name != "pb.run"
+            && name != "sum_of_best.run"
+    });
+
+    Ok(runs)
+}
+
+/// Names a dated run after its start, adding a counter if that second is already taken
+fn unique_run_name(game: &str, start_date: &chrono::DateTime<chrono::Local>) -> Result<String> {
+    let store = store::open(game)?;
+
+    let stem = start_date.format("%Y-%m-%dT%H:%M:%S").to_string();
+    let mut name = format!("{}.run", stem);
+    for i in 2.. {
+        if store.version(&name)?.is_none() {
+            break;
+        }
+        name = format!("{}_{}.run", stem, i);
+    }
+
+    Ok(name)
+}
+
+/// Splits adopted from someone else live in `shared/`, apart from the game's own runs. The
+/// name is checked like the names of games.
+fn shared_run(name: &str) -> Result<String> {
+    validate_name("comparison", name)?;
+    Ok(format!("shared/{}.run", name))
+}
+
+/// Finds the run meant by a CLI selector.
+///
+/// Accepts `pb`, `sob`, `latest`, `latest-N`, full run file names and unambiguous prefixes
+/// of them, like `2024-05-01`.
+fn resolve_run_selector(game: &str, selector: &str) -> Result<String> {
+    let run = match selector {
+        "pb" => "pb.run".to_owned(),
+        "sob" => "sum_of_best.run".to_owned(),
+        _ => {
+            let runs = list_runs(game)?;
+            if let Some(back) = selector.strip_prefix("latest") {
+                let back = if back.is_empty() {
+                    0
+                } else {
+                    back.strip_prefix('-')
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .with_context(|| format!("Invalid run selector {:?}", selector))?
+                };
+                let index = runs
+                    .len()
+                    .checked_sub(back + 1)
+                    .with_context(|| format!("{} only has {} runs", game, runs.len()))?;
+                runs[index].clone()
+            } else if runs.iter().any(|r| r == selector) {
+                selector.to_owned()
+            } else {
+                let candidates = runs
+                    .into_iter()
+                    .filter(|r| r.starts_with(selector))
+                    .collect::<Vec<_>>();
+                match candidates.len() {
+                    0 => bail!("No run of {} matches {:?}", game, selector),
+                    1 => candidates[0].clone(),
+                    _ => bail!(
+                        "{:?} matches several runs:\n{}",
+                        selector,
+                        candidates.join("\n")
+                    ),
+                }
+            }
+        }
+    };
+
+    ensure!(
+        store::open(game)?.version(&run)?.is_some(),
+        "{}/{} does not exist",
+        game,
+        run
+    );
+
+    Ok(run)
+}
+
+/// Where a run of a game is kept, to show to the user
+fn run_location(game: &str, run: &str) -> Result<PathBuf> {
+    Ok(store::open(game)?.location(run))
+}
+
+fn delete_run(game: &str, run: &str) -> Result<()> {
+    store::open(game)?.delete_run(run)
+}
+
+/// Section names of the saved runs, from the PB or else the newest run, if they don't fit
+/// the config anymore because its sections were edited
+fn outdated_sections(config: &GameConfig) -> Result<Option<Vec<String>>> {
+    let game = &config.directory_name;
+    let (saved, complete) = match load_run(game, "pb.run")? {
+        Some(pb) => (pb, true),
+        None => match list_runs(game)?.last() {
+            Some(newest) => (load_run(game, newest)?.unwrap_or_default(), false),
+            None => return Ok(None),
+        },
+    };
+    let names: Vec<String> = saved.into_iter().map(|s| s.name).collect();
+
+    // Unfinished runs only cover the first sections
+    let fits =
+        config.sections.starts_with(&names) && (!complete || names.len() == config.sections.len());
+    Ok((!fits).then_some(names))
+}
+
+/// Notices when the sections of the config were edited by hand while runs exist, asks which
+/// sections were renamed, removed or added and rewrites the saved runs to match
+fn migrate_sections(config: &GameConfig) -> Result<()> {
+    let Some(old) = outdated_sections(config)? else {
+        return Ok(());
+    };
+    let game = &config.directory_name;
+    let removed: Vec<&String> = old
+        .iter()
+        .filter(|n| !config.sections.contains(n))
+        .collect();
+    let added: Vec<&String> = config
+        .sections
+        .iter()
+        .filter(|n| !old.contains(n))
+        .collect();
+
+    println!(
+        "The sections of {} don't match its saved runs anymore",
+        game
+    );
+    println!("  saved:  {}", old.join(", "));
+    println!("  config: {}", config.sections.join(", "));
+    if !io::stdin().is_terminal() {
+        bail!(
+            "Run `speedy run {}` in a terminal to update the saved runs, or restore the old \
+             sections in config.toml",
+            game
+        );
+    }
+
+    // Removed sections are either renamed to one of the added ones or really gone
+    let mut renames = BTreeMap::new();
+    for &name in &removed {
+        let candidates: Vec<&&String> = added
+            .iter()
+            .filter(|n| !renames.values().any(|r| r == **n))
+            .collect();
+        if candidates.is_empty() {
+            println!("{} was removed", name);
+            continue;
+        }
+        let answer = ask(
+            &mut io::stdin().lock(),
+            &format!(
+                "Was {} renamed? Enter one of {}, or nothing if it was removed: ",
+                name,
+                candidates
+                    .iter()
+                    .map(|n| n.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )?;
+        if candidates.iter().any(|n| ***n == answer) {
+            renames.insert(name.clone(), answer);
+        }
+    }
+    let inserted: Vec<&&String> = added
+        .iter()
+        .filter(|n| !renames.values().any(|r| r == **n))
+        .collect();
+    if !inserted.is_empty() {
+        println!(
+            "Runs end before the first added section ({}), the times after it can't be split up",
+            inserted
+                .iter()
+                .map(|n| n.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    let mut changes: Vec<String> = renames
+        .iter()
+        .map(|(old, new)| format!("renamed {} to {}", old, new))
+        .collect();
+    for name in removed.iter().filter(|n| !renames.contains_key(**n)) {
+        changes.push(format!("removed {}", name));
+    }
+    for name in &inserted {
+        changes.push(format!("added {}", name));
+    }
+    let migration = format!(
+        "{}: {}",
+        chrono::Local::now().format("%Y-%m-%d"),
+        changes.join(", ")
+    );
+
+    let mut runs = list_runs(game)?;
+    runs.extend(["pb.run".to_owned(), "sum_of_best.run".to_owned()]);
+    let mut migrated = Vec::new();
+    for run in runs {
+        let Some(sections) = load_run(game, &run)? else {
+            continue;
+        };
+        let mut new_sections = Vec::new();
+        for section in &sections {
+            let name = match renames.get(&section.name) {
+                Some(new_name) => new_name.clone(),
+                // Dropping a split merges its segment into the next one
+                None if !config.sections.contains(&section.name) => continue,
+                None => section.name.clone(),
+            };
+            if config.sections.get(new_sections.len()) != Some(&name) {
+                break;
+            }
+            new_sections.push(Section {
+                name,
+                time: section.time,
+            });
+        }
+        let changed = new_sections.len() != sections.len()
+            || new_sections
+                .iter()
+                .zip(&sections)
+                .any(|(a, b)| a.name != b.name);
+        if changed {
+            // Renames keep every time, dropped splits merge golds and cut off runs
+            let approximate = new_sections.len() != sections.len();
+            migrated.push((run, new_sections, approximate));
+        }
+    }
+
+    let paths = migrated
+        .iter()
+        .map(|(run, _, _)| game_dir(game).map(|dir| dir.join(run)))
+        .collect::<Result<Vec<_>>>()?;
+    if !confirm(
+        &mut io::stdin().lock(),
+        "Rewriting the runs to the new sections",
+        &paths,
+        Danger::High,
+    )? {
+        bail!("The saved runs still use the old sections");
+    }
+    let _lock = lock_game(game, true)?;
+    for (run, sections, approximate) in migrated {
+        if sections.is_empty() {
+            delete_run(game, &run)?;
+        } else {
+            save_run(game, &run, &sections)?;
+            let comparison = run == "pb.run" || run == "sum_of_best.run";
+            if comparison && approximate {
+                set_migration(game, &run, Some(migration.clone()))?;
+            }
+        }
+    }
+    println!("Updated the saved runs");
+
+    Ok(())
+}
+
+/// Checks that a run covers exactly the sections of the config
+fn validate_run(config: &GameConfig, sections: &[Section]) -> Result<()> {
+    ensure!(
+        sections.len() == config.sections.len(),
+        "has {} sections, expected {}",
+        sections.len(),
+        config.sections.len()
+    );
+    for (name, section) in config.sections.iter().zip(sections) {
+        ensure!(
+            *name == section.name,
+            "has section {:?} where {:?} was expected",
+            section.name,
+            name
+        );
+    }
+
+    Ok(())
+}
+
+/// Builds the cumulative sum of best from the fastest segments of complete runs
+fn sum_of_best(runs: &[Vec<Section>]) -> Option<Vec<Section>> {
+    let first = runs.first()?;
+
+    let segments = runs
+        .iter()
+        .map(|run| stats::segment_times(run))
+        .collect::<Vec<_>>();
+    let golds = (0..first.len())
+        .map(|i| {
+            segments
+                .iter()
+                .map(|run| run[i])
+                .reduce(stats::best_segment)
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(stats::cumulative(first.iter().map(|s| &s.name), &golds))
+}
+
+/// Builds a cumulative run from the mean segment times of complete runs
+fn average(runs: &[Vec<Section>]) -> Option<Vec<Section>> {
+    let first = runs.first()?;
+
+    let mut result = Vec::new();
+    let mut total = 0;
+    for i in 0..first.len() {
+        let segments = runs
+            .iter()
+            .map(|run| (run[i].time - if i == 0 { 0 } else { run[i - 1].time }) as u64)
+            .sum::<u64>();
+        total += (segments / runs.len() as u64) as u32;
+        result.push(Section {
+            name: first[i].name.clone(),
+            time: total,
+        });
+    }
+
+    Some(result)
+}
+
+/// The median segment of every section in the history. Skipped segments of 0ms, segments
+/// during which the system clock jumped and golds that were rejected as implausible are left
+/// out. None if a section has no segments left.
+fn median_run(config: &GameConfig, history: &[HistoricalRun]) -> Result<Option<Vec<Section>>> {
+    let game = &config.directory_name;
+    let rejected = load_rejected_golds(game)?;
+    let mut segments = vec![Vec::new(); config.sections.len()];
+    for run in history {
+        let clock_jumps = load_metadata(game, &run.name)?.clock_jumps;
+        let rejected = rejected.get(&run.name);
+        let mut start = 0;
+        for (section, times) in run.sections.iter().zip(&mut segments) {
+            let segment = section.time.saturating_sub(start);
+            let jumped = clock_jumps
+                .iter()
+                .any(|jump| (start..=section.time).contains(&jump.at));
+            let suspect = rejected.is_some_and(|golds| golds.contains(&section.name));
+            if segment > 0 && !jumped && !suspect {
+                times.push(segment);
+            }
+            start = section.time;
+        }
+    }
+
+    let medians: Option<Vec<u32>> = segments.iter_mut().map(|s| stats::median(s)).collect();
+    Ok(medians.map(|medians| stats::cumulative(&config.sections, &medians)))
+}
+
+/// Loads every dated run whose sections match the start of the config's sections.
+/// Runs that can't be read or belong to a different section list are skipped.
+fn load_history(config: &GameConfig) -> Result<Vec<HistoricalRun>> {
+    let history = index::runs(config)?
+        .into_iter()
+        .filter(|(_, entry)| entry.matches_config)
+        .map(|(name, entry)| HistoricalRun {
+            name,
+            sections: config
+                .sections
+                .iter()
+                .zip(entry.times)
+                .map(|(name, time)| Section {
+                    name: name.clone(),
+                    time,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(history)
+}
+
+/// Loads every dated run that covers all sections of the config
+fn load_complete_runs(config: &GameConfig) -> Result<Vec<Vec<Section>>> {
+    Ok(load_history(config)?
+        .into_iter()
+        .map(|run| run.sections)
+        .filter(|sections| sections.len() == config.sections.len())
+        .collect())
+}
+
+/// Lets the cumulative times of a saved run be corrected by hand: moving a split, dropping an
+/// accidental double split or adding a missed one. The names stay those of the config, so the
+/// times after a dropped or added split move to the neighbouring section. The original is kept
+/// as `<run>.bak`.
+fn edit_run(config: &GameConfig, selector: &str) -> Result<()> {
+    let game = &config.directory_name;
+    let run_name = resolve_run_selector(game, selector)?;
+    let path = run_location(game, &run_name)?;
+    let original = load_run(game, &run_name)?.context("Run does not exist")?;
+    ensure!(
+        original.len() <= config.sections.len()
+            && original
+                .iter()
+                .zip(&config.sections)
+                .all(|(section, name)| section.name == *name),
+        "{} doesn't match the sections of the config, it can only be edited by hand",
+        run_name
+    );
+
+    let mut times: Vec<u32> = original.iter().map(|s| s.time).collect();
+    let width = config.sections.iter().map(|s| s.chars().count()).max();
+    let width = width.unwrap_or(0);
+    loop {
+        println!();
+        let mut last = 0;
+        for (i, &time) in times.iter().enumerate() {
+            println!(
+                "{:>3}. {:width$}  {}  (+{})",
+                i + 1,
+                config.sections[i],
+                format_run_time(time),
+                format_run_time(time.saturating_sub(last))
+            );
+            last = time;
+        }
+
+        let answer = ask(
+            &mut io::stdin().lock(),
+            "[t]ime <n> <time>, [m]erge <n>, [s]plit <n> <time>, [w]rite or [q]uit: ",
+        )?;
+        let words: Vec<&str> = answer.split_whitespace().collect();
+        let edit = match words[..] {
+            [] | ["q" | "quit"] => {
+                println!("Nothing written");
+                return Ok(());
+            }
+            ["w" | "write"] => break,
+            ["t" | "time", n, time] => edit_time(&mut times, n, time),
+            ["m" | "merge", n] => edit_merge(&mut times, n),
+            ["s" | "split", n, time] => edit_split(&mut times, config.sections.len(), n, time),
+            _ => Err(anyhow!("Unknown command {:?}", answer)),
+        };
+        if let Err(e) = edit {
+            println!("{:#}", e);
+        }
+    }
+
+    let edited: Vec<Section> = times
+        .iter()
+        .zip(&config.sections)
+        .map(|(&time, name)| Section {
+            name: name.clone(),
+            time,
+        })
+        .collect();
+    let backup = format!("{}.bak", run_name);
+    if !confirm(
+        &mut io::stdin().lock(),
+        &format!(
+            "Writing the edited {}, keeping the original as {}",
+            run_name, backup
+        ),
+        &[path.clone(), run_location(game, &backup)?],
+        Danger::Low,
+    )? {
+        println!("Nothing written");
+        return Ok(());
+    }
+    {
+        let _lock = lock_game(game, true)?;
+        save_run(game, &backup, &original)
+            .with_context(|| format!("Failed to back up {}", path.display()))?;
+        save_run(game, &run_name, &edited)?;
+    }
+    println!("Wrote {}", path.display());
+
+    // The comparisons may contain the old times or be beaten by the new ones
+    let pb = load_run(game, "pb.run")?;
+    let sob = load_run(game, "sum_of_best.run")?;
+    let from_pb = |run: &[Section]| {
+        pb.as_ref().is_some_and(|pb| {
+            let times = |run: &[Section]| run.iter().map(|s| s.time).collect::<Vec<_>>();
+            times(pb) == times(run)
+        })
+    };
+    let beats_pb = edited.len() == config.sections.len()
+        && pb
+            .as_ref()
+            .and_then(|pb| pb.last())
+            .is_none_or(|pb| edited.last().is_some_and(|s| s.time < pb.time));
+    let golds = sob.as_ref().map(|sob| stats::segment_times(sob));
+    let touches_sob = |run: &[Section], beat: bool| {
+        let segments = stats::segment_times(run);
+        golds.as_ref().is_some_and(|golds| {
+            segments
+                .iter()
+                .zip(golds)
+                .any(|(segment, gold)| segment == gold || beat && segment < gold)
+        })
+    };
+
+    let mut stale = Vec::new();
+    if run_name != "pb.run" && run_name != "sum_of_best.run" && (from_pb(&original) || beats_pb) {
+        stale.push("pb.run");
+    }
+    if run_name != "sum_of_best.run"
+        && (touches_sob(&original, false) || touches_sob(&edited, true))
+    {
+        stale.push("sum_of_best.run");
+    }
+    for run in stale {
+        let answer = ask(
+            &mut io::stdin().lock(),
+            &format!(
+                "{} may be affected, rebuild it from the complete runs? [y/N]: ",
+                run
+            ),
+        )?;
+        if answer == "y" || answer == "yes" {
+            let _lock = lock_game(game, true)?;
+            match rebuild_comparison(config, run)? {
+                Some(_) => println!("Rebuilt {}", run),
+                None => println!("No complete runs to rebuild {} from", run),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a 1-based section number of the run being edited
+fn edit_index(times: &[u32], n: &str) -> Result<usize> {
+    match n.parse::<usize>() {
+        Ok(n) if (1..=times.len()).contains(&n) => Ok(n - 1),
+        _ => bail!("{:?} is not a section number from 1 to {}", n, times.len()),
+    }
+}
+
+/// Moves the end of a section, which has to stay between its neighbours
+fn edit_time(times: &mut [u32], n: &str, time: &str) -> Result<()> {
+    let i = edit_index(times, n)?;
+    let time = parse_time(time)?;
+    let after = i.checked_sub(1).map_or(0, |i| times[i]);
+    let before = times.get(i + 1).copied().unwrap_or(u32::MAX);
+    ensure!(
+        after <= time && time <= before,
+        "The time has to be between the neighbouring splits"
+    );
+    times[i] = time;
+    Ok(())
+}
+
+/// Drops the split that ended a section, so the next section covers both
+fn edit_merge(times: &mut Vec<u32>, n: &str) -> Result<()> {
+    let i = edit_index(times, n)?;
+    ensure!(
+        i + 1 < times.len(),
+        "The last section has no next one to merge with"
+    );
+    times.remove(i);
+    Ok(())
+}
+
+/// Adds a split that ended a section early, the rest of it becomes the next section
+fn edit_split(times: &mut Vec<u32>, sections: usize, n: &str, time: &str) -> Result<()> {
+    let i = edit_index(times, n)?;
+    ensure!(times.len() < sections, "The run already has every section");
+    let time = parse_time(time)?;
+    let after = i.checked_sub(1).map_or(0, |i| times[i]);
+    ensure!(
+        after <= time && time <= times[i],
+        "The split has to be inside the section"
+    );
+    times.insert(i, time);
+    Ok(())
+}
+
+/// Looks for inconsistencies in the saved data of a game and describes each one
+fn check_game(config: &GameConfig) -> Result<Vec<String>> {
+    let game = &config.directory_name;
+    let mut problems = Vec::new();
+
+    let pb = load_run(game, "pb.run")?;
+    let sum_of_best_sections = load_run(game, "sum_of_best.run")?;
+    for (run, sections) in [("pb.run", &pb), ("sum_of_best.run", &sum_of_best_sections)] {
+        if let Some(sections) = sections {
+            if let Err(e) = validate_run(config, sections) {
+                problems.push(format!("{} {}", run, e));
+            }
+            if sections.windows(2).any(|w| w[1].time < w[0].time) {
+                problems.push(format!("{} has times that go backwards", run));
+            }
+        }
+    }
+
+    if let Some(sum_of_best_sections) = &sum_of_best_sections {
+        let stored_golds = stats::segment_times(sum_of_best_sections);
+        let history = load_history(config)?;
+
+        // Every segment of every saved run should have been considered for the golds, with
+        // the first segment timed like the sum of best's when only one of them was retimed
+        let sob_offset = retime_offset(&load_metadata(game, "sum_of_best.run")?);
+        let mut expected_golds = stored_golds.clone();
+        for run in &history {
+            let mut segments = stats::segment_times(&run.sections);
+            let offset = sob_offset - retime_offset(&load_metadata(game, &run.name)?);
+            if let Some(first) = segments.first_mut() {
+                *first = (*first as i64 + offset).max(0) as u32;
+            }
+            for (i, segment) in segments.into_iter().enumerate() {
+                if let Some(gold) = expected_golds.get_mut(i) {
+                    if stats::beats_gold(segment, *gold) {
+                        problems.push(format!(
+                            "sum_of_best.run misses the gold {} of {} in {}",
+                            format_run_time(segment),
+                            config.sections[i],
+                            run.name
+                        ));
+                        *gold = segment;
+                    }
+                }
+            }
+        }
+
+        let final_time = sum_of_best_sections.last().map_or(0, |s| s.time);
+        let total = expected_golds.iter().sum::<u32>();
+        if final_time != total {
+            problems.push(format!(
+                "sum_of_best.run ends at {} but the golds add up to {}",
+                format_run_time(final_time),
+                format_run_time(total)
+            ));
+        }
+
+        if let Some(pb_final) = pb.as_ref().and_then(|pb| pb.last()) {
+            if pb_final.time < final_time {
+                problems.push(format!(
+                    "pb.run ({}) is faster than sum_of_best.run ({})",
+                    format_run_time(pb_final.time),
+                    format_run_time(final_time)
+                ));
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Every time of a run moved by `offset` milliseconds. Only the first section may be clamped
+/// at zero, an offset that makes later sections end at or before the one before them is
+/// refused.
+fn retimed(sections: &[Section], offset: i64) -> Result<Vec<Section>> {
+    let mut retimed: Vec<Section> = Vec::new();
+    for (i, section) in sections.iter().enumerate() {
+        let time = (section.time as i64 + offset).clamp(0, u32::MAX as i64) as u32;
+        if let Some(previous) = retimed.last() {
+            ensure!(
+                time > previous.time || section.time <= sections[i - 1].time,
+                "{} would end at {}, not after {}",
+                section.name,
+                format_run_time(time),
+                previous.name
+            );
+        }
+        retimed.push(Section {
+            name: section.name.clone(),
+            time,
+        });
+    }
+
+    Ok(retimed)
+}
+
+/// An offset like "+0m04.200s"
+fn format_offset(offset: i64) -> String {
+    let sign = if offset < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_run_time(offset.unsigned_abs() as u32))
+}
+
+/// Moves the times of pb.run, sum_of_best.run and optionally every dated run by `offset`
+/// milliseconds, after the timing rules changed. The old files are kept as `.bak` and the
+/// retime is noted in the metadata.
+fn retime(config: &GameConfig, offset: i64, all_runs: bool, dry_run: bool) -> Result<()> {
+    let game = &config.directory_name;
+    let _lock = lock_game(game, !dry_run)?;
+
+    let mut runs = vec!["pb.run".to_owned(), "sum_of_best.run".to_owned()];
+    if all_runs {
+        runs.extend(list_runs(game)?);
+    }
+    let mut changes = Vec::new();
+    for run in runs {
+        let Some(sections) = load_run(game, &run)? else {
+            continue;
+        };
+        let new_sections = retimed(&sections, offset)
+            .with_context(|| format!("Retiming {} by {}", run, format_offset(offset)))?;
+        changes.push((run, sections, new_sections));
+    }
+    ensure!(!changes.is_empty(), "{} has no runs to retime", game);
+
+    let width = changes.iter().map(|(run, _, _)| run.len()).max();
+    let width = width.unwrap_or(0);
+    for (run, old, new) in &changes {
+        let time = |sections: &[Section], i: usize| format_run_time(sections[i].time);
+        let last = old.len() - 1;
+        println!(
+            "{:width$}  {} {} -> {}, ends at {} -> {}",
+            run,
+            old[0].name,
+            time(old, 0),
+            time(new, 0),
+            time(old, last),
+            time(new, last)
+        );
+    }
+    if dry_run {
+        println!("Dry run, nothing written");
+        return Ok(());
+    }
+
+    let store = store::open(game)?;
+    let paths: Vec<PathBuf> = changes
+        .iter()
+        .map(|(run, _, _)| store.location(run))
+        .collect();
+    if !confirm(
+        &mut io::stdin().lock(),
+        &format!(
+            "Retiming {} runs by {}, keeping the originals as .bak",
+            changes.len(),
+            format_offset(offset)
+        ),
+        &paths,
+        Danger::Low,
+    )? {
+        println!("Nothing written");
+        return Ok(());
+    }
+
+    let date = chrono::Local::now().date_naive();
+    for (run, old, new) in &changes {
+        store
+            .save_run(&format!("{}.bak", run), old)
+            .with_context(|| format!("Failed to back up {}", store.location(run).display()))?;
+        save_run(game, run, new)?;
+
+        let mut metadata = load_metadata(game, run)?;
+        metadata.retimes.push(Retime { date, offset });
+        // The run still started at the same moment in its recording
+        metadata.started_at = metadata
+            .started_at
+            .map(|start| start - chrono::Duration::milliseconds(offset));
+        save_metadata(game, run, &metadata)?;
+    }
+    println!("Retimed {} runs", changes.len());
+
+    Ok(())
+}
+
+/// The sum of the offsets a run was retimed by
+fn retime_offset(metadata: &RunMetadata) -> i64 {
+    metadata.retimes.iter().map(|retime| retime.offset).sum()
+}
 
-    ensure!(config.sections.len() > 0);
+/// Finds how late the bridge's start fires after a run really began. Every attempt pairs a
+/// press of Enter at the true start with the bridge's start closest to it, and the average
+/// latency becomes the game's `start_trigger_offset_ms`.
+fn calibrate(game: &str, attempts: usize) -> Result<()> {
+    let mut config = load_config(game)?;
+    let script = config
+        .bridge_script
+        .clone()
+        .with_context(|| format!("{} has no bridge_script to calibrate", game))?;
 
-    Ok(config)
+    let (starts, started) = std::sync::mpsc::channel();
+    let auto_start = config.auto_start;
+    let bridge = bridge::run(&config, &script, move |line| {
+        let start = match bridge::parse(line) {
+            bridge::Message::Start => true,
+            bridge::Message::Split(_) => auto_start,
+            bridge::Message::Other(line) => {
+                eprintln!("{}", line);
+                false
+            }
+            _ => false,
+        };
+        !start || starts.send(Instant::now()).is_ok()
+    })?;
+
+    println!(
+        "Start the game {} times. Press Enter the moment each run really begins and let the bridge start it as usual.",
+        attempts
+    );
+    let mut latencies = Vec::new();
+    while latencies.len() < attempts {
+        print!("Attempt {}/{}: ", latencies.len() + 1, attempts);
+        io::stdout().flush()?;
+        if io::stdin().read_line(&mut String::new())? == 0 {
+            bridge.kill();
+            bail!("Input ended before every attempt was done");
+        }
+        let pressed = Instant::now();
+
+        // The bridge may have started before or after the key press
+        let window = Duration::from_millis(CALIBRATION_WINDOW_MS);
+        let early = started
+            .try_iter()
+            .filter(|&start| pressed.duration_since(start) <= window)
+            .last();
+        let start = early.or_else(|| started.recv_timeout(window).ok());
+        let Some(start) = start else {
+            println!(
+                "  No start from the bridge within {}ms of the key press, try again",
+                CALIBRATION_WINDOW_MS
+            );
+            continue;
+        };
+        let latency = if start >= pressed {
+            start.duration_since(pressed).as_millis() as i64
+        } else {
+            -(pressed.duration_since(start).as_millis() as i64)
+        };
+        println!("  The bridge started {}ms after the key press", latency);
+        latencies.push(latency);
+    }
+    bridge.kill();
+
+    let offset = latencies.iter().sum::<i64>() / latencies.len() as i64;
+    let (min, max) = (latencies.iter().min(), latencies.iter().max());
+    println!(
+        "Average latency {}ms, from {}ms to {}ms",
+        offset,
+        min.unwrap_or(&0),
+        max.unwrap_or(&0)
+    );
+    if offset == config.start_trigger_offset_ms {
+        println!("start_trigger_offset_ms is already {}", offset);
+        return Ok(());
+    }
+    let action = format!(
+        "Setting start_trigger_offset_ms from {} to {} in",
+        config.start_trigger_offset_ms, offset
+    );
+    if confirm(
+        &mut io::stdin().lock(),
+        &action,
+        &[game_dir(game)?.join("config.toml")],
+        Danger::Low,
+    )? {
+        config.start_trigger_offset_ms = offset;
+        write_config(&config)?;
+    }
+
+    Ok(())
 }
 
-fn load_all_configs() -> Result<Vec<GameConfig>> {
-    let dirs = directories::ProjectDirs::from("", "", "speedy")
-        .ok_or(anyhow!("No home directory found"))?;
-    let data_dir = dirs.data_dir();
-    let mut results = Vec::new();
-    for game_dir in fs::read_dir(&data_dir)? {
-        let game = game_dir?
-            .file_name()
-            .into_string()
-            .ok()
-            .context("Invalid OsString")?;
-        if let Ok(config) = load_config(&game) {
-            results.push(config);
+/// Reverts what the most recent complete run did to pb.run and sum_of_best.run and moves it
+/// to `rejected/`. The PB before it is the previous run in `pb_history.log`, and every gold
+/// it set goes back to the latest earlier run that `golds.log` notes a gold of that section
+/// for. Refuses when those runs or log lines are missing.
+fn undo_last(config: &GameConfig) -> Result<()> {
+    let game = &config.directory_name;
+    let _lock = lock_game(game, true)?;
+
+    let mut runs = list_runs(game)?;
+    let mut found = None;
+    while let Some(name) = runs.pop() {
+        if let Some(sections) = load_run(game, &name)? {
+            if validate_run(config, &sections).is_ok() {
+                found = Some((name, sections));
+                break;
+            }
+        }
+    }
+    let (run, sections) = found.context("There is no complete run to undo")?;
+    // Unless older complete runs exist, the comparisons didn't exist before this run
+    let mut first = true;
+    for name in &runs {
+        if load_run(game, name)?.is_some_and(|s| validate_run(config, &s).is_ok()) {
+            first = false;
+            break;
         }
     }
 
-    Ok(results)
+    let golds_log = load_golds_log(game)?;
+    let golds = golds_log.get(&run).with_context(|| {
+        format!(
+            "golds.log has no line for {}, so which golds it set is unknown. Undoing needs the \
+             line written when the run was saved",
+            run
+        )
+    })?;
+    if let Some((last, _)) = golds_log.last_key_value() {
+        ensure!(
+            *last == run,
+            "golds.log notes {} after {}, only the most recent run can be undone",
+            last,
+            run
+        );
+    }
+
+    // None keeps the file, Some(None) deletes it
+    let mut new_sob: Option<Option<Vec<Section>>> = None;
+    if !golds.is_empty() && first {
+        new_sob = Some(None);
+    } else if !golds.is_empty() {
+        let sob = load_run(game, "sum_of_best.run")?
+            .filter(|sob| validate_run(config, sob).is_ok())
+            .context(
+                "sum_of_best.run is missing or doesn't match the config, rebuild it instead",
+            )?;
+        let mut segments = stats::segment_times(&sob);
+        for gold in golds {
+            let i = config
+                .sections
+                .iter()
+                .position(|s| s == gold)
+                .with_context(|| {
+                    format!(
+                        "{} set a gold in {}, which is no section anymore",
+                        run, gold
+                    )
+                })?;
+            let (holder, _) = golds_log
+                .range(..run.clone())
+                .rev()
+                .find(|(_, golds)| golds.contains(gold))
+                .with_context(|| {
+                    format!(
+                        "No run before {} has a gold in {} in golds.log. Undoing needs the line \
+                         of the run that held it",
+                        run, gold
+                    )
+                })?;
+            let held = load_run(game, holder)?
+                .filter(|held| held.get(i).is_some_and(|s| s.name == *gold))
+                .with_context(|| {
+                    format!(
+                        "{} held the gold in {} before, but it is missing or doesn't have that section",
+                        holder, gold
+                    )
+                })?;
+            segments[i] = stats::segment_times(&held)[i];
+        }
+        new_sob = Some(Some(stats::cumulative(config.sections.iter(), &segments)));
+    }
+
+    let pb_log = load_pb_log(game)?;
+    let mut new_pb: Option<Option<Vec<Section>>> = None;
+    if pb_log.last() == Some(&run) {
+        new_pb = Some(match pb_log.len().checked_sub(2).map(|i| &pb_log[i]) {
+            Some(previous) => Some(
+                load_run(game, previous)?
+                    .filter(|pb| validate_run(config, pb).is_ok())
+                    .with_context(|| {
+                        format!(
+                            "{} was the PB before {}, but it is missing or doesn't match the config",
+                            previous, run
+                        )
+                    })?,
+            ),
+            None if first => None,
+            None => bail!(
+                "pb_history.log has no PB before {}. Undoing needs the line of the run that was \
+                 PB before",
+                run
+            ),
+        });
+    } else {
+        let pb = load_run(game, "pb.run")?;
+        let times = |run: &[Section]| run.iter().map(|s| s.time).collect::<Vec<_>>();
+        ensure!(
+            pb.is_none_or(|pb| times(&pb) != times(&sections)),
+            "pb.run is {}, but pb_history.log doesn't say which run was PB before it",
+            run
+        );
+    }
+
+    let store = store::open(game)?;
+    let rejected = store.location("rejected");
+    let mut paths = Vec::new();
+    if new_pb.is_some() {
+        paths.push(store.location("pb.run"));
+    }
+    if new_sob.is_some() {
+        paths.push(store.location("sum_of_best.run"));
+    }
+    paths.push(store.location(&run));
+    if !confirm(
+        &mut io::stdin().lock(),
+        &format!(
+            "Reverting what {} changed and moving it to {}",
+            run,
+            rejected.display()
+        ),
+        &paths,
+        Danger::High,
+    )? {
+        println!("Nothing changed");
+        return Ok(());
+    }
+
+    for (file, sections) in [("pb.run", new_pb), ("sum_of_best.run", new_sob)] {
+        match sections {
+            Some(Some(sections)) => {
+                save_run(game, file, &sections)?;
+                println!(
+                    "{} is back at {}",
+                    file,
+                    format_run_time(sections.last().map_or(0, |s| s.time))
+                );
+            }
+            Some(None) => {
+                delete_run(game, file)?;
+                println!("Deleted {}, there was none before {}", file, run);
+            }
+            None => {}
+        }
+    }
+    store.rename_run(&run, &format!("rejected/{}", run))?;
+    remove_from_log(game, "golds.log", &run)?;
+    remove_from_log(game, "pb_history.log", &run)?;
+    println!("Moved {} to {}", run, rejected.display());
+
+    Ok(())
 }
 
-fn write_config(config: &GameConfig) -> Result<()> {
-    let dirs = directories::ProjectDirs::from("", "", "speedy")
-        .ok_or(anyhow!("No home directory found"))?;
-    let data_dir = dirs.data_dir();
-    let game_dir = data_dir.join(&config.directory_name);
+/// Moves everything a game's store holds to a store of another storage and switches the config
+/// over. The copy is compared with the original before the original is removed.
+fn migrate_storage(config: &mut GameConfig, to: Storage) -> Result<()> {
+    let game = config.directory_name.clone();
+    ensure!(
+        config.storage != to,
+        "{} already uses the {} storage",
+        game,
+        to.name()
+    );
+    let _lock = lock_game(&game, true)?;
+    let from = store::open(&game)?;
+    let target = store::open_as(&game, to)?;
+    ensure!(
+        target.names()?.is_empty(),
+        "{} already holds runs, move it away to migrate",
+        target.location("").display()
+    );
 
-    std::fs::create_dir_all(&game_dir)?;
+    let mut paths = vec![from.location(""), target.location("")];
+    paths.push(game_dir(&game)?.join("config.toml"));
+    if !confirm(
+        &mut io::stdin().lock(),
+        &format!(
+            "Moving {} runs of {} to the {} storage",
+            from.runs()?.len(),
+            game,
+            to.name()
+        ),
+        &paths,
+        Danger::Low,
+    )? {
+        println!("Nothing changed");
+        return Ok(());
+    }
 
-    let config_str = toml::to_string_pretty(config)?;
-    let config_path = game_dir.join("config.toml");
-    fs::write(config_path, &config_str)?;
+    let (runs, logs) = store::copy(&*from, &*target)?;
+    if let Err(e) = store::ensure_same(&*from, &*target) {
+        target.clear()?;
+        return Err(e).context("The copy differs, the runs stay where they were");
+    }
+    config.storage = to;
+    write_config(config)?;
+    from.clear()?;
+    if to == Storage::Files {
+        // Nothing is left in it
+        drop(from);
+        let _ = fs::remove_file(game_dir(&game)?.join("speedy.db"));
+    }
+    println!("Moved {} runs and {} logs of {}", runs, logs, game);
 
     Ok(())
 }
 
-fn load_run(game: &str, run: &str) -> Result<Option<Vec<Section>>> {
-    let dirs = directories::ProjectDirs::from("", "", "speedy")
-        .ok_or(anyhow!("No home directory found"))?;
-    let data_dir = dirs.data_dir();
-    let game_dir = data_dir.join(&game);
-    let file_path = game_dir.join(run);
+/// Recreates pb.run or sum_of_best.run from the complete dated runs
+fn rebuild_comparison(config: &GameConfig, run: &str) -> Result<Option<Vec<Section>>> {
+    let complete_runs = load_complete_runs(config)?;
 
-    let file = if let Ok(file) = File::open(file_path) {
-        file
-    } else {
+    let rebuilt = match run {
+        "pb.run" => complete_runs
+            .into_iter()
+            .min_by_key(|sections| sections.last().map(|s| s.time)),
+        "sum_of_best.run" => sum_of_best(&complete_runs),
+        _ => bail!("{} is not a comparison file", run),
+    };
+
+    if let Some(sections) = &rebuilt {
+        save_run(&config.directory_name, run, sections)?;
+    }
+
+    Ok(rebuilt)
+}
+
+/// The versions of pb.run and sum_of_best.run of a game, which change whenever they are
+/// written, None for missing files
+fn comparison_versions(game: &str) -> Result<[Option<String>; 2]> {
+    let store = store::open(game)?;
+    let version = |run: &str| store.version(run).ok().flatten();
+
+    Ok([version("pb.run"), version("sum_of_best.run")])
+}
+
+/// Loads a comparison file, asking what to do if it doesn't match the config
+fn load_comparison(config: &GameConfig, run: &str) -> Result<Option<Vec<Section>>> {
+    let game = &config.directory_name;
+    let error = match load_run(game, run) {
+        Ok(Some(sections)) => match validate_run(config, &sections) {
+            Ok(()) => return Ok(Some(sections)),
+            Err(e) => e,
+        },
+        Ok(None) => return Ok(None),
+        Err(e) => e,
+    };
+
+    println!("{}/{} is unusable: {}", game, run, error);
+    let answer = ask(
+        &mut io::stdin().lock(),
+        "[c]ontinue without it, [d]elete it or [r]ebuild it from saved runs? [C/d/r]: ",
+    )?;
+    match &*answer.to_lowercase() {
+        "d" | "delete" => {
+            if confirm(
+                &mut io::stdin().lock(),
+                "Deleting",
+                &[run_location(game, run)?],
+                Danger::High,
+            )? {
+                delete_run(game, run)?;
+                println!("Deleted {}/{}", game, run);
+            }
+            Ok(None)
+        }
+        "r" | "rebuild" => {
+            let rebuilt = rebuild_comparison(config, run)?;
+            if rebuilt.is_some() {
+                println!("Rebuilt {}/{}", game, run);
+            } else {
+                println!(
+                    "No complete runs to rebuild {} from, continuing without it",
+                    run
+                );
+            }
+            Ok(rebuilt)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn bridge_state_name(state: bridge::State) -> &'static str {
+    match state {
+        bridge::State::Running => "running",
+        bridge::State::Stopped => "stopped",
+        bridge::State::Exited => "exited",
+    }
+}
+
+/// Draws lines in a box on top of whatever is on screen, starting at row `top`
+fn draw_overlay(engine: &mut ConsoleEngine, top: i32, lines: &[String]) {
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let border = format!("+{}+", "-".repeat(width + 2));
+
+    engine.print_fbg(0, top, &border, FG, BG);
+    for (i, line) in lines.iter().enumerate() {
+        let y = top + 1 + i as i32;
+        engine.print_fbg(0, y, &format!("| {:width$} |", line, width = width), FG, BG);
+    }
+    engine.print_fbg(0, top + 1 + lines.len() as i32, &border, FG, BG);
+}
+
+/// Converts the colors of everything drawn so far to the theme's and then to ones the
+/// terminal can show
+fn recolor(engine: &mut ConsoleEngine, theme: &Theme, mode: ColorMode) {
+    if theme.is_default() && mode == ColorMode::Truecolor {
+        return;
+    }
+
+    for y in 0..engine.get_height() as i32 {
+        for x in 0..engine.get_width() as i32 {
+            if let Ok(mut pixel) = engine.get_pxl(x, y) {
+                pixel.fg = mode.apply(theme.apply(pixel.fg));
+                pixel.bg = mode.apply(theme.apply(pixel.bg));
+                engine.set_pxl(x, y, pixel);
+            }
+        }
+    }
+}
+
+/// Opens a single game's run with its PB as the comparison
+fn start_run(
+    game: &str,
+    no_gold: bool,
+    goal: Option<String>,
+    practice: bool,
+    accessible: Option<announce::Verbosity>,
+    race_start: Option<chrono::DateTime<chrono::Local>>,
+    settings: &SettingsOverrides,
+) -> Result<()> {
+    let mut app = RunApp::for_game(game, no_gold, settings)?;
+    app.set_goal(goal);
+    app.practice = practice;
+    app.accessible = accessible;
+    app.race_start = race_start;
+    run_app(app)
+}
+
+fn parse_time_of_day(time: &str) -> Result<chrono::NaiveTime> {
+    chrono::NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(time, "%H:%M"))
+        .with_context(|| format!("{:?} is not a time like 21:30:00", time))
+}
+
+/// Today's date at a race start time, which has to be still ahead
+fn race_start_date(time: chrono::NaiveTime) -> Result<chrono::DateTime<chrono::Local>> {
+    let now = chrono::Local::now();
+    let start = now
+        .date_naive()
+        .and_time(time)
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .with_context(|| format!("{} doesn't exist today", time))?;
+    ensure!(
+        start > now,
+        "The race start {} has already passed, it is {} now",
+        time,
+        now.format("%H:%M:%S")
+    );
+    Ok(start)
+}
+
+/// Asks for the session's goal if the `ask_goal` setting is on, None if skipped
+fn ask_goal(game: &str, cli: &SettingsOverrides) -> Result<Option<String>> {
+    let config = load_config(game)?;
+    let settings = Settings::resolve(&[&settings::load_global()?, &config.settings, cli])?;
+    if !settings.ask_goal || !io::stdin().is_terminal() {
         return Ok(None);
+    }
+    let goal = ask(
+        &mut io::stdin().lock(),
+        "Goal for this session, like \"get 2 runs to Boss\" (enter to skip): ",
+    )?;
+    Ok((!goal.is_empty()).then_some(goal))
+}
+
+/// Notes the session's goal in the game's `sessions.log` and prints how it went
+fn finish_goal(app: &RunApp) -> Result<()> {
+    let Some(goal) = &app.goal else {
+        return Ok(());
     };
+    println!("Goal: {}", goal.status());
+    let mut file = open_log(&app.config.directory_name, "sessions.log")?;
+    writeln!(
+        file,
+        "{}\t{}\t{}",
+        app.date().format("%Y-%m-%dT%H:%M:%S"),
+        match (goal.is_tracked(), goal.is_done()) {
+            (false, _) => "text",
+            (true, true) => "done",
+            (true, false) => "open",
+        },
+        goal.status()
+    )?;
 
-    let file = BufReader::new(file);
+    Ok(())
+}
 
-    let mut sections = Vec::new();
-    for line in file.lines() {
-        let line = line.context("Failed to read line in run file")?;
+/// Plays a script against a game's run without a terminal, audio, signals or bridge, for
+/// reproducing what the timer does at exact times. Whatever is unsaved at the end is saved
+/// like on SIGTERM.
+fn play_script(
+    game: &str,
+    no_gold: bool,
+    accept_golds: bool,
+    goal: Option<String>,
+    settings: &SettingsOverrides,
+    script: &Path,
+) -> Result<()> {
+    let commands = script::load(script)?;
+    let mut app = RunApp::for_game(game, no_gold, settings)?;
+    app.clock = Some(script::Clock::new());
+    app.accept_golds = accept_golds;
+    app.set_goal(goal);
 
-        // Lines look like this: "escape01: 20m01.212s
-        let re = Regex::new(r"^(.*): (\d*)m(\d{2})\.(\d{3})s$").unwrap();
-        let cap = re.captures(&line).context("Invalid run file")?;
+    let app = Arc::new(RwLock::new(app));
+    let mut last_saved = None;
+    for command in commands {
+        {
+            let app = &mut app.write().unwrap();
+            app.clock.as_mut().expect("scripts set the clock").at = command.at;
+            app.confirm_armed_start();
+        }
+        let result = match &command.action {
+            // Like a start of the bridge, which only restarts a run that just began
+            script::Action::Start => RunApp::bridge_start(&app, None),
+            script::Action::Split => RunApp::handle_signal(&app, None, SIGUSR1),
+            script::Action::Reset => RunApp::handle_signal(&app, None, SIGUSR2),
+            script::Action::Checkpoint(name) => {
+                app.write().unwrap().checkpoint(name.as_deref());
+                Ok(())
+            }
+            script::Action::Stop => break,
+        };
+        result.with_context(|| format!("at {}ms {:?}", command.at, command.action))?;
 
-        let section_name = cap[1].to_owned();
-        let section_time_ms = min_sec_mil_to_millis(
-            cap[2].parse().unwrap(),
-            cap[3].parse().unwrap(),
-            cap[4].parse().unwrap(),
-        );
+        let saved_run = app.read().unwrap().saved_run.clone();
+        if saved_run.is_some() && saved_run != last_saved {
+            println!(
+                "at {}ms saved {}",
+                command.at,
+                saved_run.as_deref().unwrap_or_default()
+            );
+        }
+        last_saved = saved_run;
+    }
 
-        sections.push(Section {
-            name: section_name,
-            time: section_time_ms,
-        });
+    let mut app = app.write().unwrap();
+    if let Some(run) = app.save_on_shutdown()? {
+        println!("Stopped, saved {}", run);
+    }
+    if app.pending_golds.is_some() {
+        app.resolve_pending_golds(false)?;
+        println!("Golds that would be asked about were not kept, see --accept-golds");
     }
+    let spurious = app.session.iter().map(|game| game.spurious).sum::<usize>();
+    if spurious > 0 {
+        println!("{} splits after the finish were ignored", spurious);
+    }
+    finish_goal(&app)?;
 
-    Ok(Some(sections))
+    Ok(())
 }
 
-fn save_run(game: &str, run: &str, sections: &[Section]) -> Result<()> {
-    let dirs = directories::ProjectDirs::from("", "", "speedy")
-        .ok_or(anyhow!("No home directory found"))?;
-    let data_dir = dirs.data_dir();
-    let game_dir = data_dir.join(game);
+/// Starts the signal handler, bridge and UI for a prepared run
+fn run_app(app: RunApp) -> Result<()> {
+    let app = Arc::new(RwLock::new(app));
 
-    let file_path = game_dir.join(run);
-    let mut file = BufWriter::new(File::create(file_path)?);
+    let sounds = Arc::new(Mutex::new(RunApp::new_sounds(&app)?));
+    RunApp::spawn_signal_handler(Arc::clone(&app), Arc::clone(&sounds))?;
+    RunApp::spawn_focus_watcher(Arc::clone(&app));
+    let mut bridge = RunApp::spawn_bridge_handler(Arc::clone(&app), Arc::clone(&sounds))?;
+    let accessible = app.read().unwrap().accessible;
+    // Read once for the whole session, it outlives switching games
+    let (commands, input) = mpsc::channel();
+    if accessible.is_some() {
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    return;
+                };
+                if commands.send(line.trim().to_lowercase()).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    let exit = loop {
+        let exit = match accessible {
+            Some(verbosity) => RunApp::run_accessible(&app, verbosity, &input)?,
+            None => RunApp::launch_ui(&app)?,
+        };
+        let UiExit::SwitchGame(game) = exit else {
+            break exit;
+        };
 
-    for section in sections {
-        let (min, sec, mil) = millis_to_min_sec_mil(section.time);
-        writeln!(file, "{}: {}m{:02}.{:03}s", section.name, min, sec, mil)?;
+        // The bridge of the old game is replaced by the new game's
+        if let Some(bridge) = bridge.take() {
+            bridge.kill();
+        }
+        let (switched, by_bridge) = {
+            let app = app.read().unwrap();
+            (app.switch_game(&game), app.category_switch.clone())
+        };
+        match switched {
+            Ok(mut switched) => {
+                if by_bridge.is_some() {
+                    let category = switched.config.category.clone().unwrap_or_default();
+                    let old = app.read().unwrap().config.directory_name.clone();
+                    switched.settings_warnings.push(format!(
+                        "The bridge switched to the category {}, from {}",
+                        category, old
+                    ));
+                }
+                *app.write().unwrap() = switched
+            }
+            Err(e) => {
+                let mut app = app.write().unwrap();
+                app.category_switch = None;
+                app.error = Some(format!("Switching to {} failed: {:#}", game, e))
+            }
+        }
+        bridge = RunApp::spawn_bridge_handler(Arc::clone(&app), Arc::clone(&sounds))?;
+    };
+    if let Some(bridge) = bridge {
+        bridge.kill();
+    }
+    // A run saved in the background is finished first, exiting would cut it off
+    if app.read().unwrap().saving {
+        println!("Waiting for the run to be saved");
+        while app.read().unwrap().saving {
+            std::thread::sleep(Duration::from_millis(50));
+        }
     }
 
-    file.flush()?;
+    {
+        let app = app.read().unwrap();
+        if let (Some(notice), Some(previous_pb)) = (&app.notice, &app.previous_pb) {
+            println!("{}", notice);
+            for (old, new) in previous_pb.iter().zip(&app.current_sections) {
+                let delta = new.time as i64 - old.time as i64;
+                println!(
+                    "{}: {} ({} by {})",
+                    new.name,
+                    format_run_time(new.time),
+                    if delta <= 0 { "ahead" } else { "behind" },
+                    format_run_time(delta.unsigned_abs() as u32)
+                );
+            }
+        }
+    }
+    if exit == UiExit::Shutdown {
+        let mut app = app.write().unwrap();
+        match app.save_on_shutdown() {
+            Ok(Some(run)) => println!("Stopped by a signal, saved {}", run),
+            Ok(None) => println!("Stopped by a signal"),
+            Err(e) => {
+                print_unsaved(&app);
+                return Err(e);
+            }
+        }
+    } else {
+        let app = app.read().unwrap();
+        if matches!(app.state, RunState::Finished { .. }) && app.saved_run.is_none() {
+            print_unsaved(&app);
+        }
+    }
+    if exit == UiExit::IdleTimeout {
+        let app = app.read().unwrap();
+        println!(
+            "No activity for {} hours, exiting",
+            app.settings.idle_timeout
+        );
+    }
+    let mut app = app.write().unwrap();
+    if app.pending_golds.is_some() {
+        app.resolve_pending_golds(false)?;
+        println!(
+            "The unconfirmed golds of the last run were not kept, recompute-sob brings them back"
+        );
+    }
+    if app.session.len() > 1 || app.session.iter().any(|game| game.spurious > 0) {
+        println!("This session:");
+        for game in &app.session {
+            let mut line = format!(
+                "  {}: {} finished, {} reset",
+                game.name, game.finished, game.resets
+            );
+            if game.spurious > 0 {
+                line += &format!(", {} splits after the finish ignored", game.spurious);
+            }
+            println!("{}", line);
+        }
+    }
+    finish_goal(&app)?;
 
     Ok(())
 }
 
-fn ask(q: &str) -> Result<String> {
+/// Prints a finished run that couldn't be saved, so it can be saved by hand
+fn print_unsaved(app: &RunApp) {
+    let name = unique_run_name(&app.config.directory_name, &app.start_date)
+        .unwrap_or_else(|_| format!("{}.run", app.start_date.format("%Y-%m-%dT%H:%M:%S")));
+    let path = run_location(&app.config.directory_name, &name).unwrap_or_default();
+    println!(
+        "The run could not be saved, to keep it write this to {}:",
+        path.display()
+    );
+    print!("{}", run_text(&app.current_sections));
+}
+
+/// How bad it is to confirm by accident
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Danger {
+    /// Answered with y
+    Low,
+    /// Data that can't be rebuilt is lost, the answer has to be typed out
+    High,
+}
+
+/// Set by `--yes`
+static ASSUME_YES: OnceLock<bool> = OnceLock::new();
+
+/// Whether confirmations are skipped, with `--yes` or `SPEEDY_ASSUME_YES`
+fn assume_yes() -> bool {
+    *ASSUME_YES
+        .get_or_init(|| std::env::var("SPEEDY_ASSUME_YES").is_ok_and(|v| !v.is_empty() && v != "0"))
+}
+
+/// Asks before modifying files, listing them, and reads the answer from `input`. Every
+/// destructive operation goes through here.
+fn confirm(
+    input: &mut impl BufRead,
+    action: &str,
+    paths: &[PathBuf],
+    danger: Danger,
+) -> Result<bool> {
+    println!("{}:", action);
+    for path in paths {
+        println!("  {}", path.display());
+    }
+    if assume_yes() {
+        println!("Confirmed by --yes");
+        return Ok(true);
+    }
+
+    let answer = match danger {
+        Danger::Low => ask(input, "Continue? [y/N]: ")?.to_lowercase(),
+        Danger::High => ask(input, "This can't be undone, type yes to continue: ")?,
+    };
+    Ok(match danger {
+        Danger::Low => answer == "y" || answer == "yes",
+        Danger::High => answer == "yes",
+    })
+}
+
+/// Asks a question and reads the answer from `input`, usually stdin
+fn ask(input: &mut impl BufRead, q: &str) -> Result<String> {
     print!("{}", q);
 
     io::stdout().flush()?;
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+
+    Ok(answer.trim().to_owned())
+}
+
+/// Asks for the sections of a new game and writes its config, false if cancelled
+///
+/// The name and sections are only asked for if they aren't given. Section names can contain a
+/// range like `Star {1..70}`, which is expanded into numbered sections.
+fn new_game(game: &str, full_game_name: Option<String>, templates: &[String]) -> Result<bool> {
+    validate_name("game", game)?;
+    println!("Registering new game");
+    let input = &mut io::stdin().lock();
+    let full_game_name = match full_game_name {
+        Some(name) => name,
+        None => ask(input, "Full game name: ")?,
+    };
+
+    let mut section_names = Vec::new();
+    for template in templates {
+        let names = expand_template(template)?;
+        println!("{}", preview_names(&names));
+        section_names.extend(names);
+    }
+    if templates.is_empty() {
+        println!("Enter section names, ranges like \"Star {{1..70}}\" are numbered (CTRL-D or write empty line to stop)");
+        loop {
+            let name = ask(input, &format!("section{}: ", section_names.len() + 1))?;
+            if name.is_empty() {
+                break;
+            }
+            let names = match expand_template(&name) {
+                Ok(names) => names,
+                Err(e) => {
+                    println!("{:#}", e);
+                    continue;
+                }
+            };
+            if names.len() > 1 {
+                println!("{}", preview_names(&names));
+                if !answered_yes(&ask(
+                    input,
+                    &format!("Add these {} sections? [Y/n]: ", names.len()),
+                )?) {
+                    continue;
+                }
+            }
+            section_names.extend(names);
+        }
+    }
+    if section_names.is_empty() {
+        println!("\nGame creation cancelled");
+        return Ok(false);
+    }
+
+    let bridge_script_raw = ask(input, "\nOptional: Enter bridge script path: ")?;
+
+    let bridge_script = if bridge_script_raw.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(bridge_script_raw))
+    };
+
+    let ask_save = ask(
+        input,
+        &format!(
+            "Do you want to create {} with {} sections? [Y/n]: ",
+            game,
+            section_names.len()
+        ),
+    )?;
+
+    let config_path = game_dir(game)?.join("config.toml");
+    let save = answered_yes(&ask_save)
+        && (!config_path.exists()
+            || confirm(
+                input,
+                &format!("{} already exists, replacing its config", game),
+                &[config_path],
+                Danger::High,
+            )?);
+
+    if save {
+        let config = GameConfig {
+            version: 1,
+            directory_name: game.to_owned(),
+            full_game_name,
+            category: None,
+            route: None,
+            bridge_script,
+            sections: section_names,
+            storage: Storage::default(),
+            triggers: Vec::new(),
+            ignore_mismatched_triggers: false,
+            bridge: Default::default(),
+            bridge_after_finish: Default::default(),
+            auto_start: false,
+            start_trigger_offset_ms: 0,
+            sum_of_best_column: false,
+            median_column: false,
+            dual_delta: false,
+            highlight: Vec::new(),
+            highlight_tone: false,
+            sounds: SoundsConfig::default(),
+            comparison_schedule: Vec::new(),
+            targets: BTreeMap::new(),
+            settings: SettingsOverrides::default(),
+            warnings: Vec::new(),
+        };
+
+        write_config(&config)?;
+
+        println!("Done");
+        print_getting_started(game);
+        Ok(true)
+    } else {
+        println!("Game creation cancelled");
+        Ok(false)
+    }
+}
+
+/// Most sections a template may expand to, to catch typos like `{1..7000}`
+const MAX_TEMPLATE_SECTIONS: usize = 1000;
+
+/// Expands ranges like `{1..70}` in a section name into one name per number. Leading zeros
+/// like `{01..70}` pad the numbers to that width. Several ranges expand to every combination.
+fn expand_template(template: &str) -> Result<Vec<String>> {
+    let re = Regex::new(r"\{(\d+)\.\.(\d+)\}").unwrap();
+    let Some(captures) = re.captures(template) else {
+        return Ok(vec![template.to_owned()]);
+    };
+    let range = captures.get(0).unwrap();
+    let (first, last) = (&captures[1], &captures[2]);
+    let (start, end) = (first.parse::<usize>()?, last.parse::<usize>()?);
+    ensure!(
+        start <= end,
+        "The range {} in {:?} counts down",
+        range.as_str(),
+        template
+    );
+    let width = if first.starts_with('0') {
+        first.len()
+    } else {
+        0
+    };
+
+    let mut names = Vec::new();
+    for n in start..=end {
+        let name = format!(
+            "{}{:0width$}{}",
+            &template[..range.start()],
+            n,
+            &template[range.end()..]
+        );
+        names.extend(expand_template(&name)?);
+        ensure!(
+            names.len() <= MAX_TEMPLATE_SECTIONS,
+            "{:?} expands to more than {} sections",
+            template,
+            MAX_TEMPLATE_SECTIONS
+        );
+    }
+
+    Ok(names)
+}
+
+/// The first and last few of many names: "Star 1, Star 2, Star 3, …, Star 70 (70 sections)"
+fn preview_names(names: &[String]) -> String {
+    let shown = if names.len() <= 6 {
+        names.join(", ")
+    } else {
+        format!(
+            "{}, …, {}",
+            names[..3].join(", "),
+            names[names.len() - 3..].join(", ")
+        )
+    };
+    format!("{} ({} sections)", shown, names.len())
+}
+
+/// Explains how to split, for runs started from this process after creating a game
+fn print_getting_started(game: &str) {
+    let pid = std::process::id();
+    println!("\nWhile the run is open, start and split with:");
+    println!("    kill -USR1 {}", pid);
+    println!("and reset with:");
+    println!("    kill -USR2 {}", pid);
+    println!(
+        "Bind these to global hotkeys, or set bridge_script in {} to split from a script",
+        game_dir(game)
+            .map(|dir| dir.join("config.toml").display().to_string())
+            .unwrap_or_else(|_| "the game's config.toml".to_owned())
+    );
+}
 
-    Ok(input.trim().to_owned())
+/// Answers to [Y/n] questions, empty means yes
+fn answered_yes(answer: &str) -> bool {
+    ["y", "yes", "ja", "j", ""].contains(&&*answer.to_lowercase())
 }
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about=None)]
 #[command(propagate_version = true)]
 struct Args {
+    /// Where games are stored instead of the platform's data directory, their logs and caches
+    /// are kept there too
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+    /// Don't ask before modifying or deleting files, also set by SPEEDY_ASSUME_YES
+    #[arg(long, global = true)]
+    yes: bool,
+    /// Parse every run file instead of using the games' index.toml
+    #[arg(long, global = true)]
+    no_cache: bool,
     #[command(subcommand)]
     mode: Mode,
 }
@@ -757,119 +6578,1294 @@ struct Args {
 enum Mode {
     Run {
         game: String,
+        /// Keep this run's golds out of the sum of best, e.g. for races
+        #[arg(long)]
+        no_gold: bool,
+        /// Play timed commands like `at 1500ms split` with a made up clock instead of
+        /// opening the UI
+        #[arg(long, hide = true, value_name = "FILE")]
+        script: Option<PathBuf>,
+        /// Keep golds that would be asked about, for runs without the UI
+        #[arg(long)]
+        accept_golds: bool,
+        /// Pause while the game isn't focused, see --focus-backend. The run is saved, but
+        /// never as the PB.
+        #[arg(long)]
+        practice: bool,
+        /// What this session is for, like "get 2 runs to Boss", "gold Library" or "pb"
+        #[arg(long)]
+        goal: Option<String>,
+        /// Print short plain text announcements for screen readers instead of the UI, and
+        /// take commands as lines on stdin
+        #[arg(long)]
+        accessible: bool,
+        /// How much the accessible mode says about each split
+        #[arg(long, value_enum, default_value_t, requires = "accessible")]
+        verbosity: announce::Verbosity,
+        /// Count down to this local time, like 21:30:00, and start the run then. Racers who
+        /// pass the same time start together.
+        #[arg(long, value_name = "TIME", value_parser = parse_time_of_day, conflicts_with = "script")]
+        race_start: Option<chrono::NaiveTime>,
+        #[command(flatten)]
+        settings: SettingsOverrides,
+    },
+    /// Show a made up split table in the colors of the game's theme
+    Theme {
+        game: String,
+        /// Theme file to try instead of the configured one
+        #[arg(long, value_name = "FILE")]
+        theme: Option<PathBuf>,
     },
+    /// Run while racing a ghost of a saved run, the PB unless given
     Against {
+        game: String,
+        /// Run to race, like `pb`, `latest` or a run file name
         enemy: Option<String>,
+        /// Race splits adopted with `adopt-splits` by name, or a run file given by a path with
+        /// a separator, like `./friend.run`
+        #[arg(long, conflicts_with = "enemy")]
+        compare_file: Option<String>,
+        #[command(flatten)]
+        settings: SettingsOverrides,
+    },
+    /// Run several games back to back as one relay
+    Relay {
+        #[arg(required = true, num_args = 2..)]
+        games: Vec<String>,
+        /// Name of the relay's own comparison directory, defaults to the games joined by +
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        no_gold: bool,
+        #[command(flatten)]
+        settings: SettingsOverrides,
     },
     ListGames,
     NewGame {
         game: String,
+        /// Full name of the game, asked for if not given
+        #[arg(long)]
+        name: Option<String>,
+        /// Section names instead of asking for them, ranges like "Star {1..70}" are numbered.
+        /// Can be given several times.
+        #[arg(long = "sections-template", value_name = "TEMPLATE")]
+        sections_templates: Vec<String>,
     },
     ListRuns {
         game: String,
+        #[command(flatten)]
+        filter: RunFilter,
+    },
+    Stats {
+        game: String,
+        #[command(flatten)]
+        filter: RunFilter,
+        /// Only count runs timed on this machine
+        #[arg(long, value_name = "HOSTNAME")]
+        host: Option<String>,
+        /// Leave out runs timed on this machine
+        #[arg(long, value_name = "HOSTNAME")]
+        exclude_host: Option<String>,
+        /// Print the stats as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    Check {
+        game: String,
+    },
+    /// Convert a run for other timers or sites
+    Export {
+        game: String,
+        /// Run to export, defaults to the PB
+        run: Option<String>,
+        #[arg(long, value_enum, default_value = "exchange")]
+        format: export::Format,
+        /// Defaults to standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Include an anonymous ID of the run, to recognize it in later uploads
+        #[arg(long)]
+        run_id: bool,
+    },
+    /// Print a blank table of the sections to fill in by hand while practicing
+    Sheet {
+        game: String,
+        /// Defaults to standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Tables printed one after another, e.g. to fill a page
+        #[arg(long, default_value_t = 1)]
+        copies: usize,
+        /// Write Markdown tables for pasting into notes
+        #[arg(long)]
+        markdown: bool,
+        /// Fill in the targets of the config
+        #[arg(long)]
+        targets: bool,
+    },
+    /// Write a standalone HTML page with the stats of a game
+    Report {
+        game: String,
+        /// Defaults to <game>.html in the current directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write the sections, PB and sum of best of a game to one file for someone else
+    ShareSplits {
+        game: String,
+        /// Defaults to <game>-splits.txt in the current directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Keep the PB of a shared splits file as a comparison for `against --compare-file`
+    AdoptSplits {
+        game: String,
+        path: PathBuf,
+        /// Defaults to the name of the file
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Rebuild sum_of_best.run from all complete runs
+    RecomputeSob {
+        game: String,
+        /// Leave out runs that were saved with their golds suppressed
+        #[arg(long)]
+        exclude_suppressed: bool,
     },
     Show {
         game: String,
         run: Option<String>,
     },
+    /// Compare a run's timer to the wall clock, to check its timing
+    Audit {
+        game: String,
+        run: Option<String>,
+        /// Drift in milliseconds that is flagged
+        #[arg(long, default_value_t = 1000)]
+        threshold: u32,
+    },
+    /// Correct the split times of a saved run
+    EditRun {
+        game: String,
+        /// Run to edit, like `pb`, `latest` or a run file name
+        run: String,
+    },
+    /// Revert the PB and golds of the most recent complete run and move it to rejected/
+    UndoLast {
+        game: String,
+    },
+    /// Move the runs, metadata and logs of a game to another storage, like SQLite
+    Migrate {
+        game: String,
+        #[arg(value_enum)]
+        to: Storage,
+    },
+    /// Measure how late the bridge starts a run and move its starts back by that much
+    Calibrate {
+        game: String,
+        /// Starts to average over
+        #[arg(long, default_value_t = 3)]
+        attempts: usize,
+    },
+    /// Check config.toml, the settings file and the theme of a game, without touching its
+    /// runs. Fails if anything is wrong
+    ValidateConfig {
+        game: String,
+        /// Also fail on unknown keys, which are only warned about otherwise
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Move the times of the PB and sum of best by an offset, after the timing rules changed
+    Retime {
+        game: String,
+        /// Milliseconds to add to every time, negative if the timer starts later now
+        #[arg(allow_hyphen_values = true)]
+        offset_ms: i64,
+        /// Also retime every dated run
+        #[arg(long)]
+        all_runs: bool,
+        /// Only show what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show which sections of a run were golds when it was timed and which of them are
+    /// still part of the sum of best
+    Golds {
+        game: String,
+        run: Option<String>,
+    },
+    /// Print when each section of a run started, e.g. as chapters of its recording
+    Timestamps {
+        game: String,
+        run: Option<String>,
+        /// When the recording started, like 2024-05-01T19:58:30 or 19:58:30 on the run's day.
+        /// The times are printed relative to it instead of as wall clock times.
+        #[arg(long, value_name = "TIME")]
+        video_start: Option<String>,
+    },
+    /// Run the game's bridge script and show what it sends, without timing a run
+    Simulate {
+        game: String,
+    },
     Compare {
         game: String,
         a: Option<String>,
         b: Option<String>,
     },
+    /// Print the directories speedy reads and writes
+    Paths,
+    Completions {
+        shell: completions::Shell,
+    },
+    #[command(name = "__complete-games", hide = true)]
+    CompleteGames,
+    #[command(name = "__complete-runs", hide = true)]
+    CompleteRuns {
+        game: String,
+    },
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    if let Some(data_dir) = args.data_dir {
+        STATE_DIR
+            .set(data_dir.clone())
+            .expect("state directory is only set once");
+        CACHE_DIR
+            .set(data_dir.clone())
+            .expect("cache directory is only set once");
+        DATA_DIR
+            .set(data_dir)
+            .expect("data directory is only set once");
+    }
+    if args.yes {
+        ASSUME_YES.set(true).expect("--yes is only set once");
+    }
+    if args.no_cache {
+        index::DISABLED
+            .set(true)
+            .expect("--no-cache is only set once");
+    }
+    // A fresh install starts without one
+    if let Ok(data_dir) = data_dir() {
+        fs::create_dir_all(data_dir)
+            .with_context(|| format!("Failed to create {}", data_dir.display()))?;
+    }
 
     match args.mode {
-        Mode::Run { game } => {
-            let mut app = RunApp::prepare_run(load_config(&game)?)?;
+        Mode::Run {
+            game,
+            no_gold,
+            script,
+            accept_golds,
+            practice,
+            goal,
+            accessible,
+            verbosity,
+            race_start,
+            settings,
+        } => {
+            let race_start = race_start.map(race_start_date).transpose()?;
+            if let Some(script) = script {
+                return play_script(&game, no_gold, accept_golds, goal, &settings, &script);
+            }
+            if !game_dir(&game)?.join("config.toml").exists() {
+                let create = ask(
+                    &mut io::stdin().lock(),
+                    &format!("{} is not registered yet, create it? [Y/n]: ", game),
+                )?;
+                if !answered_yes(&create) || !new_game(&game, None, &[])? {
+                    return Ok(());
+                }
+                if !answered_yes(&ask(&mut io::stdin().lock(), "Start the run now? [Y/n]: ")?) {
+                    return Ok(());
+                }
+            }
+            let goal = match goal {
+                Some(goal) => Some(goal),
+                None => ask_goal(&game, &settings)?,
+            };
+            let accessible = accessible.then_some(verbosity);
+            start_run(
+                &game, no_gold, goal, practice, accessible, race_start, &settings,
+            )?;
+        }
+        Mode::Against {
+            game,
+            enemy,
+            compare_file,
+            settings,
+        } => {
+            let config = load_config(&game)?;
+            migrate_sections(&config)?;
+            let (ghost, path) = match compare_file {
+                Some(file) if is_game_path(&file) => {
+                    (load_run_file(Path::new(&file))?, PathBuf::from(file))
+                }
+                Some(file) => {
+                    let run = shared_run(&file)?;
+                    (load_run(&game, &run)?, run_location(&game, &run)?)
+                }
+                None => {
+                    let run = resolve_run_selector(&game, enemy.as_deref().unwrap_or("pb"))?;
+                    (load_run(&game, &run)?, run_location(&game, &run)?)
+                }
+            };
+            let ghost = ghost.context("Run does not exist")?;
+            let matches_config = ghost.len() <= config.sections.len()
+                && ghost
+                    .iter()
+                    .zip(&config.sections)
+                    .all(|(section, name)| section.name == *name);
+            ensure!(
+                matches_config,
+                "{} doesn't have the sections of the config",
+                path.display()
+            );
+
+            let mut app = RunApp::prepare_run(config, &settings)?;
+            if let Some(pb) = load_comparison(&app.config, "pb.run")? {
+                app.set_pb(pb)?;
+            }
+            app.ghost = Some(ghost);
+            run_app(app)?;
+        }
+        Mode::Relay {
+            games,
+            name,
+            no_gold,
+            settings,
+        } => {
+            let mut configs = Vec::new();
+            for game in &games {
+                configs.push(load_config(game)?);
+            }
+
+            // The relay as a whole is saved like a game of its own
+            let name = name.unwrap_or_else(|| games.join("+"));
+            validate_name("relay", &name)?;
+            let config = GameConfig {
+                version: 1,
+                // A path, so it isn't mistaken for a game of that name
+                directory_name: data_dir()?
+                    .join("relay")
+                    .join(&name)
+                    .to_string_lossy()
+                    .into_owned(),
+                full_game_name: configs
+                    .iter()
+                    .map(|c| c.full_game_name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" + "),
+                category: None,
+                route: None,
+                bridge_script: None,
+                sections: configs.iter().flat_map(|c| c.sections.clone()).collect(),
+                storage: Storage::default(),
+                // Only complete if every game names its triggers
+                triggers: if configs.iter().all(|c| !c.triggers.is_empty()) {
+                    configs.iter().flat_map(|c| c.triggers.clone()).collect()
+                } else {
+                    Vec::new()
+                },
+                ignore_mismatched_triggers: configs.iter().any(|c| c.ignore_mismatched_triggers),
+                bridge: Default::default(),
+                bridge_after_finish: Default::default(),
+                auto_start: configs.first().is_some_and(|c| c.auto_start),
+                start_trigger_offset_ms: configs.first().map_or(0, |c| c.start_trigger_offset_ms),
+                sum_of_best_column: false,
+                median_column: false,
+                dual_delta: false,
+                highlight: configs.iter().flat_map(|c| c.highlight.clone()).collect(),
+                highlight_tone: configs.iter().any(|c| c.highlight_tone),
+                sounds: SoundsConfig::default(),
+                comparison_schedule: Vec::new(),
+                targets: BTreeMap::new(),
+                settings: SettingsOverrides::default(),
+                warnings: Vec::new(),
+            };
+            write_config(&config)?;
+
+            let global = settings::load_global()?;
+            let mut legs = Vec::new();
+            let mut first_section = 0;
+            for config in configs {
+                let sections = config.sections.len();
+                legs.push(RelayLeg {
+                    settings: Settings::resolve(&[&global, &config.settings, &settings])?,
+                    pb_sections: load_comparison(&config, "pb.run")?,
+                    sum_of_best_sections: load_comparison(&config, "sum_of_best.run")?,
+                    config,
+                    first_section,
+                });
+                first_section += sections;
+            }
 
-            if let Some(pb) = load_run(&game, "pb.run")? {
+            let mut app = RunApp::prepare_run(config, &settings)?;
+            app.no_gold = no_gold;
+            app.relay = Some(legs);
+
+            if let Some(pb) = load_comparison(&app.config, "pb.run")? {
                 app.set_pb(pb)?;
             }
 
-            let app = Arc::new(RwLock::new(app));
+            run_app(app)?;
+        }
+        Mode::NewGame {
+            game,
+            name,
+            sections_templates,
+        } => {
+            if new_game(&game, name, &sections_templates)?
+                && answered_yes(&ask(&mut io::stdin().lock(), "Start a run now? [Y/n]: ")?)
+            {
+                start_run(
+                    &game,
+                    false,
+                    None,
+                    false,
+                    None,
+                    None,
+                    &SettingsOverrides::default(),
+                )?;
+            }
+        }
+        Mode::ListGames => {
+            let configs = load_all_configs()?;
+            if configs.is_empty() {
+                println!("No games registered yet");
+            } else {
+                let pbs = configs
+                    .iter()
+                    .map(|config| match load_run(&config.directory_name, "pb.run") {
+                        Ok(Some(pb_run)) => pb_run
+                            .last()
+                            .map(|s| format_final_time(s.time))
+                            .unwrap_or_else(|| "corrupt PB".to_owned()),
+                        Ok(None) => "No PB!".to_owned(),
+                        Err(_) => "corrupt PB".to_owned(),
+                    })
+                    .collect::<Vec<_>>();
+                let width = pbs.iter().map(|pb| pb.len()).max().unwrap_or(0);
 
-            RunApp::spawn_signal_handler(Arc::clone(&app))?;
-            let child = RunApp::spawn_bridge_handler(Arc::clone(&app))?;
-            // child.unwrap().stdout.unwrap();
-            RunApp::launch_ui(&app)?;
-            if let Some(mut child) = child {
-                child.kill().unwrap();
+                for (config, pb) in configs.iter().zip(pbs) {
+                    println!(
+                        "{:>width$}: [{}] {}",
+                        pb, config.directory_name, config.full_game_name
+                    );
+                }
             }
         }
-        Mode::NewGame { game } => {
-            println!("Registering new game");
-            let full_game_name = ask("Full game name: ")?;
+        Mode::Show { game, run } => {
+            let _lock = lock_for_reading(&game)?;
+            let run_name = resolve_run_selector(&game, run.as_deref().unwrap_or("latest"))?;
+            let path = run_location(&game, &run_name)?;
+            let sections = load_run(&game, &run_name)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .with_context(|| format!("{} does not exist", path.display()))?;
+
+            let metadata = load_metadata(&game, &run_name)?;
+            let pb = load_run(&game, "pb.run")?;
 
-            println!("Enter section names (CTRL-D or write empty line to stop)");
-            let mut section_names = Vec::new();
-            for i in 1.. {
-                let name = ask(&format!("section{}: ", i))?;
-                if name.is_empty() {
-                    break;
+            // Section, cumulative time, segment and the delta to the PB at that section
+            println!("{}", path.display());
+            let name_width = sections.iter().map(|s| s.name.chars().count()).max();
+            let name_width = name_width.unwrap_or(0).max("Total".len());
+            let segments = stats::segment_times(&sections);
+            let mut section_start = 0;
+            for (i, section) in sections.iter().enumerate() {
+                let mut line = format!(
+                    "{:name_width$}  {:>11}  {:>11}",
+                    section.name,
+                    format_run_time(section.time),
+                    format_run_time(segments[i]),
+                );
+                let pb_section = pb.as_ref().and_then(|pb| pb.get(i));
+                if let Some(pb_section) = pb_section.filter(|s| s.name == section.name) {
+                    line += &format!(
+                        "  {:>12}",
+                        format_offset(section.time as i64 - pb_section.time as i64)
+                    );
                 }
-                section_names.push(name);
+                println!("{}", line.trim_end());
+                for checkpoint in metadata.checkpoints.iter().filter(|c| c.section == i) {
+                    println!(
+                        "    {}: +{}",
+                        checkpoint.name,
+                        format_run_time(checkpoint.time.saturating_sub(section_start))
+                    );
+                }
+                section_start = section.time;
             }
-            if section_names.is_empty() {
-                println!("\nGame creation cancelled");
-                return Ok(());
+            if let Some(last) = sections.last() {
+                println!(
+                    "{:name_width$}  {:>11}",
+                    "Total",
+                    format_run_time(last.time)
+                );
+            }
+
+            if let Some(comparison) = metadata.comparison {
+                let time = metadata
+                    .comparison_time
+                    .map_or_else(|| "no time".to_owned(), format_run_time);
+                println!("Compared against {} ({})", comparison.name(), time);
+            }
+            if let Some(environment) = metadata.environment() {
+                println!("{}", environment);
+            }
+            if let Some(category) = &metadata.category {
+                println!("Category: {}", category);
+            }
+            if let Some(route) = &metadata.route {
+                println!("Route: {}", route);
+            }
+            if !metadata.instant_splits.is_empty() {
+                println!(
+                    "Split in the same millisecond as the section before, never golds: {}",
+                    metadata.instant_splits.join(", ")
+                );
+            }
+            if let Some(note) = &metadata.note {
+                println!("Note: {}", note);
             }
 
-            let bridge_script_raw = ask("\nOptional: Enter bridge script path: ")?;
+            let total = |run: Option<Vec<Section>>| {
+                run.and_then(|run| run.last().map(|s| format_run_time(s.time)))
+                    .unwrap_or_else(|| "none".to_owned())
+            };
+            println!("Personal Best — {}", total(load_run(&game, "pb.run")?));
+            println!(
+                "Sum of Best — {}",
+                total(load_run(&game, "sum_of_best.run")?)
+            );
+        }
+        Mode::Audit {
+            game,
+            run,
+            threshold,
+        } => {
+            let _lock = lock_for_reading(&game)?;
+            let run_name = resolve_run_selector(&game, run.as_deref().unwrap_or("latest"))?;
+            let path = run_location(&game, &run_name)?;
+            let samples = load_metadata(&game, &run_name)?.drift_samples;
+            ensure!(
+                !samples.is_empty(),
+                "{} has no drift samples, they are only recorded since this version",
+                run_name
+            );
 
-            let bridge_script = if bridge_script_raw.is_empty() {
-                None
+            println!("{}", path.display());
+            println!("{:>11}  {:>11}  {:>9}", "timer", "wall clock", "drift");
+            let mut anomalies = 0;
+            for sample in &samples {
+                let drift = sample.wall_clock - sample.monotonic as i64;
+                let anomaly = drift.unsigned_abs() > threshold as u64;
+                if anomaly {
+                    anomalies += 1;
+                }
+                println!(
+                    "{:>11}  {:>11}  {:>+7}ms{}",
+                    format_run_time(sample.monotonic),
+                    format_run_time(sample.wall_clock.max(0) as u32),
+                    drift,
+                    if anomaly { "  !" } else { "" }
+                );
+            }
+            if anomalies > 0 {
+                println!(
+                    "{} of {} samples drift more than {}ms",
+                    anomalies,
+                    samples.len(),
+                    threshold
+                );
             } else {
-                Some(PathBuf::from(bridge_script_raw))
+                println!("No drift above {}ms", threshold);
+            }
+        }
+        Mode::EditRun { game, run } => {
+            edit_run(&load_config(&game)?, &run)?;
+        }
+        Mode::UndoLast { game } => {
+            undo_last(&load_config(&game)?)?;
+        }
+        Mode::Migrate { game, to } => {
+            migrate_storage(&mut load_config(&game)?, to)?;
+        }
+        Mode::Retime {
+            game,
+            offset_ms,
+            all_runs,
+            dry_run,
+        } => {
+            retime(&load_config(&game)?, offset_ms, all_runs, dry_run)?;
+        }
+        Mode::ValidateConfig { game, strict } => {
+            let config = load_config(&game)?;
+            let config_path = game_dir(&game)?.join("config.toml");
+            let (global, global_warnings) = settings::load_global_checked()?;
+            let global_path = settings::global_path()?;
+            let mut warnings: Vec<String> = global_warnings
+                .iter()
+                .map(|warning| format!("{}: {}", global_path.display(), warning))
+                .collect();
+            warnings.extend(
+                config
+                    .warnings
+                    .iter()
+                    .map(|warning| format!("{}: {}", config_path.display(), warning)),
+            );
+
+            let settings = Settings::resolve(&[&global, &config.settings])?;
+            let mut problems = Vec::new();
+            if let Some(path) = &settings.theme {
+                let (errors, theme_warnings) = Theme::load(path)?.0.conflicts();
+                let in_theme = |message: String| format!("{}: {}", path.display(), message);
+                warnings.extend(theme_warnings.into_iter().map(in_theme));
+                problems.extend(errors.into_iter().map(in_theme));
+            }
+            if strict {
+                problems.append(&mut warnings);
+            }
+            for warning in &warnings {
+                println!("{}", warning);
+            }
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            ensure!(problems.is_empty(), "Found {} problems", problems.len());
+            println!("{} is valid", config_path.display());
+        }
+        Mode::Calibrate { game, attempts } => {
+            ensure!(attempts > 0, "--attempts must be at least 1");
+            calibrate(&game, attempts)?;
+        }
+        Mode::Golds { game, run } => {
+            let _lock = lock_for_reading(&game)?;
+            let config = load_config(&game)?;
+            let run_name = resolve_run_selector(&game, run.as_deref().unwrap_or("latest"))?;
+            let path = run_location(&game, &run_name)?;
+            let sections = load_run(&game, &run_name)?.context("Run does not exist")?;
+
+            let golds_then = load_golds_log(&game)?.remove(&run_name);
+            let golds_now = load_run(&game, "sum_of_best.run")?
+                .map(|sob| stats::segment_times(&sob))
+                .unwrap_or_default();
+            let segments = stats::segment_times(&sections);
+
+            let width = sections.iter().map(|s| s.name.chars().count()).max();
+            let width = width.unwrap_or(0);
+            println!("{}", path.display());
+            println!("{:width$}  {:>7}  {:>7}", "", "then", "now");
+            let mut contributed = 0;
+            for (i, (section, segment)) in sections.iter().zip(&segments).enumerate() {
+                let then = match &golds_then {
+                    Some(golds) if golds.contains(&section.name) => "gold",
+                    Some(_) => "-",
+                    None => "unknown",
+                };
+                // Only counts if the section is where the config has it
+                let in_sob = config.sections.get(i) == Some(&section.name)
+                    && golds_now.get(i) == Some(segment);
+                if in_sob {
+                    contributed += 1;
+                }
+                println!(
+                    "{:width$}  {:>7}  {:>7}",
+                    section.name,
+                    then,
+                    if in_sob { "gold" } else { "-" },
+                );
+            }
+            if golds_then.is_none() {
+                println!("The run is older than golds.log, its golds back then are unknown");
+            }
+            println!(
+                "{} of the {} golds in the sum of best are from this run",
+                contributed,
+                golds_now.len()
+            );
+        }
+        Mode::Timestamps {
+            game,
+            run,
+            video_start,
+        } => {
+            let _lock = lock_for_reading(&game)?;
+            let run_name = resolve_run_selector(&game, run.as_deref().unwrap_or("latest"))?;
+            let sections = load_run(&game, &run_name)?.context("Run does not exist")?;
+            let metadata = load_metadata(&game, &run_name)?;
+            let started_at = metadata.started_at.with_context(|| {
+                format!(
+                    "{} has no timestamps, they are only recorded since this version",
+                    run_name
+                )
+            })?;
+
+            let video_start = video_start
+                .map(|time| parse_wall_time(&time, &started_at))
+                .transpose()?;
+
+            // Each section starts where the previous one was split
+            let starts = std::iter::once(started_at).chain(metadata.split_dates.iter().copied());
+            for (section, start) in sections.iter().zip(starts) {
+                match video_start {
+                    Some(video_start) => println!(
+                        "{} {}",
+                        format_chapter_time((start - video_start).num_milliseconds()),
+                        section.name
+                    ),
+                    None => println!("{} {}", start.format("%H:%M:%S%.3f"), section.name),
+                }
+            }
+        }
+        Mode::Compare { game, a, b } => {
+            let _lock = lock_for_reading(&game)?;
+            let name_a = resolve_run_selector(&game, a.as_deref().unwrap_or("pb"))?;
+            let name_b = resolve_run_selector(&game, b.as_deref().unwrap_or("latest"))?;
+            let run_a = load_run(&game, &name_a)?.context("Run does not exist")?;
+            let run_b = load_run(&game, &name_b)?.context("Run does not exist")?;
+            let (path_a, path_b) = (run_location(&game, &name_a)?, run_location(&game, &name_b)?);
+
+            println!("{} vs {}", path_a.display(), path_b.display());
+            for aligned in stats::align_sections(&run_a, &run_b)? {
+                match aligned {
+                    stats::Aligned::Both(i, j) => {
+                        let (section_a, section_b) = (&run_a[i], &run_b[j]);
+                        let delta = section_b.time as i64 - section_a.time as i64;
+                        println!(
+                            "{}: {} {} ({}{})",
+                            section_a.name,
+                            format_run_time(section_a.time),
+                            format_run_time(section_b.time),
+                            if delta < 0 { "-" } else { "+" },
+                            format_run_time(delta.unsigned_abs() as u32)
+                        );
+                    }
+                    stats::Aligned::OnlyFirst(i) => println!(
+                        "{}: {} (unaligned, only in {})",
+                        run_a[i].name,
+                        format_run_time(run_a[i].time),
+                        path_a.display()
+                    ),
+                    stats::Aligned::OnlySecond(j) => println!(
+                        "{}: {} (unaligned, only in {})",
+                        run_b[j].name,
+                        format_run_time(run_b[j].time),
+                        path_b.display()
+                    ),
+                }
+            }
+        }
+        Mode::Stats {
+            game,
+            filter,
+            host,
+            exclude_host,
+            json,
+        } => {
+            let _lock = lock_for_reading(&game)?;
+            let config = load_config(&game)?;
+            let mut history = load_history(&config)?;
+            if host.is_some() || exclude_host.is_some() || !filter.is_empty() {
+                // Runs from before hostnames were recorded only match an exclusion
+                let mut filtered = Vec::new();
+                for run in history {
+                    let metadata = load_metadata(&game, &run.name)?;
+                    let hostname = &metadata.hostname;
+                    let complete = run.sections.len() == config.sections.len();
+                    let included = host.as_ref().is_none_or(|h| hostname.as_ref() == Some(h))
+                        && exclude_host
+                            .as_ref()
+                            .is_none_or(|h| hostname.as_ref() != Some(h))
+                        && filter.matches(&run.name, complete, &metadata);
+                    if included {
+                        filtered.push(run);
+                    }
+                }
+                history = filtered;
+            }
+            let filtered = host.is_some() || exclude_host.is_some() || !filter.is_empty();
+            let complete = history
+                .iter()
+                .filter(|run| run.sections.len() == config.sections.len())
+                .count();
+            let best_exits = stats::best_exits(config.sections.len(), &history);
+            let most_golds = stats::most_golds(config.sections.len(), &history);
+            let best_single_run = stats::best_single_run(config.sections.len(), &history);
+            let resets = load_resets(&game)?;
+            let reset_counts: Vec<usize> = (0..config.sections.len())
+                .map(|i| resets.iter().filter(|reset| reset.section == i).count())
+                .collect();
+            let target_hits: Vec<Option<(u32, (usize, usize))>> = (0..config.sections.len())
+                .map(|i| {
+                    let target = config.target(i)?;
+                    Some((target, stats::target_hits(&history, i, target)))
+                })
+                .collect();
+            let abandoned = abandoned_sections(&resets, &history, !filtered);
+            let time_spent = stats::time_spent(config.sections.len(), &history, &abandoned);
+            let total_spent = time_spent.iter().sum::<u64>();
+            let pb_segments = load_run(&game, "pb.run")?
+                .filter(|pb| validate_run(&config, pb).is_ok())
+                .map(|pb| stats::segment_times(&pb));
+            let percent = |part: u64, whole: u64| (whole > 0).then(|| part * 100 / whole);
+            let pb_share = |i: usize| {
+                let pb = pb_segments.as_ref()?;
+                percent(pb[i] as u64, pb.iter().map(|&s| s as u64).sum())
+            };
+
+            if json {
+                let sections: Vec<serde_json::Value> = config
+                    .sections
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        serde_json::json!({
+                            "name": name,
+                            "best_exit": best_exits[i].as_ref().map(|exit| exit.time),
+                            "best_exit_run": best_exits[i].as_ref().map(|exit| &exit.run),
+                            "resets": reset_counts[i],
+                            "target": target_hits[i].map(|(target, _)| target),
+                            "target_hits": target_hits[i].map(|(_, (hits, _))| hits),
+                            "target_attempts": target_hits[i].map(|(_, (_, attempts))| attempts),
+                            "time_spent": time_spent[i],
+                            "time_spent_percent": percent(time_spent[i], total_spent),
+                            "pb_percent": pb_share(i),
+                        })
+                    })
+                    .collect();
+                let stats = serde_json::json!({
+                    "runs": history.len(),
+                    "complete": complete,
+                    "most_golds": most_golds.as_ref().map(|(golds, _)| golds),
+                    "most_golds_run": most_golds.as_ref().map(|(_, run)| run),
+                    "best_single_run": best_single_run.as_ref().map(|best| best.time),
+                    "best_single_run_run": best_single_run.as_ref().map(|best| &best.run),
+                    "time_spent": total_spent,
+                    "sections": sections,
+                });
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+                return Ok(());
+            }
+
+            println!("{} runs, {} complete", history.len(), complete);
+
+            let width = config.sections.iter().map(|s| s.len()).max().unwrap_or(0);
+            println!("\nBest exits:");
+            for (name, exit) in config.sections.iter().zip(best_exits) {
+                match exit {
+                    Some(exit) => println!(
+                        "{:width$}  {:>11}  {}",
+                        name,
+                        format_run_time(exit.time),
+                        exit.run
+                    ),
+                    None => println!("{:width$}  never completed", name),
+                }
+            }
+
+            if let Some((golds, run)) = most_golds {
+                println!("\nMost golds in a run: {} in {}", golds, run);
+            }
+            if let Some(best) = best_single_run {
+                println!(
+                    "Best single run with golds after it: {} from {}",
+                    format_run_time(best.time),
+                    best.run
+                );
+            }
+
+            if !resets.is_empty() {
+                println!("\nResets ({}):", resets.len());
+                for (name, count) in config.sections.iter().zip(&reset_counts) {
+                    println!(
+                        "{:width$}  {:>4}  {:>3}%",
+                        name,
+                        count,
+                        count * 100 / resets.len()
+                    );
+                }
+                if let Some(last) = resets.last() {
+                    println!(
+                        "Last reset: run of {} in {} at {}",
+                        last.date,
+                        config
+                            .sections
+                            .get(last.section)
+                            .map_or("?", |s| s.as_str()),
+                        last.elapsed
+                    );
+                }
+            }
+
+            if !config.targets.is_empty() {
+                println!("\nTargets:");
+                for (name, target_hits) in config.sections.iter().zip(&target_hits) {
+                    let Some((target, (hits, attempts))) = *target_hits else {
+                        continue;
+                    };
+                    let rate = match attempts {
+                        0 => "-".to_owned(),
+                        _ => format!("{}%", hits * 100 / attempts),
+                    };
+                    println!(
+                        "{:width$}  {:>11}  {}/{} ({})",
+                        name,
+                        format_run_time(target),
+                        hits,
+                        attempts,
+                        rate
+                    );
+                }
+            }
+
+            if total_spent > 0 {
+                println!(
+                    "\nTime spent over all attempts ({}), next to the share of the PB:",
+                    format_duration(total_spent)
+                );
+                for (i, name) in config.sections.iter().enumerate() {
+                    let share =
+                        |share: Option<u64>| share.map_or("-".to_owned(), |p| format!("{}%", p));
+                    println!(
+                        "{:width$}  {:>11}  {:>4}  {:>4}",
+                        name,
+                        format_duration(time_spent[i]),
+                        share(percent(time_spent[i], total_spent)),
+                        share(pb_share(i))
+                    );
+                }
+            }
+        }
+        Mode::Export {
+            game,
+            run,
+            format,
+            output,
+            run_id,
+        } => {
+            let _lock = lock_for_reading(&game)?;
+            let config = load_config(&game)?;
+            let run_name = resolve_run_selector(&game, run.as_deref().unwrap_or("pb"))?;
+            let sections = load_run(&game, &run_name)?.context("Run does not exist")?;
+            let sum_of_best = load_run(&game, "sum_of_best.run")?;
+            let history = load_history(&config)?;
+
+            let exported = match format {
+                export::Format::Exchange => serde_json::to_string_pretty(&export::exchange(
+                    &config,
+                    &run_name,
+                    &sections,
+                    sum_of_best.as_deref(),
+                    &history,
+                    run_id,
+                ))?,
             };
+            match output {
+                Some(output) => fs::write(&output, exported)
+                    .with_context(|| format!("Failed to write {}", output.display()))?,
+                None => println!("{}", exported),
+            }
+        }
+        Mode::Sheet {
+            game,
+            output,
+            copies,
+            markdown,
+            targets,
+        } => {
+            let config = load_config(&game)?;
+            let global = settings::load_global()?;
+            let settings = Settings::resolve(&[&global, &config.settings])?;
+            let time_width = TableLayout::new(&settings, false, false).time_width;
+
+            let sheet = sheet::text(&config, time_width, targets, markdown, copies);
+            match output {
+                Some(output) => fs::write(&output, sheet)
+                    .with_context(|| format!("Failed to write {}", output.display()))?,
+                None => print!("{}", sheet),
+            }
+        }
+        Mode::ShareSplits { game, output } => {
+            let config = load_config(&game)?;
+            let pb = load_run(&game, "pb.run")?;
+            let sum_of_best = load_run(&game, "sum_of_best.run")?;
+            ensure!(pb.is_some(), "{} has no PB to share yet", game);
+
+            let text = share::text(&config, pb.as_deref(), sum_of_best.as_deref());
+            let output = output.unwrap_or_else(|| PathBuf::from(format!("{}-splits.txt", game)));
+            fs::write(&output, text)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            println!("Wrote {}", output.display());
+        }
+        Mode::AdoptSplits { game, path, name } => {
+            let config = load_config(&game)?;
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let shared = share::parse(&text)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let pb = shared
+                .pb
+                .with_context(|| format!("{} has no PB", path.display()))?;
+            if shared.game != config.full_game_name {
+                println!(
+                    "The splits are for {:?}, not {:?}",
+                    shared.game, config.full_game_name
+                );
+            }
+            if shared.category.is_some() && shared.category != config.category {
+                println!(
+                    "The splits are for the category {}",
+                    shared.category.as_deref().unwrap_or_default()
+                );
+            }
 
-            let ask_save = ask(&format!(
-                "Do you want to create {} with {} sections? [Y/n]: ",
+            let (run, report) = share::adopt(&config, &pb)?;
+            for line in &report {
+                println!("{}", line);
+            }
+            let name = match name {
+                Some(name) => name,
+                None => path
+                    .file_stem()
+                    .context("The file has no name to adopt it as, use --name")?
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            let target = shared_run(&name)?;
+            let location = run_location(&game, &target)?;
+            let exists = store::open(&game)?.version(&target)?.is_some();
+            if exists
+                && !confirm(
+                    &mut io::stdin().lock(),
+                    "Replacing",
+                    std::slice::from_ref(&location),
+                    Danger::Low,
+                )?
+            {
+                return Ok(());
+            }
+            save_run(&game, &target, &run)?;
+            println!(
+                "Adopted as {}, race it with `speedy against {} --compare-file {}`",
+                location.display(),
                 game,
-                section_names.len()
-            ))?;
-
-            if ["y", "yes", "ja", "j", ""].contains(&&*ask_save.to_lowercase()) {
-                let config = GameConfig {
-                    version: 1,
-                    directory_name: game,
-                    full_game_name,
-                    bridge_script,
-                    sections: section_names,
+                name
+            );
+        }
+        Mode::Report { game, output } => {
+            let _lock = lock_for_reading(&game)?;
+            let config = load_config(&game)?;
+            let history = load_history(&config)?;
+            let pb = load_run(&game, "pb.run")?;
+            let sum_of_best = load_run(&game, "sum_of_best.run")?;
+
+            let html = report::html(&config, pb.as_deref(), sum_of_best.as_deref(), &history);
+            let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.html", game)));
+            fs::write(&output, html)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            println!("Wrote {}", output.display());
+        }
+        Mode::ListRuns { game, filter } => {
+            let _lock = lock_for_reading(&game)?;
+            let config = load_config(&game)?;
+            let pb = load_run(&game, "pb.run");
+            let pb_times = pb
+                .as_ref()
+                .ok()
+                .and_then(Option::as_ref)
+                .map(|pb| pb.iter().map(|s| s.time).collect::<Vec<_>>());
+
+            // The comparisons first, they have no date
+            if filter.is_empty() {
+                for (run, sections) in [
+                    ("pb.run", pb),
+                    ("sum_of_best.run", load_run(&game, "sum_of_best.run")),
+                ] {
+                    match sections {
+                        Ok(Some(sections)) => {
+                            let final_time = sections
+                                .last()
+                                .map_or_else(|| "empty".to_owned(), |s| format_run_time(s.time));
+                            println!("{}  {:>11}  comparison", run, final_time);
+                        }
+                        Ok(None) => {}
+                        Err(e) => println!("{}  {:>11}  {:#}", run, "error", e),
+                    }
+                }
+            }
+
+            // Names are dates, so sorting them puts the most recent runs at the bottom
+            let mut lines = BTreeMap::new();
+            for (name, entry) in index::runs(&config)? {
+                if !filter.is_empty()
+                    && !filter.matches(&name, entry.complete, &load_metadata(&game, &name)?)
+                {
+                    continue;
+                }
+                let final_time = entry
+                    .final_time
+                    .map_or_else(|| "empty".to_owned(), format_run_time);
+                let mut state = if entry.complete {
+                    "complete".to_owned()
+                } else if entry.matches_config {
+                    format!("{}/{} sections", entry.sections, config.sections.len())
+                } else {
+                    "other sections".to_owned()
                 };
+                if entry.complete && pb_times.as_ref() == Some(&entry.times) {
+                    state += "  PB";
+                }
+                lines.insert(name, (final_time, state));
+            }
+            // Runs the index left out because they can't be read
+            if filter.is_empty() {
+                for name in list_runs(&game)? {
+                    if lines.contains_key(&name) {
+                        continue;
+                    }
+                    if let Err(e) = load_run(&game, &name) {
+                        lines.insert(name, ("error".to_owned(), format!("{:#}", e)));
+                    }
+                }
+            }
+            for (name, (final_time, state)) in lines {
+                println!("{}  {:>11}  {}", name, final_time, state);
+            }
+        }
+        Mode::Check { game } => {
+            let _lock = lock_for_reading(&game)?;
+            let config = load_config(&game)?;
+            for (_, run, migration) in migrated_comparisons(&game)? {
+                println!(
+                    "{} was changed by the section migration of {}, its deltas are approximate",
+                    run, migration
+                );
+            }
+            for run in ["pb.run", "sum_of_best.run"] {
+                for retime in load_metadata(&game, run)?.retimes {
+                    println!(
+                        "{} was retimed by {} on {}, runs that weren't retimed along with it \
+                         use the old timing",
+                        run,
+                        format_offset(retime.offset),
+                        retime.date
+                    );
+                }
+            }
+            for warning in &config.warnings {
+                println!("config.toml: {}", warning);
+            }
+            let mut problems = check_game(&config)?;
+            let global = settings::load_global()?;
+            let settings = Settings::resolve(&[&global, &config.settings])?;
+            if let Some(path) = &settings.theme {
+                let (errors, warnings) = Theme::load(path)?.0.conflicts();
+                for warning in warnings {
+                    println!("{}: {}", path.display(), warning);
+                }
+                problems.extend(
+                    errors
+                        .into_iter()
+                        .map(|error| format!("{}: {}", path.display(), error)),
+                );
+            }
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            ensure!(problems.is_empty(), "Found {} problems", problems.len());
+            println!("No problems found");
+        }
+        Mode::RecomputeSob {
+            game,
+            exclude_suppressed,
+        } => {
+            let config = load_config(&game)?;
+            let mut runs = Vec::new();
+            for run in load_history(&config)? {
+                if run.sections.len() != config.sections.len() {
+                    continue;
+                }
+                if exclude_suppressed && load_metadata(&game, &run.name)?.golds_suppressed {
+                    continue;
+                }
+                runs.push(run.sections);
+            }
 
-                write_config(&config)?;
+            let new_sob =
+                sum_of_best(&runs).context("No complete runs to compute the sum of best from")?;
+            let old_final = load_run(&game, "sum_of_best.run")?
+                .and_then(|sob| sob.last().map(|s| format_run_time(s.time)))
+                .unwrap_or_else(|| "none".to_owned());
+            let new_final = format_run_time(new_sob.last().context("Run is empty")?.time);
 
+            let action = format!(
+                "Sum of best of {} runs is {} (currently {}), overwriting",
+                runs.len(),
+                new_final,
+                old_final
+            );
+            let path = run_location(&game, "sum_of_best.run")?;
+            if confirm(&mut io::stdin().lock(), &action, &[path], Danger::Low)? {
+                let _lock = lock_game(&game, true)?;
+                save_run(&game, "sum_of_best.run", &new_sob)?;
                 println!("Done");
             } else {
-                println!("Game creation cancelled");
+                println!("Cancelled");
             }
         }
-        Mode::ListGames => {
-            let configs = load_all_configs()?;
-            if configs.is_empty() {
-                println!("No games registered yet");
-            } else {
-                for config in configs {
-                    let pb = if let Some(pb_run) = load_run(&config.directory_name, "pb.run")? {
-                        let (min, sec, _mil) =
-                            millis_to_min_sec_mil(pb_run.last().context("Run is empty")?.time);
-                        format!("{}m{:02}s", min, sec)
-                    } else {
-                        "No PB!".to_owned()
-                    };
-                    println!(
-                        "{}: [{}] {}",
-                        pb, config.directory_name, config.full_game_name
-                    );
+        Mode::Simulate { game } => {
+            bridge::simulate(&load_config(&game)?)?;
+        }
+        Mode::Theme { game, theme } => {
+            let config = load_config(&game)?;
+            let global = settings::load_global()?;
+            let settings = Settings::resolve(&[&global, &config.settings])?;
+
+            let (theme, defaults) = match theme.as_ref().or(settings.theme.as_ref()) {
+                Some(path) => {
+                    let (theme, defaults) = Theme::load(path)?;
+                    println!("{}", path.display());
+                    (theme, defaults)
                 }
+                None => {
+                    println!("No theme configured, showing the default colors");
+                    (Theme::default(), Vec::new())
+                }
+            };
+            if !defaults.is_empty() {
+                println!("Default colors for: {}", defaults.join(", "));
             }
+            println!();
+            let layout =
+                TableLayout::new(&settings, config.sum_of_best_column, config.median_column);
+            theme::preview(&theme, settings.color, &layout);
         }
-        _ => {
-            eprintln!("Mode is not implemented yet!");
+        Mode::Paths => {
+            println!("settings: {}", settings::global_path()?.display());
+            println!("games:    {}", data_dir()?.display());
+            println!("logs:     {}", state_dir()?.display());
+            println!("caches:   {}", cache_dir()?.display());
+        }
+        Mode::Completions { shell } => {
+            print!("{}", completions::script(shell));
+        }
+        // The completion helpers run on every TAB press and must never print errors
+        Mode::CompleteGames => {
+            for game in list_games().unwrap_or_default() {
+                println!("{}", game);
+            }
+        }
+        Mode::CompleteRuns { game } => {
+            if let Ok(runs) = list_runs(&game) {
+                for selector in ["latest", "pb", "sob"] {
+                    println!("{}", selector);
+                }
+                for run in runs {
+                    println!("{}", run);
+                }
+            }
         }
     }
 
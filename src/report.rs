@@ -0,0 +1,191 @@
+//! A standalone HTML page with the stats of a game
+
+use crate::stats::{self, HistoricalRun};
+use crate::{format_run_time, GameConfig, Section};
+use std::fmt::Write;
+
+const STYLE: &str = "body{font-family:sans-serif;background:#111;color:#eee;margin:2em}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{padding:0.2em 0.8em;text-align:right}\
+th:first-child,td:first-child{text-align:left}\
+tr:nth-child(even){background:#1c1c1c}\
+polyline{fill:none;stroke:#6c6;stroke-width:2}\
+circle{fill:#6c6}";
+
+/// Renders the report. Everything, including the charts, is inline so the file works on its own.
+pub fn html(
+    config: &GameConfig,
+    pb: Option<&[Section]>,
+    sum_of_best: Option<&[Section]>,
+    history: &[HistoricalRun],
+) -> String {
+    let section_count = config.sections.len();
+    let complete = history
+        .iter()
+        .filter(|run| run.sections.len() == section_count)
+        .count();
+    let title = escape(&config.full_game_name);
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    writeln!(out, "<title>{}</title>", title).unwrap();
+    writeln!(out, "<style>{}</style>\n</head>\n<body>", STYLE).unwrap();
+    writeln!(out, "<h1>{}</h1>", title).unwrap();
+    writeln!(
+        out,
+        "<p class=\"attempts\">{} attempts, {} completed</p>",
+        history.len(),
+        complete
+    )
+    .unwrap();
+
+    out.push_str("<h2>Personal best</h2>\n");
+    match pb {
+        Some(pb) => split_table(&mut out, pb),
+        None => out.push_str("<p>No personal best yet</p>\n"),
+    }
+
+    if let Some(total) = sum_of_best.and_then(|sob| sob.last()) {
+        writeln!(
+            out,
+            "<p class=\"sum-of-best\">Sum of best: {}</p>",
+            format_run_time(total.time)
+        )
+        .unwrap();
+    }
+
+    out.push_str("<h2>Progression</h2>\n");
+    let progression = stats::pb_progression(section_count, history);
+    if progression.is_empty() {
+        out.push_str("<p>No completed runs yet</p>\n");
+    } else {
+        progression_chart(&mut out, &progression);
+    }
+
+    out.push_str("<h2>Sections</h2>\n");
+    section_table(&mut out, config, sum_of_best, history);
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn split_table(out: &mut String, sections: &[Section]) {
+    out.push_str("<table class=\"splits\">\n");
+    out.push_str("<tr><th>section</th><th>total</th><th>segment</th></tr>\n");
+    for (section, segment) in sections.iter().zip(stats::segment_times(sections)) {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&section.name),
+            format_run_time(section.time),
+            format_run_time(segment)
+        )
+        .unwrap();
+    }
+    out.push_str("</table>\n");
+}
+
+fn section_table(
+    out: &mut String,
+    config: &GameConfig,
+    sum_of_best: Option<&[Section]>,
+    history: &[HistoricalRun],
+) {
+    let golds = sum_of_best.map(stats::segment_times);
+
+    out.push_str("<table class=\"sections\">\n");
+    out.push_str(
+        "<tr><th>section</th><th>gold</th><th>mean</th><th>median</th>\
+         <th>best</th><th>worst</th><th>last 20</th></tr>\n",
+    );
+    for (i, name) in config.sections.iter().enumerate() {
+        let gold = golds
+            .as_ref()
+            .and_then(|golds| golds.get(i))
+            .map_or_else(|| "-".to_owned(), |&gold| format_run_time(gold));
+        write!(out, "<tr><td>{}</td><td>{}</td>", escape(name), gold).unwrap();
+        match stats::section_stats(history, i, 20) {
+            Some(section_stats) => {
+                writeln!(
+                    out,
+                    "<td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    format_run_time(section_stats.mean),
+                    format_run_time(section_stats.median),
+                    format_run_time(section_stats.best.time),
+                    format_run_time(section_stats.worst.time),
+                    line_chart(&section_stats.recent, 120, 24)
+                )
+                .unwrap();
+            }
+            None => out.push_str("<td colspan=\"5\">never completed</td></tr>\n"),
+        }
+    }
+    out.push_str("</table>\n");
+}
+
+fn progression_chart(out: &mut String, progression: &[stats::TimeInRun]) {
+    let times = progression.iter().map(|pb| pb.time).collect::<Vec<_>>();
+    out.push_str("<div class=\"progression\">\n");
+    out.push_str(&line_chart(&times, 600, 200));
+    out.push_str("\n<table>\n<tr><th>run</th><th>time</th></tr>\n");
+    for pb in progression {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            escape(&pb.run),
+            format_run_time(pb.time)
+        )
+        .unwrap();
+    }
+    out.push_str("</table>\n</div>\n");
+}
+
+/// Draws values as an SVG line, the largest value at the top
+fn line_chart(values: &[u32], width: u32, height: u32) -> String {
+    let min = values.iter().copied().min().unwrap_or(0);
+    let max = values.iter().copied().max().unwrap_or(0);
+    let margin = 4.0;
+    let inner_width = width as f64 - 2.0 * margin;
+    let inner_height = height as f64 - 2.0 * margin;
+
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let x = if values.len() > 1 {
+                margin + inner_width * i as f64 / (values.len() - 1) as f64
+            } else {
+                margin + inner_width / 2.0
+            };
+            let y = if max > min {
+                margin + inner_height * (max - value) as f64 / (max - min) as f64
+            } else {
+                margin + inner_height / 2.0
+            };
+            (x, y)
+        })
+        .collect::<Vec<_>>();
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+        width, height
+    );
+    if let [(x, y)] = points[..] {
+        write!(svg, "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\"/>", x, y).unwrap();
+    } else {
+        let coordinates = points
+            .iter()
+            .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+            .collect::<Vec<_>>();
+        write!(svg, "<polyline points=\"{}\"/>", coordinates.join(" ")).unwrap();
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
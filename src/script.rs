@@ -0,0 +1,116 @@
+//! Scripted runs for reproducing timer behavior: a file of timed commands is played against the
+//! run's state machine with a made up clock, without a terminal, audio or signals.
+
+use anyhow::{bail, ensure, Context, Result};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// What a script line does, the same as the signal or bridge line of that name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Starts a run, ignored while one is active
+    Start,
+    /// Like SIGUSR1
+    Split,
+    /// Like SIGUSR2
+    Reset,
+    Checkpoint(Option<String>),
+    /// Like SIGTERM, ends the script and saves what is unsaved
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    /// Milliseconds since the script started
+    pub at: u32,
+    pub action: Action,
+}
+
+/// Reads commands like `at 1500ms split`, separated by lines or `;`. Empty commands and
+/// `#` comments are skipped. Times may not go backwards.
+pub fn load(path: &Path) -> Result<Vec<Command>> {
+    let script = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the script {}", path.display()))?;
+
+    let mut commands: Vec<Command> = Vec::new();
+    for (n, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        for command in line.split(';').map(str::trim).filter(|c| !c.is_empty()) {
+            let command = parse(command)
+                .with_context(|| format!("{}:{}: {:?}", path.display(), n + 1, command))?;
+            if let Some(last) = commands.last() {
+                ensure!(
+                    command.at >= last.at,
+                    "{}:{}: at {}ms comes before the previous command at {}ms",
+                    path.display(),
+                    n + 1,
+                    command.at,
+                    last.at
+                );
+            }
+            commands.push(command);
+        }
+    }
+
+    Ok(commands)
+}
+
+fn parse(command: &str) -> Result<Command> {
+    let mut words = command.split_whitespace();
+    ensure!(
+        words.next() == Some("at"),
+        "expected `at <time>ms <action>`"
+    );
+    let at = words
+        .next()
+        .and_then(|at| at.strip_suffix("ms"))
+        .and_then(|at| at.parse().ok())
+        .context("expected a time like 1500ms after `at`")?;
+
+    let action = match words.next() {
+        Some("start") => Action::Start,
+        Some("split") => Action::Split,
+        Some("reset") => Action::Reset,
+        Some("checkpoint") => {
+            let name = words.by_ref().collect::<Vec<_>>().join(" ");
+            Action::Checkpoint(Some(name).filter(|name| !name.is_empty()))
+        }
+        Some("stop") => Action::Stop,
+        Some(action) => bail!(
+            "unknown action {:?}, expected start, split, reset, checkpoint or stop",
+            action
+        ),
+        None => bail!("missing the action after the time"),
+    };
+    ensure!(words.next().is_none(), "unexpected words after the action");
+
+    Ok(Command { at, action })
+}
+
+/// The clock of a scripted run, which only moves when the next command is played
+#[derive(Debug, Clone)]
+pub struct Clock {
+    epoch: Instant,
+    epoch_date: chrono::DateTime<chrono::Local>,
+    /// Milliseconds since the epoch
+    pub at: u32,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            epoch_date: chrono::Local::now(),
+            at: 0,
+        }
+    }
+
+    pub fn instant(&self) -> Instant {
+        self.epoch + Duration::from_millis(self.at as u64)
+    }
+
+    pub fn date(&self) -> chrono::DateTime<chrono::Local> {
+        self.epoch_date + chrono::Duration::milliseconds(self.at as i64)
+    }
+}
@@ -0,0 +1,488 @@
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use clap::{Args, ValueEnum};
+use console_engine::Color;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// The run that deltas are measured against
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Comparison {
+    Pb,
+    SumOfBest,
+    Average,
+    /// The fastest time each section was ever left at, even in runs that died later
+    BestExit,
+    /// The median of each segment in the history, leaving out suspect ones
+    Median,
+}
+
+impl Comparison {
+    /// The name used in configs and on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Comparison::Pb => "pb",
+            Comparison::SumOfBest => "sum-of-best",
+            Comparison::Average => "average",
+            Comparison::BestExit => "best-exit",
+            Comparison::Median => "median",
+        }
+    }
+
+    /// The name used as a column header
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Comparison::Pb => "pb",
+            Comparison::SumOfBest => "sob",
+            Comparison::Average => "avg",
+            Comparison::BestExit => "exit",
+            Comparison::Median => "med",
+        }
+    }
+}
+
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// Comparison, total and segment columns
+    Full,
+    /// Only the total time and its delta
+    Compact,
+}
+
+/// How colors are written to the terminal
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    Truecolor,
+    /// The nearest of the 16 basic terminal colors
+    Ansi16,
+    /// No colors, state is shown with symbols instead
+    Mono,
+}
+
+impl ColorMode {
+    /// Guesses what the terminal supports from `$COLORTERM` and `$TERM`
+    pub fn detect() -> Self {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorMode::Truecolor;
+        }
+
+        match env::var("TERM").as_deref() {
+            Err(_) | Ok("") | Ok("dumb") => ColorMode::Mono,
+            Ok(term) if term.starts_with("vt") => ColorMode::Mono,
+            Ok("linux") | Ok("ansi") | Ok("cons25") => ColorMode::Ansi16,
+            Ok(term) if term.ends_with("-16color") => ColorMode::Ansi16,
+            Ok(_) => ColorMode::Truecolor,
+        }
+    }
+
+    /// Converts a color of the palette to one the terminal can show
+    pub fn apply(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorMode::Truecolor, _) => color,
+            (ColorMode::Mono, _) => Color::Reset,
+            (ColorMode::Ansi16, Color::Rgb { r, g, b }) => nearest_ansi16(r, g, b),
+            (ColorMode::Ansi16, _) => color,
+        }
+    }
+}
+
+fn nearest_ansi16(r: u8, g: u8, b: u8) -> Color {
+    // The xterm defaults, most terminals are close to them
+    const ANSI16: [(Color, [u8; 3]); 16] = [
+        (Color::Black, [0x00, 0x00, 0x00]),
+        (Color::DarkRed, [0xcd, 0x00, 0x00]),
+        (Color::DarkGreen, [0x00, 0xcd, 0x00]),
+        (Color::DarkYellow, [0xcd, 0xcd, 0x00]),
+        (Color::DarkBlue, [0x00, 0x00, 0xee]),
+        (Color::DarkMagenta, [0xcd, 0x00, 0xcd]),
+        (Color::DarkCyan, [0x00, 0xcd, 0xcd]),
+        (Color::Grey, [0xe5, 0xe5, 0xe5]),
+        (Color::DarkGrey, [0x7f, 0x7f, 0x7f]),
+        (Color::Red, [0xff, 0x00, 0x00]),
+        (Color::Green, [0x00, 0xff, 0x00]),
+        (Color::Yellow, [0xff, 0xff, 0x00]),
+        (Color::Blue, [0x5c, 0x5c, 0xff]),
+        (Color::Magenta, [0xff, 0x00, 0xff]),
+        (Color::Cyan, [0x00, 0xff, 0xff]),
+        (Color::White, [0xff, 0xff, 0xff]),
+    ];
+
+    let distance = |[cr, cg, cb]: [u8; 3]| {
+        [(r, cr), (g, cg), (b, cb)]
+            .iter()
+            .map(|&(a, b)| (a as i32 - b as i32).pow(2))
+            .sum::<i32>()
+    };
+    ANSI16
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map(|&(color, _)| color)
+        .unwrap()
+}
+
+/// Where a practice run learns that the game lost the focus
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FocusBackend {
+    /// Practice runs never pause
+    Off,
+    /// Asks `swaymsg`, for sway and compositors like it
+    Sway,
+    /// Asks `xdotool`
+    X11,
+}
+
+// Settings that can be given in the settings file, the game config and on the command line.
+// Not a doc comment, clap would show it as the help text of `run`.
+#[derive(Args, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SettingsOverrides {
+    /// Run to compare against
+    #[arg(long = "comparison", value_name = "COMPARISON")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_comparison: Option<Comparison>,
+
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+
+    /// Show the column with the comparison's times, can be toggled with `c`
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparison_column: Option<bool>,
+
+    /// Number of decimal places shown for times (0-3)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub precision: Option<u8>,
+
+    /// Show the elapsed time in big digits below the table
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub big_timer: Option<bool>,
+
+    /// Ignore keys while a run is active, until unlocked with `l`
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_on_start: Option<bool>,
+
+    /// Detected from $COLORTERM and $TERM if not given
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorMode>,
+
+    /// Exit after this many hours without a run or a key press, 0 never exits
+    #[arg(long, value_name = "HOURS")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<u32>,
+
+    /// Wait this long before a start counts, so it can be cancelled with esc or SIGUSR2
+    #[arg(long, value_name = "MS")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_start_ms: Option<u32>,
+
+    /// Show how often runs were reset in each section while no run is active
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_percentages: Option<bool>,
+
+    /// Session events kept in memory, older ones are written to session.log
+    #[arg(long, value_name = "EVENTS")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_history: Option<usize>,
+
+    /// File with the colors of the UI, see `speedy theme`
+    #[arg(long, value_name = "FILE")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<PathBuf>,
+
+    /// Ask before keeping a gold this many seconds faster than the old one and every segment
+    /// in the history, 0 never asks
+    #[arg(long, value_name = "SECONDS")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gold_guard: Option<u32>,
+
+    /// Another start this soon after the start restarts the run, 0 never does. A split only
+    /// restarts it within half the gold of the first section.
+    #[arg(long, value_name = "MS")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart_grace_ms: Option<u32>,
+
+    /// How a practice run notices that the game lost the focus
+    #[arg(long, value_name = "BACKEND")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_backend: Option<FocusBackend>,
+
+    /// Part of the title or class of the game's window, the full game name if not given
+    #[arg(long, value_name = "NAME")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_window: Option<String>,
+
+    /// A practice run pauses once the game lost the focus for this long
+    #[arg(long, value_name = "MS")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_pause_ms: Option<u32>,
+
+    /// Always show deltas as (+M:SS), instead of seconds for small deltas and hours for big ones
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_deltas: Option<bool>,
+
+    /// Ask for a goal for the session before `run` opens, unless one is given with --goal
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ask_goal: Option<bool>,
+
+    /// Save finished runs on a copy in the background, for data directories on slow disks
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub async_save: Option<bool>,
+
+    /// Note in the footer when a run is ahead of every past run at a split, or further behind
+    /// the PB than any past run made up
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pace_notes: Option<bool>,
+}
+
+/// The keys of the settings above as they are written in TOML files
+pub const KEYS: &[&str] = &[
+    "default_comparison",
+    "layout",
+    "comparison_column",
+    "precision",
+    "big_timer",
+    "lock_on_start",
+    "color",
+    "idle_timeout",
+    "confirm_start_ms",
+    "reset_percentages",
+    "event_history",
+    "theme",
+    "gold_guard",
+    "restart_grace_ms",
+    "focus_backend",
+    "focus_window",
+    "focus_pause_ms",
+    "fixed_deltas",
+    "ask_goal",
+    "async_save",
+    "pace_notes",
+];
+
+/// Finds the keys of a TOML table that aren't `known`. One that is a typo away from a known
+/// key is an error, any other is only warned about, as a newer speedy may have written it.
+/// `prefix` names the table in the messages, like `sounds.`.
+pub fn check_keys(table: &toml::Table, known: &[&str], prefix: &str) -> Result<Vec<String>> {
+    let mut warnings = Vec::new();
+    for key in table.keys().filter(|key| !known.contains(&key.as_str())) {
+        let closest = known
+            .iter()
+            .map(|k| (edit_distance(key, k), k))
+            .min()
+            .filter(|&(distance, k)| distance <= 2 && distance < k.len() / 2);
+        match closest {
+            Some((_, k)) => bail!(
+                "Unknown key {}{}, did you mean {}{}?",
+                prefix,
+                key,
+                prefix,
+                k
+            ),
+            None => warnings.push(format!(
+                "Unknown key {}{}, ignored. It may be from a newer speedy",
+                prefix, key
+            )),
+        }
+    }
+    Ok(warnings)
+}
+
+/// The number of single character insertions, deletions and substitutions between two words
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The effective settings of a run. The UI only ever reads these.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub comparison: Comparison,
+    pub layout: Layout,
+    pub comparison_column: bool,
+    pub precision: u8,
+    pub big_timer: bool,
+    pub lock_on_start: bool,
+    pub color: ColorMode,
+    /// In hours, 0 if disabled
+    pub idle_timeout: u32,
+    /// 0 if starts count immediately
+    pub confirm_start_ms: u32,
+    pub reset_percentages: bool,
+    pub event_history: usize,
+    pub theme: Option<PathBuf>,
+    /// In seconds, 0 if disabled
+    pub gold_guard: u32,
+    /// 0 if a second start never restarts
+    pub restart_grace_ms: u32,
+    pub focus_backend: FocusBackend,
+    pub focus_window: Option<String>,
+    pub focus_pause_ms: u32,
+    pub fixed_deltas: bool,
+    pub ask_goal: bool,
+    pub async_save: bool,
+    pub pace_notes: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            comparison: Comparison::Pb,
+            layout: Layout::Full,
+            comparison_column: true,
+            precision: 0,
+            big_timer: false,
+            lock_on_start: false,
+            color: ColorMode::detect(),
+            idle_timeout: 0,
+            confirm_start_ms: 0,
+            reset_percentages: false,
+            event_history: 1000,
+            theme: None,
+            gold_guard: 0,
+            restart_grace_ms: 5000,
+            focus_backend: FocusBackend::Off,
+            focus_window: None,
+            focus_pause_ms: 10000,
+            fixed_deltas: false,
+            ask_goal: false,
+            async_save: false,
+            pace_notes: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Applies the layers on top of the defaults, later layers win
+    pub fn resolve(layers: &[&SettingsOverrides]) -> Result<Self> {
+        let mut settings = Self::default();
+        for layer in layers {
+            if let Some(comparison) = layer.default_comparison {
+                settings.comparison = comparison;
+            }
+            if let Some(layout) = layer.layout {
+                settings.layout = layout;
+            }
+            if let Some(comparison_column) = layer.comparison_column {
+                settings.comparison_column = comparison_column;
+            }
+            if let Some(precision) = layer.precision {
+                settings.precision = precision;
+            }
+            if let Some(big_timer) = layer.big_timer {
+                settings.big_timer = big_timer;
+            }
+            if let Some(lock_on_start) = layer.lock_on_start {
+                settings.lock_on_start = lock_on_start;
+            }
+            if let Some(color) = layer.color {
+                settings.color = color;
+            }
+            if let Some(idle_timeout) = layer.idle_timeout {
+                settings.idle_timeout = idle_timeout;
+            }
+            if let Some(confirm_start_ms) = layer.confirm_start_ms {
+                settings.confirm_start_ms = confirm_start_ms;
+            }
+            if let Some(reset_percentages) = layer.reset_percentages {
+                settings.reset_percentages = reset_percentages;
+            }
+            if let Some(event_history) = layer.event_history {
+                settings.event_history = event_history;
+            }
+            if let Some(theme) = &layer.theme {
+                settings.theme = Some(theme.clone());
+            }
+            if let Some(gold_guard) = layer.gold_guard {
+                settings.gold_guard = gold_guard;
+            }
+            if let Some(restart_grace_ms) = layer.restart_grace_ms {
+                settings.restart_grace_ms = restart_grace_ms;
+            }
+            if let Some(focus_backend) = layer.focus_backend {
+                settings.focus_backend = focus_backend;
+            }
+            if let Some(focus_window) = &layer.focus_window {
+                settings.focus_window = Some(focus_window.clone());
+            }
+            if let Some(focus_pause_ms) = layer.focus_pause_ms {
+                settings.focus_pause_ms = focus_pause_ms;
+            }
+            if let Some(fixed_deltas) = layer.fixed_deltas {
+                settings.fixed_deltas = fixed_deltas;
+            }
+            if let Some(ask_goal) = layer.ask_goal {
+                settings.ask_goal = ask_goal;
+            }
+            if let Some(async_save) = layer.async_save {
+                settings.async_save = async_save;
+            }
+            if let Some(pace_notes) = layer.pace_notes {
+                settings.pace_notes = pace_notes;
+            }
+        }
+
+        ensure!(
+            settings.precision <= 3,
+            "precision must be between 0 and 3, got {}",
+            settings.precision
+        );
+
+        Ok(settings)
+    }
+}
+
+/// The global settings file, usually `~/.config/speedy/settings.toml`
+pub fn global_path() -> Result<PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "", "speedy")
+        .ok_or(anyhow!("No home directory found"))?;
+    Ok(dirs.config_dir().join("settings.toml"))
+}
+
+/// Loads the global settings file
+pub fn load_global() -> Result<SettingsOverrides> {
+    Ok(load_global_checked()?.0)
+}
+
+/// Loads the global settings file along with warnings about the keys it doesn't know
+pub fn load_global_checked() -> Result<(SettingsOverrides, Vec<String>)> {
+    let path = global_path()?;
+
+    let settings_str = match fs::read_to_string(&path) {
+        Ok(settings_str) => settings_str,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(e) => return Err(e.into()),
+    };
+    let table: toml::Table = toml::from_str(&settings_str)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let warnings = check_keys(&table, KEYS, "")
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let settings = toml::from_str(&settings_str)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok((settings, warnings))
+}
@@ -0,0 +1,153 @@
+//! A single text file with the sections, PB and sum of best of a game, to share comparisons
+//! without the history. Adopted files become comparisons of their own in the game's
+//! `shared/` directory and never replace the own PB or sum of best.
+
+use crate::stats::{self, Aligned};
+use crate::{parse_run_lines, run_text, GameConfig, Section};
+use anyhow::{bail, ensure, Context, Result};
+
+const HEADER: &str = "# Splits shared with speedy";
+
+pub struct Shared {
+    pub game: String,
+    pub category: Option<String>,
+    pub sections: Vec<String>,
+    pub pb: Option<Vec<Section>>,
+    pub sum_of_best: Option<Vec<Section>>,
+}
+
+/// Writes the shared file: a few `key: value` lines, then a `[block]` of section names and
+/// one for each comparison in the format of run files
+pub fn text(
+    config: &GameConfig,
+    pb: Option<&[Section]>,
+    sum_of_best: Option<&[Section]>,
+) -> String {
+    let mut out = format!("{}\ngame: {}\n", HEADER, config.full_game_name);
+    if let Some(category) = &config.category {
+        out += &format!("category: {}\n", category);
+    }
+    out += "\n[sections]\n";
+    for section in &config.sections {
+        out += section;
+        out += "\n";
+    }
+    for (block, run) in [("pb", pb), ("sum_of_best", sum_of_best)] {
+        if let Some(run) = run {
+            out += &format!("\n[{}]\n{}", block, run_text(run));
+        }
+    }
+
+    out
+}
+
+pub fn parse(text: &str) -> Result<Shared> {
+    let mut lines = text.lines();
+    ensure!(
+        lines.next() == Some(HEADER),
+        "Not a file written by share-splits, it should start with {:?}",
+        HEADER
+    );
+
+    let mut shared = Shared {
+        game: String::new(),
+        category: None,
+        sections: Vec::new(),
+        pb: None,
+        sum_of_best: None,
+    };
+    let mut block: Option<&str> = None;
+    let mut block_lines: Vec<&str> = Vec::new();
+    // A trailing empty line ends the last block
+    for line in lines.chain([""]) {
+        let line = line.trim();
+        // Within a block every line is part of it, also a section named like `[Boss]`
+        let header = line.strip_prefix('[').and_then(|l| l.strip_suffix(']'));
+        if let (None, Some(name)) = (block, header) {
+            block = Some(name);
+            continue;
+        }
+        match block {
+            Some(name) if line.is_empty() => {
+                match name {
+                    "sections" => {
+                        shared.sections = block_lines.iter().map(|&l| l.to_owned()).collect()
+                    }
+                    "pb" => shared.pb = Some(parse_run_lines(&block_lines)?),
+                    "sum_of_best" => shared.sum_of_best = Some(parse_run_lines(&block_lines)?),
+                    _ => bail!("Unknown block [{}]", name),
+                }
+                block = None;
+                block_lines.clear();
+            }
+            Some(_) => block_lines.push(line),
+            None if line.is_empty() || line.starts_with('#') => {}
+            None => {
+                let (key, value) = line
+                    .split_once(": ")
+                    .with_context(|| format!("Expected `key: value`, got {:?}", line))?;
+                match key {
+                    "game" => shared.game = value.to_owned(),
+                    "category" => shared.category = Some(value.to_owned()),
+                    _ => bail!("Unknown key {:?}", key),
+                }
+            }
+        }
+    }
+
+    Ok(shared)
+}
+
+/// Maps a shared run onto the sections of the config by their names. Sections of the config
+/// that the run doesn't have take no time, sections only the run has are merged into the
+/// next one. Returns the run and what had to be changed.
+pub fn adopt(config: &GameConfig, run: &[Section]) -> Result<(Vec<Section>, Vec<String>)> {
+    let own: Vec<Section> = config
+        .sections
+        .iter()
+        .map(|name| Section {
+            name: name.clone(),
+            time: 0,
+        })
+        .collect();
+
+    let mut adopted = Vec::new();
+    let mut report = Vec::new();
+    let mut missing = Vec::new();
+    for aligned in stats::align_sections(&own, run)? {
+        match aligned {
+            Aligned::Both(i, j) => {
+                let before = adopted.last().map_or(0, |s: &Section| s.time);
+                for name in missing.drain(..) {
+                    report.push(format!(
+                        "{} isn't in the shared splits, it takes no time before {}",
+                        name, own[i].name
+                    ));
+                    adopted.push(Section { name, time: before });
+                }
+                adopted.push(Section {
+                    name: own[i].name.clone(),
+                    time: run[j].time,
+                });
+            }
+            Aligned::OnlyFirst(i) => missing.push(own[i].name.clone()),
+            Aligned::OnlySecond(j) => report.push(format!(
+                "{} isn't one of your sections, its time is part of the next one",
+                run[j].name
+            )),
+        }
+    }
+    if let Some(first) = missing.first() {
+        report.push(format!(
+            "The shared splits end before {}, the comparison ends there",
+            first
+        ));
+    }
+    ensure!(
+        !adopted.is_empty(),
+        "The shared splits have none of the sections of {}",
+        config.directory_name
+    );
+
+    Ok((adopted, report))
+}
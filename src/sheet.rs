@@ -0,0 +1,70 @@
+//! A blank table of a game's sections to fill in by hand while practicing
+
+use crate::GameConfig;
+
+/// Width of the notes column, the other columns are sized from the config
+const NOTES_WIDTH: usize = 30;
+
+/// Renders `copies` empty tables with the section names and columns for the target, the
+/// actual time and notes. `time_width` is the width of a time in the UI's split table, so
+/// times written by hand take as much space as the timer's. With `targets`, the target
+/// column has the targets of the config.
+pub fn text(
+    config: &GameConfig,
+    time_width: usize,
+    targets: bool,
+    markdown: bool,
+    copies: usize,
+) -> String {
+    let target = |i: usize| {
+        let target = targets.then(|| config.targets.get(&config.sections[i]));
+        target.flatten().map_or("", |t| t.as_str())
+    };
+    let name_width = config.sections.iter().map(|s| s.chars().count()).max();
+    let name_width = name_width.unwrap_or(0).max("section".len());
+    let target_width = (0..config.sections.len())
+        .map(|i| target(i).chars().count())
+        .chain([time_width, "target".len()])
+        .max()
+        .unwrap_or(time_width);
+    let widths = [
+        name_width,
+        target_width,
+        time_width.max("actual".len()),
+        NOTES_WIDTH,
+    ];
+
+    let row = |cells: [&str; 4]| {
+        let cells = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell))
+            .collect::<Vec<_>>();
+        if markdown {
+            format!("| {} |\n", cells.join(" | "))
+        } else {
+            format!(" {}\n", cells.join(" | ").trim_end())
+        }
+    };
+    let dashes = widths.map(|width| "-".repeat(width + 2)).join("|");
+    let divider = if markdown {
+        format!("|{}|\n", dashes)
+    } else {
+        // Lined up with the rows like the divider of the split table
+        let mut divider = dashes;
+        divider.replace_range(0..1, " ");
+        divider.pop();
+        divider + "\n"
+    };
+
+    let mut table = row(["section", "target", "actual", "notes"]);
+    table += &divider;
+    for (i, section) in config.sections.iter().enumerate() {
+        table += &row([section, target(i), "", ""]);
+    }
+
+    let title = if markdown { "## " } else { "" };
+    let mut out = format!("{}{}\n\n", title, config.full_game_name);
+    out += &vec![table; copies.max(1)].join("\n");
+    out
+}
@@ -0,0 +1,430 @@
+//! Figures computed from the history of saved runs
+
+use crate::Section;
+use anyhow::{bail, Result};
+
+/// A saved dated run, which may end before the last section
+#[derive(Debug, Clone)]
+pub struct HistoricalRun {
+    pub name: String,
+    pub sections: Vec<Section>,
+}
+
+/// A time together with the run it comes from
+#[derive(Debug, Clone)]
+pub struct TimeInRun {
+    pub time: u32,
+    /// File name of the run it comes from
+    pub run: String,
+}
+
+/// Finds the best exit of every section: the fastest cumulative time it was ever left at,
+/// across complete and unfinished runs alike.
+///
+/// Unlike the PB, these can come from runs that died later on.
+pub fn best_exits(section_count: usize, history: &[HistoricalRun]) -> Vec<Option<TimeInRun>> {
+    (0..section_count)
+        .map(|i| {
+            history
+                .iter()
+                .filter_map(|run| run.sections.get(i).map(|s| (s.time, &run.name)))
+                .min_by_key(|&(time, _)| time)
+                .map(|(time, run)| TimeInRun {
+                    time,
+                    run: run.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Converts cumulative section times into the time spent in each section
+pub fn segment_times(sections: &[Section]) -> Vec<u32> {
+    let mut last = 0;
+    sections
+        .iter()
+        .map(|section| {
+            let segment = section.time.saturating_sub(last);
+            last = section.time;
+            segment
+        })
+        .collect()
+}
+
+/// The sections of a run that took no time, split in the same millisecond as the one before,
+/// like when a bridge sends two splits at once
+pub fn zero_segments(sections: &[Section]) -> Vec<usize> {
+    segment_times(sections)
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, segment)| segment == 0)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Whether a segment is a new gold. Segments of 0ms are never golds, and a stored gold of 0ms
+/// came from such a segment and is beaten by any real one.
+pub fn beats_gold(segment: u32, gold: u32) -> bool {
+    segment > 0 && (gold == 0 || segment < gold)
+}
+
+/// The gold after a segment, the segment if it beats the gold
+pub fn best_segment(gold: u32, segment: u32) -> u32 {
+    if beats_gold(segment, gold) {
+        segment
+    } else {
+        gold
+    }
+}
+
+/// Converts segment times back into cumulative sections with the given names
+pub fn cumulative<'a>(
+    names: impl IntoIterator<Item = &'a String>,
+    segments: &[u32],
+) -> Vec<Section> {
+    let mut total = 0;
+    names
+        .into_iter()
+        .zip(segments)
+        .map(|(name, segment)| {
+            total += segment;
+            Section {
+                name: name.clone(),
+                time: total,
+            }
+        })
+        .collect()
+}
+
+/// The middle of some times, or the mean of the two in the middle. Sorts the times.
+pub fn median(times: &mut [u32]) -> Option<u32> {
+    times.sort_unstable();
+    let middle = times.len() / 2;
+    match times.len() {
+        0 => None,
+        len if len % 2 == 0 => Some(((times[middle - 1] as u64 + times[middle] as u64) / 2) as u32),
+        _ => Some(times[middle]),
+    }
+}
+
+/// How a section went across the history
+#[derive(Debug, Clone)]
+pub struct SectionStats {
+    pub mean: u32,
+    pub median: u32,
+    /// The most recent segment times, oldest first
+    pub recent: Vec<u32>,
+    pub best: TimeInRun,
+    pub worst: TimeInRun,
+}
+
+/// Collects the segment times of a section from every run that completed it.
+/// None if no run did.
+pub fn section_stats(
+    history: &[HistoricalRun],
+    section: usize,
+    recent: usize,
+) -> Option<SectionStats> {
+    let segments = history
+        .iter()
+        .filter(|run| run.sections.len() > section)
+        .map(|run| (segment_times(&run.sections)[section], &run.name))
+        .collect::<Vec<_>>();
+
+    let (best_time, best_run) = segments.iter().min_by_key(|&&(time, _)| time)?;
+    let (worst_time, worst_run) = segments.iter().max_by_key(|&&(time, _)| time)?;
+
+    let mut sorted = segments.iter().map(|&(time, _)| time).collect::<Vec<_>>();
+    let median = median(&mut sorted)?;
+    let mean = (sorted.iter().map(|&t| t as u64).sum::<u64>() / sorted.len() as u64) as u32;
+
+    Some(SectionStats {
+        mean,
+        median,
+        recent: segments[segments.len().saturating_sub(recent)..]
+            .iter()
+            .map(|&(time, _)| time)
+            .collect(),
+        best: TimeInRun {
+            time: *best_time,
+            run: (*best_run).clone(),
+        },
+        worst: TimeInRun {
+            time: *worst_time,
+            run: (*worst_run).clone(),
+        },
+    })
+}
+
+/// Draws values as a line of block characters, scaled between their minimum and maximum
+pub fn sparkline(values: &[u32]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let min = values.iter().copied().min().unwrap_or(0);
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&value| {
+            if max == min {
+                BARS[BARS.len() / 2]
+            } else {
+                let level = (value - min) as u64 * (BARS.len() - 1) as u64 / (max - min) as u64;
+                BARS[level as usize]
+            }
+        })
+        .collect()
+}
+
+/// Every complete run that beat all runs before it, oldest first.
+/// The history has to be sorted by date, which the dated run names are.
+pub fn pb_progression(section_count: usize, history: &[HistoricalRun]) -> Vec<TimeInRun> {
+    let mut progression: Vec<TimeInRun> = Vec::new();
+    for run in history {
+        if run.sections.len() != section_count {
+            continue;
+        }
+        let Some(last) = run.sections.last() else {
+            continue;
+        };
+        if progression.last().is_none_or(|pb| last.time < pb.time) {
+            progression.push(TimeInRun {
+                time: last.time,
+                run: run.name.clone(),
+            });
+        }
+    }
+
+    progression
+}
+
+/// The run with the most golds at the time it was timed, replaying the history oldest first.
+/// A section's first segment is not counted, there was no gold to beat yet.
+pub fn most_golds(section_count: usize, history: &[HistoricalRun]) -> Option<(usize, String)> {
+    let mut golds: Vec<Option<u32>> = vec![None; section_count];
+    let mut most: Option<(usize, String)> = None;
+    for run in history {
+        let segments = segment_times(&run.sections);
+        let mut count = 0;
+        for (gold, &segment) in golds.iter_mut().zip(&segments) {
+            if gold.is_some_and(|gold| beats_gold(segment, gold)) {
+                count += 1;
+            }
+            *gold = Some(gold.map_or(segment, |gold| best_segment(gold, segment)));
+        }
+        if count > 0 && most.as_ref().is_none_or(|(most, _)| count > *most) {
+            most = Some((count, run.name.clone()));
+        }
+    }
+
+    most
+}
+
+/// The best time a single run could have finished in: its own segments, then the golds for
+/// the sections it didn't reach. Lies between the PB and the sum of best.
+pub fn best_single_run(section_count: usize, history: &[HistoricalRun]) -> Option<TimeInRun> {
+    let mut golds: Vec<Option<u32>> = vec![None; section_count];
+    for run in history {
+        for (gold, segment) in golds.iter_mut().zip(segment_times(&run.sections)) {
+            *gold = Some(gold.map_or(segment, |gold| gold.min(segment)));
+        }
+    }
+    let golds: Vec<u32> = golds.into_iter().collect::<Option<_>>()?;
+
+    history
+        .iter()
+        .map(|run| {
+            let own = run.sections.last().map_or(0, |s| s.time);
+            let rest = golds[run.sections.len()..].iter().sum::<u32>();
+            TimeInRun {
+                time: own + rest,
+                run: run.name.clone(),
+            }
+        })
+        .min_by_key(|potential| potential.time)
+}
+
+/// How many runs finished a section within its target, out of all runs that finished it
+pub fn target_hits(history: &[HistoricalRun], section: usize, target: u32) -> (usize, usize) {
+    let segments = history
+        .iter()
+        .filter(|run| run.sections.len() > section)
+        .map(|run| segment_times(&run.sections)[section])
+        .collect::<Vec<_>>();
+    let hits = segments.iter().filter(|&&time| time <= target).count();
+
+    (hits, segments.len())
+}
+
+/// Fewer comparable runs than this are too few to tell a likely finish from
+pub const MIN_COMPARABLE_RUNS: usize = 5;
+
+/// The time below which the given share of some sorted times lies, by nearest rank
+pub fn percentile(sorted: &[u32], percent: usize) -> Option<u32> {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+/// Where a run that left `section` at `time` likely finishes: the middle half of the finishes
+/// of complete runs that left it within `tolerance` of that time, each moved by the
+/// difference at the section. None with fewer than [`MIN_COMPARABLE_RUNS`] of them.
+pub fn finish_range(
+    section_count: usize,
+    history: &[HistoricalRun],
+    section: usize,
+    time: u32,
+    tolerance: u32,
+) -> Option<(u32, u32)> {
+    let mut finishes = history
+        .iter()
+        .filter(|run| run.sections.len() == section_count)
+        .filter(|run| {
+            run.sections
+                .get(section)
+                .is_some_and(|s| s.time.abs_diff(time) <= tolerance)
+        })
+        .map(|run| {
+            let finish = run.sections[section_count - 1].time;
+            time + finish.saturating_sub(run.sections[section].time)
+        })
+        .collect::<Vec<_>>();
+    if finishes.len() < MIN_COMPARABLE_RUNS {
+        return None;
+    }
+
+    finishes.sort_unstable();
+    Some((percentile(&finishes, 25)?, percentile(&finishes, 75)?))
+}
+
+/// What the history says about a run at some split
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pace {
+    /// Ahead of every run that got this far
+    BestEver,
+    /// Further behind the PB than any complete run ever made up on it from here
+    PbUnrecoverable,
+}
+
+/// Compares a run that left `section` at `time` with the runs that got as far, None with
+/// fewer than [`MIN_COMPARABLE_RUNS`] of them or when nothing stands out. A run makes up on
+/// the PB by finishing the sections after `section` faster than the PB did.
+pub fn pace(
+    section_count: usize,
+    history: &[HistoricalRun],
+    pb: Option<&[Section]>,
+    section: usize,
+    time: u32,
+) -> Option<Pace> {
+    let reached = history
+        .iter()
+        .filter_map(|run| run.sections.get(section))
+        .map(|s| s.time)
+        .collect::<Vec<_>>();
+    if reached.len() < MIN_COMPARABLE_RUNS {
+        return None;
+    }
+    if reached.iter().all(|&t| time < t) {
+        return Some(Pace::BestEver);
+    }
+
+    let pb = pb.filter(|pb| pb.len() == section_count)?;
+    let behind = time.checked_sub(pb[section].time)?;
+    let pb_rest = pb[section_count - 1].time.saturating_sub(pb[section].time);
+    let recovered = history
+        .iter()
+        .filter(|run| run.sections.len() == section_count)
+        .map(|run| {
+            let rest = run.sections[section_count - 1]
+                .time
+                .saturating_sub(run.sections[section].time);
+            pb_rest.saturating_sub(rest)
+        })
+        .max()?;
+    (behind > recovered).then_some(Pace::PbUnrecoverable)
+}
+
+/// The milliseconds spent in each section over every attempt: the segments of every run,
+/// complete or not, and `abandoned`, the time spent in the section a reset run was abandoned
+/// in
+pub fn time_spent(
+    section_count: usize,
+    history: &[HistoricalRun],
+    abandoned: &[(usize, u32)],
+) -> Vec<u64> {
+    let mut spent = vec![0; section_count];
+    for run in history {
+        for (total, segment) in spent.iter_mut().zip(segment_times(&run.sections)) {
+            *total += segment as u64;
+        }
+    }
+    for &(section, time) in abandoned {
+        if let Some(total) = spent.get_mut(section) {
+            *total += time as u64;
+        }
+    }
+
+    spent
+}
+
+/// Where a section of one run lines up with the other run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aligned {
+    /// Same name in both runs, indices into the first and second run
+    Both(usize, usize),
+    OnlyFirst(usize),
+    OnlySecond(usize),
+}
+
+/// Lines up two runs with different section lists by their section names, keeping the order
+/// of both. Names that can't be matched in only one way are reported instead of guessed.
+pub fn align_sections(first: &[Section], second: &[Section]) -> Result<Vec<Aligned>> {
+    let count = |run: &[Section], name: &str| run.iter().filter(|s| s.name == name).count();
+    let is_common = |name: &str| count(first, name) > 0 && count(second, name) > 0;
+
+    let mut ambiguities = Vec::new();
+    for name in first.iter().chain(second).map(|s| s.name.as_str()) {
+        let repeated = count(first, name) > 1 || count(second, name) > 1;
+        let message = format!("{:?} appears more than once", name);
+        if is_common(name) && repeated && !ambiguities.contains(&message) {
+            ambiguities.push(message);
+        }
+    }
+
+    let common_first = first
+        .iter()
+        .filter(|s| is_common(&s.name))
+        .map(|s| s.name.as_str())
+        .collect::<Vec<_>>();
+    let common_second = second
+        .iter()
+        .filter(|s| is_common(&s.name))
+        .map(|s| s.name.as_str())
+        .collect::<Vec<_>>();
+    if ambiguities.is_empty() && common_first != common_second {
+        ambiguities.push(format!(
+            "the shared sections are in a different order: {:?} and {:?}",
+            common_first, common_second
+        ));
+    }
+    if !ambiguities.is_empty() {
+        bail!("Can't line up the runs: {}", ambiguities.join(", "));
+    }
+
+    let mut aligned = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < first.len() || j < second.len() {
+        let first_common = first.get(i).is_some_and(|s| is_common(&s.name));
+        let second_common = second.get(j).is_some_and(|s| is_common(&s.name));
+        if first_common && second_common {
+            aligned.push(Aligned::Both(i, j));
+            i += 1;
+            j += 1;
+        } else if i < first.len() && !first_common {
+            aligned.push(Aligned::OnlyFirst(i));
+            i += 1;
+        } else {
+            aligned.push(Aligned::OnlySecond(j));
+            j += 1;
+        }
+    }
+
+    Ok(aligned)
+}
@@ -0,0 +1,680 @@
+//! Where a game's runs, their metadata and its logs are kept. The `files` storage keeps each
+//! of them in a file of its own, the `sqlite` storage keeps them all in the game's `speedy.db`.
+//! The config's `storage` picks one and `speedy migrate` moves a game from one to the other.
+
+use crate::{
+    ensure_inside, game_dir, load_run_file, log_path, run_text, write_atomically, Section,
+};
+use anyhow::{ensure, Context, Result};
+use clap::ValueEnum;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How a game keeps its runs, `storage` in the config
+#[derive(Serialize, Deserialize, ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Storage {
+    /// A file for every run, its metadata and every log
+    #[default]
+    Files,
+    /// Everything in the game's `speedy.db`
+    Sqlite,
+}
+
+impl Storage {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// As written in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Files => "files",
+            Self::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// The logs kept in the store. `current.log` and `bridge.log` stay files with either storage,
+/// they are only read after a crash or while looking into a bridge.
+pub const LOGS: &[&str] = &["pb_history.log", "golds.log", "resets.log", "session.log"];
+
+/// The runs, metadata and logs of one game.
+///
+/// Runs are named like their files: `pb.run`, `sum_of_best.run`, dated runs, `.bak` copies and
+/// the runs in `rejected/`, `shared/` and `routes/<route>/`.
+pub trait RunStore: Send + Sync {
+    /// The sections of a run, None if there is no such run
+    fn load_run(&self, run: &str) -> Result<Option<Vec<Section>>>;
+    fn save_run(&self, run: &str, sections: &[Section]) -> Result<()>;
+    /// Deletes a run, its metadata stays
+    fn delete_run(&self, run: &str) -> Result<()>;
+    /// Moves a run and its metadata to another name, replacing what was there
+    fn rename_run(&self, from: &str, to: &str) -> Result<()>;
+    /// Every run that the store holds the sections of, sorted
+    fn runs(&self) -> Result<Vec<String>>;
+    /// Every run that the store holds the sections or the metadata of, sorted
+    fn names(&self) -> Result<Vec<String>>;
+    /// Changes whenever the run is saved, None if there is no such run
+    fn version(&self, run: &str) -> Result<Option<String>>;
+    /// The metadata of a run as TOML
+    fn load_metadata(&self, run: &str) -> Result<Option<String>>;
+    fn save_metadata(&self, run: &str, metadata: &str) -> Result<()>;
+    /// The lines of one of the `LOGS`, empty if nothing was logged yet
+    fn read_log(&self, log: &str) -> Result<Vec<String>>;
+    fn append_log(&self, log: &str, line: &str) -> Result<()>;
+    /// Replaces the lines of a log, to drop some of them
+    fn write_log(&self, log: &str, lines: &[String]) -> Result<()>;
+    /// Where a run is kept, to show before it is changed
+    fn location(&self, run: &str) -> PathBuf;
+    /// Removes every run, metadata and log, once `speedy migrate` copied them elsewhere
+    fn clear(&self) -> Result<()>;
+}
+
+/// Stores that were opened, by game directory. Each game's store stays open for the rest of
+/// the process, so a database isn't opened again for every run that is read.
+static STORES: Mutex<BTreeMap<PathBuf, Arc<dyn RunStore>>> = Mutex::new(BTreeMap::new());
+
+/// The store of a game, with the storage of its config
+pub fn open(game: &str) -> Result<Arc<dyn RunStore>> {
+    let dir = game_dir(game)?;
+    let mut stores = STORES.lock().unwrap();
+    if let Some(store) = stores.get(&dir) {
+        return Ok(Arc::clone(store));
+    }
+    let store = new(game, configured(&dir)?)?;
+    stores.insert(dir, Arc::clone(&store));
+    Ok(store)
+}
+
+/// Opens a game's store with a storage other than the configured one, for `speedy migrate`.
+/// Stores opened before are forgotten, so the game is opened again with its new storage.
+pub fn open_as(game: &str, storage: Storage) -> Result<Arc<dyn RunStore>> {
+    STORES.lock().unwrap().remove(&game_dir(game)?);
+    new(game, storage)
+}
+
+#[cfg(test)]
+/// Makes `open` return the store for the game
+pub fn set(game: &str, store: Arc<dyn RunStore>) -> Result<()> {
+    STORES.lock().unwrap().insert(game_dir(game)?, store);
+    Ok(())
+}
+
+fn new(game: &str, storage: Storage) -> Result<Arc<dyn RunStore>> {
+    Ok(match storage {
+        Storage::Files => Arc::new(FileStore::new(game)?),
+        Storage::Sqlite => Arc::new(SqliteStore::open(&game_dir(game)?.join("speedy.db"))?),
+    })
+}
+
+/// The `storage` of a game's config, without loading all of it. Games without a config keep
+/// their runs in files, which tells what is missing when they are read.
+fn configured(dir: &Path) -> Result<Storage> {
+    #[derive(Deserialize)]
+    struct Config {
+        #[serde(default)]
+        storage: Storage,
+    }
+
+    let path = dir.join("config.toml");
+    let config_str = match fs::read_to_string(&path) {
+        Ok(config_str) => config_str,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Storage::Files),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let config: Config = toml::from_str(&config_str)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(config.storage)
+}
+
+/// Copies everything of one store into another, which should be empty. Returns how many runs
+/// and logs were copied.
+pub fn copy(from: &dyn RunStore, to: &dyn RunStore) -> Result<(usize, usize)> {
+    let names = from.names()?;
+    let mut runs = 0;
+    for name in &names {
+        if let Some(sections) = from.load_run(name)? {
+            to.save_run(name, &sections)?;
+            runs += 1;
+        }
+        if let Some(metadata) = from.load_metadata(name)? {
+            to.save_metadata(name, &metadata)?;
+        }
+    }
+    let mut logs = 0;
+    for log in LOGS {
+        let lines = from.read_log(log)?;
+        if !lines.is_empty() {
+            to.write_log(log, &lines)?;
+            logs += 1;
+        }
+    }
+
+    Ok((runs, logs))
+}
+
+/// Checks that two stores hold the same runs, metadata and logs
+pub fn ensure_same(a: &dyn RunStore, b: &dyn RunStore) -> Result<()> {
+    let names = a.names()?;
+    ensure!(
+        names == b.names()?,
+        "{} and {} hold different runs",
+        a.location("").display(),
+        b.location("").display()
+    );
+    for name in &names {
+        let text = |store: &dyn RunStore| -> Result<_> {
+            Ok(store.load_run(name)?.as_deref().map(run_text))
+        };
+        ensure!(text(a)? == text(b)?, "{} differs", name);
+        ensure!(
+            a.load_metadata(name)? == b.load_metadata(name)?,
+            "The metadata of {} differs",
+            name
+        );
+    }
+    for log in LOGS {
+        ensure!(a.read_log(log)? == b.read_log(log)?, "{} differs", log);
+    }
+
+    Ok(())
+}
+
+/// Nanoseconds since the epoch, as the version of a run
+fn nanos(time: SystemTime) -> String {
+    let nanos = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+    nanos.to_string()
+}
+
+/// Metadata of `2024-01-02T10:11:12.run` lives in `2024-01-02T10:11:12.toml`
+pub fn metadata_name(run: &str) -> String {
+    format!("{}.toml", run.trim_end_matches(".run"))
+}
+
+/// The run whose metadata is in a file, undoing `metadata_name`
+fn metadata_run(file: &str) -> Option<String> {
+    let stem = file.strip_suffix(".toml")?;
+    Some(if stem.ends_with(".bak") {
+        stem.to_owned()
+    } else {
+        format!("{}.run", stem)
+    })
+}
+
+/// Runs in the game directory, each in a file like `pb.run` next to their metadata in
+/// `pb.toml`, with the logs in the state directory
+pub struct FileStore {
+    game: String,
+    dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(game: &str) -> Result<Self> {
+        Ok(Self {
+            game: game.to_owned(),
+            dir: game_dir(game)?,
+        })
+    }
+
+    /// The file of a run or its metadata. Names only come from speedy, but a link in the game
+    /// directory could still lead elsewhere.
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        ensure!(
+            name.split('/')
+                .all(|part| !part.is_empty() && part != "." && part != ".."),
+            "{:?} is no run name",
+            name
+        );
+        let path = self.dir.join(name);
+        let link = path
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_symlink());
+        if link || name.contains('/') {
+            ensure_inside(&self.dir, &path)?;
+        }
+        Ok(path)
+    }
+
+    /// The runs in a directory and the ones below it, with their names relative to the game
+    /// directory, and the runs that metadata files there belong to
+    fn walk(
+        &self,
+        dir: &Path,
+        prefix: &str,
+        runs: &mut Vec<String>,
+        metadata: &mut Vec<String>,
+    ) -> Result<()> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !prefix.is_empty() => return Ok(()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", dir.display())),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let Ok(file) = entry.file_name().into_string() else {
+                continue;
+            };
+            if file.starts_with('.') {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                if prefix.is_empty() && matches!(&*file, "rejected" | "shared" | "routes")
+                    || prefix == "routes/"
+                {
+                    let prefix = format!("{}{}/", prefix, file);
+                    self.walk(&entry.path(), &prefix, runs, metadata)?;
+                }
+                continue;
+            }
+            if file.ends_with(".run") || file.ends_with(".run.bak") {
+                runs.push(format!("{}{}", prefix, file));
+            } else if prefix.is_empty() && matches!(&*file, "config.toml" | "index.toml") {
+                continue;
+            } else if let Some(run) = metadata_run(&file) {
+                metadata.push(format!("{}{}", prefix, run));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl RunStore for FileStore {
+    fn load_run(&self, run: &str) -> Result<Option<Vec<Section>>> {
+        load_run_file(&self.path(run)?)
+    }
+
+    fn save_run(&self, run: &str, sections: &[Section]) -> Result<()> {
+        let path = self.path(run)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        write_atomically(&path, run_text(sections).as_bytes())
+    }
+
+    fn delete_run(&self, run: &str) -> Result<()> {
+        let path = self.path(run)?;
+        fs::remove_file(&path).with_context(|| format!("Failed to delete {}", path.display()))
+    }
+
+    fn rename_run(&self, from: &str, to: &str) -> Result<()> {
+        for (from, to) in [
+            (from.to_owned(), to.to_owned()),
+            (metadata_name(from), metadata_name(to)),
+        ] {
+            let (from, to) = (self.path(&from)?, self.path(&to)?);
+            if !from.exists() {
+                continue;
+            }
+            if let Some(dir) = to.parent() {
+                fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create {}", dir.display()))?;
+            }
+            fs::rename(&from, &to).with_context(|| {
+                format!("Failed to move {} to {}", from.display(), to.display())
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn runs(&self) -> Result<Vec<String>> {
+        let mut runs = Vec::new();
+        self.walk(&self.dir, "", &mut runs, &mut Vec::new())?;
+        runs.sort();
+        Ok(runs)
+    }
+
+    fn names(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        let mut metadata = Vec::new();
+        self.walk(&self.dir, "", &mut names, &mut metadata)?;
+        names.extend(metadata);
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    fn version(&self, run: &str) -> Result<Option<String>> {
+        match fs::metadata(self.path(run)?) {
+            Ok(metadata) => Ok(Some(nanos(metadata.modified()?))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn load_metadata(&self, run: &str) -> Result<Option<String>> {
+        let path = self.path(&metadata_name(run))?;
+        match fs::read_to_string(&path) {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    fn save_metadata(&self, run: &str, metadata: &str) -> Result<()> {
+        write_atomically(&self.path(&metadata_name(run))?, metadata.as_bytes())
+    }
+
+    fn read_log(&self, log: &str) -> Result<Vec<String>> {
+        let path = log_path(&self.game, log)?;
+        match fs::read_to_string(&path) {
+            Ok(text) => Ok(text.lines().map(str::to_owned).collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    fn append_log(&self, log: &str, line: &str) -> Result<()> {
+        let mut file = crate::open_log(&self.game, log)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    fn write_log(&self, log: &str, lines: &[String]) -> Result<()> {
+        let path = log_path(&self.game, log)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let text: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        write_atomically(&path, text.as_bytes())
+    }
+
+    fn location(&self, run: &str) -> PathBuf {
+        self.dir.join(run)
+    }
+
+    fn clear(&self) -> Result<()> {
+        for name in self.names()? {
+            for file in [name.clone(), metadata_name(&name)] {
+                let path = self.path(&file)?;
+                match fs::remove_file(&path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                        return Err(e)
+                            .with_context(|| format!("Failed to delete {}", path.display()))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for log in LOGS {
+            let path = log_path(&self.game, log)?;
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    return Err(e).with_context(|| format!("Failed to delete {}", path.display()))
+                }
+                _ => {}
+            }
+        }
+        // Only removed when empty, anything else in them was put there by hand
+        if let Ok(routes) = fs::read_dir(self.dir.join("routes")) {
+            for route in routes.flatten() {
+                let _ = fs::remove_dir(route.path());
+            }
+        }
+        for dir in ["rejected", "shared", "routes"] {
+            let _ = fs::remove_dir(self.dir.join(dir));
+        }
+
+        Ok(())
+    }
+}
+
+/// Every run, metadata and log of a game in one SQLite database. The sections of the runs
+/// get a row each, so the database can be queried without speedy.
+pub struct SqliteStore {
+    path: PathBuf,
+    connection: Mutex<Connection>,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        name TEXT PRIMARY KEY,
+        version TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS sections (
+        run TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        time_ms INTEGER NOT NULL,
+        PRIMARY KEY (run, position)
+    );
+    CREATE TABLE IF NOT EXISTS metadata (
+        run TEXT PRIMARY KEY,
+        toml TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS log_lines (
+        id INTEGER PRIMARY KEY,
+        log TEXT NOT NULL,
+        line TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS log_lines_by_log ON log_lines (log, id);
+";
+
+impl SqliteStore {
+    /// Opens the database, creating it if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection =
+            Connection::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        // Only one speedy writes at a time, others wait for the game's lock before that
+        connection.busy_timeout(std::time::Duration::from_secs(10))?;
+        connection
+            .execute_batch(SCHEMA)
+            .with_context(|| format!("Failed to set up {}", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>) -> Result<T> {
+        let mut connection = self.connection.lock().unwrap();
+        f(&mut connection).with_context(|| format!("Failed to use {}", self.path.display()))
+    }
+}
+
+impl RunStore for SqliteStore {
+    fn load_run(&self, run: &str) -> Result<Option<Vec<Section>>> {
+        self.with(|connection| {
+            let exists = connection
+                .query_row("SELECT 1 FROM runs WHERE name = ?1", [run], |_| Ok(()))
+                .optional()?;
+            if exists.is_none() {
+                return Ok(None);
+            }
+            let mut statement = connection.prepare_cached(
+                "SELECT name, time_ms FROM sections WHERE run = ?1 ORDER BY position",
+            )?;
+            let sections = statement
+                .query_map([run], |row| {
+                    Ok(Section {
+                        name: row.get(0)?,
+                        time: row.get(1)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(Some(sections))
+        })
+    }
+
+    fn save_run(&self, run: &str, sections: &[Section]) -> Result<()> {
+        self.with(|connection| {
+            let transaction = connection.transaction()?;
+            transaction.execute(
+                "INSERT OR REPLACE INTO runs (name, version) VALUES (?1, ?2)",
+                params![run, nanos(SystemTime::now())],
+            )?;
+            transaction.execute("DELETE FROM sections WHERE run = ?1", [run])?;
+            for (position, section) in sections.iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO sections (run, position, name, time_ms) VALUES (?1, ?2, ?3, ?4)",
+                    params![run, position, section.name, section.time],
+                )?;
+            }
+            transaction.commit()
+        })
+    }
+
+    fn delete_run(&self, run: &str) -> Result<()> {
+        let deleted = self.with(|connection| {
+            let transaction = connection.transaction()?;
+            let deleted = transaction.execute("DELETE FROM runs WHERE name = ?1", [run])?;
+            transaction.execute("DELETE FROM sections WHERE run = ?1", [run])?;
+            transaction.commit()?;
+            Ok(deleted)
+        })?;
+        ensure!(
+            deleted > 0,
+            "{} does not exist",
+            self.location(run).display()
+        );
+        Ok(())
+    }
+
+    fn rename_run(&self, from: &str, to: &str) -> Result<()> {
+        self.with(|connection| {
+            let transaction = connection.transaction()?;
+            let exists = |table: &str, column: &str| {
+                transaction
+                    .query_row(
+                        &format!("SELECT 1 FROM {} WHERE {} = ?1", table, column),
+                        [from],
+                        |_| Ok(()),
+                    )
+                    .optional()
+                    .map(|row| row.is_some())
+            };
+            let mut tables = Vec::new();
+            if exists("runs", "name")? {
+                tables.extend([("runs", "name"), ("sections", "run")]);
+            }
+            if exists("metadata", "run")? {
+                tables.push(("metadata", "run"));
+            }
+            for (table, column) in tables {
+                let delete = format!("DELETE FROM {} WHERE {} = ?1", table, column);
+                transaction.execute(&delete, [to])?;
+                let update = format!("UPDATE {} SET {} = ?2 WHERE {} = ?1", table, column, column);
+                transaction.execute(&update, [from, to])?;
+            }
+            transaction.commit()
+        })
+    }
+
+    fn runs(&self) -> Result<Vec<String>> {
+        self.with(|connection| {
+            let mut statement = connection.prepare_cached("SELECT name FROM runs ORDER BY name")?;
+            let runs = statement
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(runs)
+        })
+    }
+
+    fn names(&self) -> Result<Vec<String>> {
+        self.with(|connection| {
+            let mut statement = connection.prepare_cached(
+                "SELECT name FROM runs UNION SELECT run FROM metadata ORDER BY 1",
+            )?;
+            let names = statement
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(names)
+        })
+    }
+
+    fn version(&self, run: &str) -> Result<Option<String>> {
+        self.with(|connection| {
+            connection
+                .query_row("SELECT version FROM runs WHERE name = ?1", [run], |row| {
+                    row.get(0)
+                })
+                .optional()
+        })
+    }
+
+    fn load_metadata(&self, run: &str) -> Result<Option<String>> {
+        self.with(|connection| {
+            connection
+                .query_row("SELECT toml FROM metadata WHERE run = ?1", [run], |row| {
+                    row.get(0)
+                })
+                .optional()
+        })
+    }
+
+    fn save_metadata(&self, run: &str, metadata: &str) -> Result<()> {
+        self.with(|connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO metadata (run, toml) VALUES (?1, ?2)",
+                [run, metadata],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn read_log(&self, log: &str) -> Result<Vec<String>> {
+        self.with(|connection| {
+            let mut statement = connection
+                .prepare_cached("SELECT line FROM log_lines WHERE log = ?1 ORDER BY id")?;
+            let lines = statement
+                .query_map([log], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(lines)
+        })
+    }
+
+    fn append_log(&self, log: &str, line: &str) -> Result<()> {
+        self.with(|connection| {
+            connection.execute(
+                "INSERT INTO log_lines (log, line) VALUES (?1, ?2)",
+                [log, line],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn write_log(&self, log: &str, lines: &[String]) -> Result<()> {
+        self.with(|connection| {
+            let transaction = connection.transaction()?;
+            transaction.execute("DELETE FROM log_lines WHERE log = ?1", [log])?;
+            for line in lines {
+                transaction.execute(
+                    "INSERT INTO log_lines (log, line) VALUES (?1, ?2)",
+                    [log, line],
+                )?;
+            }
+            transaction.commit()
+        })
+    }
+
+    fn location(&self, run: &str) -> PathBuf {
+        if run.is_empty() {
+            return self.path.clone();
+        }
+        let mut location = self.path.clone().into_os_string();
+        location.push(":");
+        location.push(run);
+        location.into()
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.with(|connection| {
+            connection.execute_batch(
+                "DELETE FROM runs; DELETE FROM sections; DELETE FROM metadata; \
+                 DELETE FROM log_lines;",
+            )
+        })
+    }
+}
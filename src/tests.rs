@@ -0,0 +1,1729 @@
+//! Tests of saving runs, against a temporary data directory. The data directory is set once
+//! per process, so every test makes games of its own in it.
+
+use super::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex, OnceLock};
+use store::RunStore;
+
+static TEMP: OnceLock<tempfile::TempDir> = OnceLock::new();
+
+/// A game with a fresh directory and the given sections
+fn new_game(sections: &[&str]) -> String {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    TEMP.get_or_init(|| {
+        let temp = tempfile::TempDir::new().unwrap();
+        for dir in [&DATA_DIR, &STATE_DIR, &CACHE_DIR] {
+            dir.set(temp.path().join("data")).unwrap();
+        }
+        // Away from the settings of whoever runs the tests
+        std::env::set_var("XDG_CONFIG_HOME", temp.path().join("config"));
+        temp
+    });
+
+    let game = format!("game{}", NEXT.fetch_add(1, Ordering::Relaxed));
+    let dir = game_path(&game);
+    fs::create_dir_all(&dir).unwrap();
+    let sections = sections
+        .iter()
+        .map(|s| format!("{:?}", s))
+        .collect::<Vec<_>>()
+        .join(", ");
+    fs::write(
+        dir.join("config.toml"),
+        format!(
+            "version = 1\nfull_game_name = \"Game\"\nsections = [{}]\n",
+            sections
+        ),
+    )
+    .unwrap();
+    game
+}
+
+/// A run of the game that hasn't started yet
+fn app(game: &str) -> Arc<RwLock<RunApp>> {
+    let app = RunApp::prepare_run(load_config(game).unwrap(), &Default::default()).unwrap();
+    Arc::new(RwLock::new(app))
+}
+
+/// Sections named A, B, … ending at the times
+fn sections(times: &[u32]) -> Vec<Section> {
+    times
+        .iter()
+        .zip('A'..)
+        .map(|(&time, name)| Section {
+            name: name.to_string(),
+            time,
+        })
+        .collect()
+}
+
+/// Where the files of the game are, like the data directory of speedy
+fn game_path(game: &str) -> PathBuf {
+    TEMP.get().unwrap().path().join("data").join(game)
+}
+
+fn read(game: &str, file: &str) -> String {
+    fs::read_to_string(game_path(game).join(file)).unwrap()
+}
+
+#[test]
+fn runs_started_in_the_same_second_get_their_own_files() {
+    let game = new_game(&["A", "B"]);
+    let mut app = RunApp::prepare_run(load_config(&game).unwrap(), &Default::default()).unwrap();
+    // Every save has the same start
+    for times in [[1000, 2000], [900, 1900], [800, 1800]] {
+        app.current_sections = sections(&times);
+        app.save_progress = SaveProgress::default();
+        app.saved_run = None;
+        app.save().unwrap();
+    }
+
+    let runs = list_runs(&game).unwrap();
+    assert_eq!(runs.len(), 3, "{:?}", runs);
+    let stem = runs[0].strip_suffix(".run").unwrap();
+    assert_eq!(runs[1], format!("{}_2.run", stem));
+    assert_eq!(runs[2], format!("{}_3.run", stem));
+    assert_eq!(read(&game, &runs[0]), "A: 0m01.000s\nB: 0m02.000s\n");
+    assert_eq!(read(&game, &runs[1]), "A: 0m00.900s\nB: 0m01.900s\n");
+    assert_eq!(read(&game, &runs[2]), "A: 0m00.800s\nB: 0m01.800s\n");
+}
+
+#[test]
+fn run_selectors() {
+    let game = new_game(&["A"]);
+    let error = |selector| {
+        resolve_run_selector(&game, selector)
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(error("pb").contains("does not exist"));
+    assert!(error("sob").contains("does not exist"));
+    assert!(error("latest").contains("only has 0 runs"));
+
+    for run in [
+        "2024-01-01T10:00:00.run",
+        "2024-01-02T09:00:00.run",
+        "2024-01-02T18:00:00.run",
+        "pb.run",
+        "sum_of_best.run",
+    ] {
+        save_run(&game, run, &sections(&[1000])).unwrap();
+    }
+    let resolve = |selector| resolve_run_selector(&game, selector).unwrap();
+    assert_eq!(resolve("pb"), "pb.run");
+    assert_eq!(resolve("sob"), "sum_of_best.run");
+    assert_eq!(resolve("latest"), "2024-01-02T18:00:00.run");
+    assert_eq!(resolve("latest-0"), "2024-01-02T18:00:00.run");
+    assert_eq!(resolve("latest-2"), "2024-01-01T10:00:00.run");
+    assert_eq!(resolve("2024-01-01"), "2024-01-01T10:00:00.run");
+    assert_eq!(resolve("2024-01-02T09"), "2024-01-02T09:00:00.run");
+    assert_eq!(
+        resolve("2024-01-02T18:00:00.run"),
+        "2024-01-02T18:00:00.run"
+    );
+
+    assert!(error("latest-3").contains("only has 3 runs"));
+    for selector in ["latest-", "latest-x", "latest2", "latest--1"] {
+        assert!(
+            error(selector).contains("Invalid run selector"),
+            "{}",
+            selector
+        );
+    }
+    let ambiguous = error("2024-01-02");
+    assert!(ambiguous.contains("matches several runs"), "{}", ambiguous);
+    assert!(ambiguous.contains("2024-01-02T09:00:00.run"));
+    assert!(ambiguous.contains("2024-01-02T18:00:00.run"));
+    assert!(!ambiguous.contains("2024-01-01"));
+    // The comparisons are only found by their short names
+    assert!(error("2025").contains("No run of"));
+    assert!(error("pb.run").contains("No run of"));
+    assert!(error("").contains("matches several runs"));
+}
+
+#[test]
+fn fifty_signals_never_wait_for_playback() {
+    let sections: Vec<String> = (0..50).map(|i| format!("S{}", i)).collect();
+    let game = new_game(&sections.iter().map(String::as_str).collect::<Vec<_>>());
+    let app = app(&game);
+    let mut sounds = silent_sounds(&app);
+    // An audio output that hangs until the end of the test
+    let (sender, receiver) = mpsc::channel::<Sound>();
+    let (release, released) = mpsc::channel::<()>();
+    let player = std::thread::spawn(move || {
+        let _ = released.recv();
+        receiver.try_iter().count()
+    });
+    sounds.player = Player {
+        sender: Some(sender),
+    };
+
+    let mut slowest = Duration::ZERO;
+    for _ in 0..50 {
+        let started = Instant::now();
+        RunApp::handle_signal(&app, Some(&sounds), SIGUSR1).unwrap();
+        slowest = slowest.max(started.elapsed());
+    }
+    assert!(slowest < Duration::from_millis(100), "{:?}", slowest);
+    assert_eq!(app.read().unwrap().current_sections.len(), 50);
+
+    drop(release);
+    // Every sound is still queued, the start and 49 split beeps
+    assert_eq!(player.join().unwrap(), 50);
+}
+
+/// Compares `actual` with the snapshot in `tests/snapshots`, or writes it with
+/// `UPDATE_SNAPSHOTS=1`
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}: {}, run with UPDATE_SNAPSHOTS=1", path.display(), e));
+    assert!(
+        actual == expected,
+        "{} changed, run with UPDATE_SNAPSHOTS=1 if that's intended:\n{}",
+        path.display(),
+        actual
+    );
+}
+
+#[test]
+fn report_snapshot() {
+    let mut config = load_config(&new_game(&["A", "B & C", "<D>"])).unwrap();
+    config.full_game_name = "Mario & <Luigi>".to_owned();
+    let run = |name: &str, times: &[u32]| stats::HistoricalRun {
+        name: name.to_owned(),
+        sections: sections(times)
+            .into_iter()
+            .zip(&config.sections)
+            .map(|(section, name)| Section {
+                name: name.clone(),
+                ..section
+            })
+            .collect(),
+    };
+    let history = [
+        run("2024-01-01T10:00:00.run", &[60_000, 130_000, 200_000]),
+        run("2024-01-02T10:00:00.run", &[58_000, 140_000]),
+        run("2024-01-03T10:00:00.run", &[59_000, 125_000, 190_500]),
+        run("2024-01-04T10:00:00.run", &[61_000, 128_000, 195_000]),
+    ];
+    let pb = &history[2].sections;
+    let sum_of_best = run("sum_of_best.run", &[58_000, 123_000, 188_500]).sections;
+
+    let html = report::html(&config, Some(pb), Some(&sum_of_best), &history);
+    assert_snapshot("report.html", &html);
+
+    let empty = report::html(&config, None, None, &[]);
+    assert_snapshot("report_empty.html", &empty);
+}
+
+/// The parts of the splits.io Exchange Format v1.0.1 that speedy writes, unknown fields
+/// are rejected so a typo can't slip through
+mod exchange_schema {
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase", deny_unknown_fields)]
+    pub struct Document {
+        #[serde(rename = "_schemaVersion")]
+        pub schema_version: String,
+        pub id: Option<String>,
+        pub timer: Timer,
+        pub started_at: Option<String>,
+        pub attempts: Attempts,
+        pub game: Named,
+        pub category: Named,
+        pub runners: Vec<Named>,
+        pub default_timing: String,
+        pub segments: Vec<Segment>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Timer {
+        pub shortname: String,
+        pub longname: String,
+        pub version: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Named {
+        pub longname: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Attempts {
+        pub total: usize,
+        pub histories: Vec<Attempt>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase", deny_unknown_fields)]
+    pub struct Attempt {
+        pub attempt_number: usize,
+        #[serde(rename = "realtimeDurationMS")]
+        pub realtime_duration_ms: Option<u32>,
+        pub started_at: Option<String>,
+        pub ended_at: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase", deny_unknown_fields)]
+    pub struct Segment {
+        pub id: String,
+        pub name: String,
+        pub ended_at: Duration,
+        pub best_duration: Duration,
+        pub histories: Vec<SegmentAttempt>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Duration {
+        #[serde(rename = "realtimeMS")]
+        pub realtime_ms: u32,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase", deny_unknown_fields)]
+    pub struct SegmentAttempt {
+        pub attempt_number: usize,
+        #[serde(rename = "realtimeMS")]
+        pub realtime_ms: u32,
+    }
+}
+
+/// A date of the exchange format, which has to be RFC 3339
+fn date(date: Option<&str>) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    date.map(|date| chrono::DateTime::parse_from_rfc3339(date).unwrap())
+}
+
+#[test]
+fn exchange_export_round_trips() {
+    let game = new_game(&["A", "B", "C"]);
+    let config = load_config(&game).unwrap();
+    let history = [
+        stats::HistoricalRun {
+            name: "2024-01-01T10:00:00.run".to_owned(),
+            sections: sections(&[1000, 2500, 4000]),
+        },
+        stats::HistoricalRun {
+            name: "2024-01-02T10:00:00.run".to_owned(),
+            sections: sections(&[900]),
+        },
+        stats::HistoricalRun {
+            name: "2024-01-03T10:00:00_2.run".to_owned(),
+            sections: sections(&[1100, 2300, 3900]),
+        },
+    ];
+    let sum_of_best = sections(&[900, 2100, 3600]);
+    let run = &history[2];
+
+    let exported = export::exchange(
+        &config,
+        &run.name,
+        &run.sections,
+        Some(&sum_of_best),
+        &history,
+        true,
+    );
+    let text = serde_json::to_string_pretty(&exported).unwrap();
+    let document: exchange_schema::Document = serde_json::from_str(&text).unwrap();
+
+    assert_eq!(document.schema_version, "v1.0.1");
+    assert_eq!(document.timer.shortname, "speedy");
+    assert_eq!(document.timer.longname, "Speedy");
+    assert!(document.timer.version.starts_with('v'));
+    assert_eq!(document.game.longname, "Game");
+    assert_eq!(document.category.longname, "Any%");
+    assert!(document.runners.is_empty());
+    assert_eq!(document.default_timing, "real");
+    assert_eq!(document.id.unwrap().len(), 16);
+    let started_at = date(document.started_at.as_deref());
+    assert_eq!(started_at, export::run_start(&run.name).map(Into::into));
+
+    // The run comes back from the segments
+    let names: Vec<_> = document.segments.iter().map(|s| s.name.clone()).collect();
+    assert_eq!(names, config.sections);
+    let ended_at: Vec<_> = document
+        .segments
+        .iter()
+        .map(|s| s.ended_at.realtime_ms)
+        .collect();
+    assert_eq!(ended_at, [1100, 2300, 3900]);
+    let best: Vec<_> = document
+        .segments
+        .iter()
+        .map(|s| s.best_duration.realtime_ms)
+        .collect();
+    assert_eq!(best, stats::segment_times(&sum_of_best));
+    // Segment IDs only depend on the game and section
+    let ids: Vec<_> = document.segments.iter().map(|s| s.id.clone()).collect();
+    let other = export::exchange(&config, "pb.run", &run.sections, None, &[], false);
+    let other: exchange_schema::Document = serde_json::from_value(other).unwrap();
+    assert!(other.id.is_none() && other.started_at.is_none());
+    assert_eq!(
+        other
+            .segments
+            .iter()
+            .map(|s| s.id.clone())
+            .collect::<Vec<_>>(),
+        ids
+    );
+    let mut unique = ids.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), 3);
+
+    // And so do the attempts, with the segments each of them reached
+    assert_eq!(document.attempts.total, 3);
+    for (i, (attempt, run)) in document.attempts.histories.iter().zip(&history).enumerate() {
+        assert_eq!(attempt.attempt_number, i + 1);
+        let started_at = date(attempt.started_at.as_deref());
+        assert_eq!(started_at, export::run_start(&run.name).map(Into::into));
+        let complete = run.sections.len() == 3;
+        let duration = complete.then(|| run.sections[2].time);
+        assert_eq!(attempt.realtime_duration_ms, duration);
+        let ended_at = date(attempt.ended_at.as_deref()).map(|end| end - started_at.unwrap());
+        assert_eq!(
+            ended_at.map(|d| d.num_milliseconds() as u32),
+            duration,
+            "{}",
+            run.name
+        );
+    }
+    for (i, segment) in document.segments.iter().enumerate() {
+        let times: Vec<_> = segment
+            .histories
+            .iter()
+            .map(|h| (h.attempt_number, h.realtime_ms))
+            .collect();
+        let expected: Vec<_> = history
+            .iter()
+            .enumerate()
+            .filter(|(_, run)| run.sections.len() > i)
+            .map(|(n, run)| (n + 1, stats::segment_times(&run.sections)[i]))
+            .collect();
+        assert_eq!(times, expected, "{}", segment.name);
+    }
+}
+
+#[test]
+fn failing_saver_leaves_the_finished_run_intact() {
+    let game = new_game(&["A", "B"]);
+    let app = app(&game);
+    app.write().unwrap().saver = |_| bail!("disk on fire");
+    let sounds = silent_sounds(&app);
+    for _ in 0..3 {
+        RunApp::handle_signal(&app, Some(&sounds), SIGUSR1).unwrap();
+    }
+
+    // The save thread fails
+    let started = Instant::now();
+    while app.read().unwrap().error.is_none() {
+        assert!(started.elapsed() < Duration::from_secs(5), "no save error");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    {
+        let app = app.read().unwrap();
+        assert_eq!(app.state, RunState::Finished { pb: true });
+        assert_eq!(app.current_sections.len(), 2);
+        assert_eq!(app.saved_run, None);
+        let error = app.error.as_deref().unwrap();
+        assert!(error.contains("disk on fire"), "{}", error);
+    }
+    assert!(list_runs(&game).unwrap().is_empty());
+
+    // The run is still there to be saved
+    let app = &mut app.write().unwrap();
+    app.saver = RunApp::save;
+    (app.saver)(app).unwrap();
+    assert_eq!(list_runs(&game).unwrap().len(), 1);
+    assert_eq!(app.saved_run.as_ref(), Some(&list_runs(&game).unwrap()[0]));
+}
+
+#[test]
+fn confirmations_read_the_given_input() {
+    let _ = ASSUME_YES.set(false);
+    assert!(!assume_yes());
+
+    let mut input = io::Cursor::new("  first answer \nsecond\n");
+    assert_eq!(ask(&mut input, "? ").unwrap(), "first answer");
+    assert_eq!(ask(&mut input, "? ").unwrap(), "second");
+    // Nothing left, like CTRL-D
+    assert_eq!(ask(&mut input, "? ").unwrap(), "");
+
+    let confirm = |answer: &str, danger| {
+        let mut input = answer.as_bytes();
+        confirm(&mut input, "Deleting", &[PathBuf::from("pb.run")], danger).unwrap()
+    };
+    for answer in ["y\n", "Y\n", "yes\n", " YES \n", "yes"] {
+        assert!(confirm(answer, Danger::Low), "{:?}", answer);
+    }
+    for answer in ["", "\n", "n\n", "no\n", "yess\n", "ok\n"] {
+        assert!(!confirm(answer, Danger::Low), "{:?}", answer);
+    }
+    // Dangerous ones have to be typed out
+    for answer in ["yes\n", " yes\n", "yes"] {
+        assert!(confirm(answer, Danger::High), "{:?}", answer);
+    }
+    for answer in ["", "y\n", "Y\n", "YES\n", "yes please\n"] {
+        assert!(!confirm(answer, Danger::High), "{:?}", answer);
+    }
+}
+
+/// The sounds of the run's game, without opening the audio output
+fn silent_sounds(app: &Arc<RwLock<RunApp>>) -> Sounds {
+    let mut config = app.read().unwrap().config.clone();
+    config.sounds.silent = true;
+    Sounds::new(&config, |_| {}).unwrap()
+}
+
+/// Sends a signal to the run, with sounds that nobody hears
+fn signal(app: &Arc<RwLock<RunApp>>, sig: i32) -> Result<()> {
+    static SOUNDS: OnceLock<Sounds> = OnceLock::new();
+    let sounds = SOUNDS.get_or_init(|| silent_sounds(app));
+    RunApp::handle_signal(app, Some(sounds), sig)
+}
+
+/// The state of the run and how many sections it has
+fn state(app: &Arc<RwLock<RunApp>>) -> (RunState, usize) {
+    let app = app.read().unwrap();
+    (app.state, app.current_sections.len())
+}
+
+/// Waits until the save thread of the finished run is done
+fn wait_for_save(app: &Arc<RwLock<RunApp>>) {
+    let started = Instant::now();
+    loop {
+        let app = app.read().unwrap();
+        if app.saved_run.is_some() || app.error.is_some() {
+            return;
+        }
+        assert!(started.elapsed() < Duration::from_secs(5), "not saved");
+        drop(app);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn run_state_transitions() {
+    use signal_hook::consts::SIGHUP;
+    let game = new_game(&["A", "B"]);
+    let app = app(&game);
+
+    // Idle: a reset and unknown signals change nothing
+    for sig in [SIGUSR2, SIGHUP] {
+        signal(&app, sig).unwrap();
+        assert_eq!(state(&app), (RunState::Idle, 0));
+    }
+    // Idle to running, then a split and a reset back to idle with the first section saved
+    signal(&app, SIGUSR1).unwrap();
+    assert_eq!(state(&app), (RunState::Running, 1));
+    signal(&app, SIGHUP).unwrap();
+    assert_eq!(state(&app), (RunState::Running, 1));
+    signal(&app, SIGUSR1).unwrap();
+    assert_eq!(state(&app), (RunState::Running, 2));
+    signal(&app, SIGUSR2).unwrap();
+    assert_eq!(state(&app), (RunState::Idle, 0));
+    assert_eq!(list_runs(&game).unwrap().len(), 1);
+
+    // Running to finished, where more splits and unknown signals change nothing
+    for _ in 0..3 {
+        signal(&app, SIGUSR1).unwrap();
+    }
+    assert_eq!(state(&app), (RunState::Finished { pb: true }, 2));
+    wait_for_save(&app);
+    for sig in [SIGUSR1, SIGHUP] {
+        signal(&app, sig).unwrap();
+        assert_eq!(state(&app), (RunState::Finished { pb: true }, 2));
+    }
+    signal(&app, SIGUSR2).unwrap();
+    assert_eq!(state(&app), (RunState::Idle, 0));
+    assert_eq!(list_runs(&game).unwrap().len(), 2);
+
+    // A finished run that isn't saved can't be reset
+    app.write().unwrap().saver = |_| bail!("disk on fire");
+    for _ in 0..3 {
+        signal(&app, SIGUSR1).unwrap();
+    }
+    wait_for_save(&app);
+    signal(&app, SIGUSR2).unwrap();
+    assert!(matches!(state(&app), (RunState::Finished { .. }, 2)));
+    {
+        let app = &mut app.write().unwrap();
+        app.saver = RunApp::save;
+        (app.saver)(app).unwrap();
+    }
+    signal(&app, SIGUSR2).unwrap();
+    assert_eq!(state(&app), (RunState::Idle, 0));
+
+    // Idle to armed, which only a reset or the end of the window leaves
+    app.write().unwrap().settings.confirm_start_ms = 60_000;
+    signal(&app, SIGUSR1).unwrap();
+    assert_eq!(state(&app), (RunState::Armed, 0));
+    signal(&app, SIGUSR1).unwrap();
+    assert_eq!(state(&app), (RunState::Armed, 0));
+    signal(&app, SIGUSR2).unwrap();
+    assert_eq!(state(&app), (RunState::Idle, 0));
+    app.write().unwrap().settings.confirm_start_ms = 20;
+    signal(&app, SIGUSR1).unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    // A split after the window begins the run from the first start and splits it
+    signal(&app, SIGUSR1).unwrap();
+    assert_eq!(state(&app), (RunState::Running, 2));
+    assert!(app.read().unwrap().current_sections[0].time >= 50);
+
+    // Shutting down leaves the state to the exit, which saves the active run
+    signal(&app, SIGTERM).unwrap();
+    assert!(app.read().unwrap().shutdown);
+    assert_eq!(state(&app), (RunState::Running, 2));
+    let saved = app.write().unwrap().save_on_shutdown().unwrap();
+    assert!(saved.is_some());
+    assert_eq!(app.read().unwrap().state, RunState::Idle);
+}
+
+/// A run of three sections with triggers `a`, `b` and `c`, brought into a state by name
+fn bridge_app(state: &str) -> Arc<RwLock<RunApp>> {
+    let app = app(&new_game(&["A", "B", "C"]));
+    app.write().unwrap().config.triggers = vec!["a".into(), "b".into(), "c".into()];
+    let splits = match state {
+        "idle" => 0,
+        "armed" => {
+            app.write().unwrap().settings.confirm_start_ms = 60000;
+            1
+        }
+        "running" => 2,
+        "finished" => 4,
+        _ => unreachable!(),
+    };
+    for _ in 0..splits {
+        signal(&app, SIGUSR1).unwrap();
+    }
+    app
+}
+
+/// Applies a line of the bridge
+fn bridge_line(app: &Arc<RwLock<RunApp>>, line: &str) -> Result<()> {
+    RunApp::bridge_message(app, Some(&silent_sounds(app)), bridge::parse(line))
+}
+
+#[test]
+fn bridge_lines_in_every_state() {
+    use RunState::*;
+    let lines = [
+        "start",
+        "split",
+        "split b",
+        "split x",
+        "checkpoint",
+        "checkpoint Door",
+        "capabilities a b c",
+        "category Any%",
+        "something else",
+    ];
+    let finished = Finished { pb: true };
+    let expected = [
+        // Only a start begins a run, a split without `auto_start` is left over
+        (
+            "idle",
+            [
+                (Running, 1),
+                (Idle, 0),
+                (Idle, 0),
+                (Idle, 0),
+                (Idle, 0),
+                (Idle, 0),
+                (Idle, 0),
+                (Idle, 0),
+                (Idle, 0),
+            ],
+        ),
+        // Waits for the confirmation window
+        ("armed", [(Armed, 0); 9]),
+        // A start never splits, a mismatched trigger still does
+        (
+            "running",
+            [
+                (Running, 2),
+                (Running, 3),
+                (Running, 3),
+                (Running, 3),
+                (Running, 2),
+                (Running, 2),
+                (Running, 2),
+                (Running, 2),
+                (Running, 2),
+            ],
+        ),
+        ("finished", [(finished, 3); 9]),
+    ];
+    for (state_name, results) in expected {
+        for (line, result) in lines.iter().zip(results) {
+            let app = bridge_app(state_name);
+            bridge_line(&app, line).unwrap();
+            assert_eq!(state(&app), result, "{} in {}", line, state_name);
+
+            // Checkpoints are only recorded during a run
+            let app = app.read().unwrap();
+            let checkpoint = line.starts_with("checkpoint") && state_name == "running";
+            assert_eq!(
+                app.checkpoints.len(),
+                checkpoint as usize,
+                "{} in {}",
+                line,
+                state_name
+            );
+            if *line == "checkpoint Door" && checkpoint {
+                assert_eq!(app.checkpoints[0].name, "Door");
+            }
+            assert_eq!(app.capabilities_warning, None);
+        }
+    }
+
+    // Warnings about splits that don't fit
+    let app = bridge_app("idle");
+    bridge_line(&app, "split").unwrap();
+    let warning = app.read().unwrap().trigger_warning.clone().unwrap();
+    assert!(warning.contains("before the run started"), "{}", warning);
+    let app = bridge_app("running");
+    bridge_line(&app, "split x").unwrap();
+    assert!(app.read().unwrap().trigger_warning.is_some());
+    bridge_line(&app, "split c").unwrap();
+    assert_eq!(app.read().unwrap().trigger_warning, None);
+    assert_eq!(state(&app), (finished, 3));
+
+    // Mismatched triggers may be ignored, and `auto_start` lets splits start runs
+    let app = bridge_app("running");
+    app.write().unwrap().config.ignore_mismatched_triggers = true;
+    bridge_line(&app, "split x").unwrap();
+    assert_eq!(state(&app), (Running, 2));
+    assert!(app.read().unwrap().trigger_warning.is_some());
+    let app = bridge_app("idle");
+    app.write().unwrap().config.auto_start = true;
+    bridge_line(&app, "split a").unwrap();
+    assert_eq!(state(&app), (Running, 1));
+
+    // Capabilities are only checked against the triggers of the config
+    let app = bridge_app("idle");
+    bridge_line(&app, "capabilities a").unwrap();
+    let warning = app.read().unwrap().capabilities_warning.clone().unwrap();
+    assert!(warning.contains("doesn't know b, c"), "{}", warning);
+    app.write().unwrap().config.triggers.clear();
+    app.write().unwrap().capabilities_warning = None;
+    bridge_line(&app, "capabilities a").unwrap();
+    assert_eq!(app.read().unwrap().capabilities_warning, None);
+}
+
+#[test]
+fn failed_save_resumes_without_a_second_run() {
+    let game = new_game(&["A", "B"]);
+    let app = app(&game);
+    // pb.run can't be replaced while it's a directory
+    let blocker = game_path(&game).join("pb.run");
+    fs::create_dir_all(blocker.join("in the way")).unwrap();
+    for _ in 0..3 {
+        signal(&app, SIGUSR1).unwrap();
+    }
+    wait_for_save(&app);
+    assert_eq!(app.read().unwrap().saved_run, None);
+    assert_eq!(list_runs(&game).unwrap().len(), 1);
+
+    fs::remove_dir_all(&blocker).unwrap();
+    let app = &mut app.write().unwrap();
+    assert!(app.try_save());
+    assert_eq!(app.error, None);
+    let runs = list_runs(&game).unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(app.saved_run.as_ref(), Some(&runs[0]));
+    assert_eq!(read(&game, "pb.run"), read(&game, &runs[0]));
+    assert_eq!(read(&game, "sum_of_best.run"), read(&game, "pb.run"));
+    assert_eq!(read(&game, "pb_history.log").lines().count(), 1);
+    assert_eq!(read(&game, "golds.log").lines().count(), 1);
+}
+
+#[test]
+fn hundred_signals_around_the_finish() {
+    let game = new_game(&["A", "B", "C"]);
+    let app = app(&game);
+    for _ in 0..3 {
+        signal(&app, SIGUSR1).unwrap();
+    }
+    assert_eq!(state(&app), (RunState::Running, 3));
+
+    let threads: Vec<_> = (0..100)
+        .map(|_| {
+            let app = Arc::clone(&app);
+            std::thread::spawn(move || signal(&app, SIGUSR1).unwrap())
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    wait_for_save(&app);
+
+    let app = app.read().unwrap();
+    assert_eq!(app.state, RunState::Finished { pb: true });
+    assert_eq!(app.current_sections.len(), 3);
+    assert_eq!(app.session.last().unwrap().spurious, 99);
+    let runs = list_runs(&game).unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(app.saved_run.as_ref(), Some(&runs[0]));
+    assert_eq!(read(&game, &runs[0]).lines().count(), 3);
+    assert_eq!(read(&game, "pb_history.log").lines().count(), 1);
+}
+
+#[test]
+fn restart_grace_with_the_default_settings() {
+    use RunState::*;
+    let game = new_game(&["A", "B", "C"]);
+    let restarted = |run: &Arc<RwLock<RunApp>>| {
+        let toast = run.write().unwrap().toast.take();
+        toast.is_some_and(|(_, text, _)| text == "Restarted")
+    };
+
+    // Without a gold a quick split is a real one
+    let run = app(&game);
+    assert_eq!(run.read().unwrap().settings.restart_grace_ms, 5000);
+    signal(&run, SIGUSR1).unwrap();
+    signal(&run, SIGUSR1).unwrap();
+    assert_eq!(state(&run), (Running, 2));
+    assert!(!restarted(&run));
+
+    // A second start from the bridge restarts
+    let run = app(&game);
+    signal(&run, SIGUSR1).unwrap();
+    bridge_line(&run, "start").unwrap();
+    assert_eq!(state(&run), (Running, 1));
+    assert!(restarted(&run));
+
+    // Within half the gold of the first section a split restarts as well
+    run.write().unwrap().sum_of_best_sections = Some(sections(&[60000, 120000, 180000]));
+    signal(&run, SIGUSR1).unwrap();
+    assert_eq!(state(&run), (Running, 1));
+    assert!(restarted(&run));
+    run.write().unwrap().sum_of_best_sections = Some(sections(&[20, 40, 60]));
+    std::thread::sleep(Duration::from_millis(20));
+    signal(&run, SIGUSR1).unwrap();
+    assert_eq!(state(&run), (Running, 2));
+    assert!(!restarted(&run));
+
+    // Past the first section a start does nothing
+    bridge_line(&run, "start").unwrap();
+    assert_eq!(state(&run), (Running, 2));
+    assert!(!restarted(&run));
+}
+
+#[test]
+fn practice_runs_pause_while_the_game_is_unfocused() {
+    let game = new_game(&["A", "B"]);
+    let settings = SettingsOverrides {
+        focus_pause_ms: Some(50),
+        ..Default::default()
+    };
+    let unfocused_for = |app: &Arc<RwLock<RunApp>>, ms| {
+        app.write().unwrap().focus_changed(false, "test");
+        std::thread::sleep(Duration::from_millis(ms));
+        app.write().unwrap().focus_changed(false, "test");
+    };
+
+    // The main run never pauses
+    let run = app(&game);
+    run.write().unwrap().settings = Settings::resolve(&[&settings]).unwrap();
+    signal(&run, SIGUSR1).unwrap();
+    unfocused_for(&run, 100);
+    assert_eq!(run.read().unwrap().pause, None);
+    assert!(run.read().unwrap().elapsed() >= 100);
+
+    let practice = app(&game);
+    {
+        let practice = &mut practice.write().unwrap();
+        practice.settings = Settings::resolve(&[&settings]).unwrap();
+        practice.practice = true;
+    }
+    signal(&practice, SIGUSR1).unwrap();
+    // Back before the pause is due
+    unfocused_for(&practice, 10);
+    practice.write().unwrap().focus_changed(true, "test");
+    assert_eq!(practice.read().unwrap().pause, None);
+
+    unfocused_for(&practice, 100);
+    let paused_at = practice.read().unwrap().elapsed();
+    assert!(paused_at < 100, "{}", paused_at);
+    let (banner, _) = practice.read().unwrap().state_banner().unwrap();
+    assert_eq!(banner, "Paused, the game lost the focus (test)");
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(practice.read().unwrap().elapsed(), paused_at);
+
+    // A split continues the run without the time it was paused
+    signal(&practice, SIGUSR1).unwrap();
+    assert_eq!(state(&practice), (RunState::Running, 2));
+    let practice = practice.read().unwrap();
+    assert_eq!(practice.pause, None);
+    assert!(practice.current_sections[0].time < paused_at + 20);
+}
+
+#[test]
+fn thousands_of_attempts_keep_the_session_bounded() {
+    const ATTEMPTS: usize = 3000;
+    let game = new_game(&["A", "B"]);
+    let app = app(&game);
+    app.write().unwrap().settings.event_history = 50;
+    // The splits come within a few milliseconds of the start, which would restart the run
+    // once the golds of A are that short
+    app.write().unwrap().settings.restart_grace_ms = 0;
+
+    let first_start = chrono::Local::now();
+    let mut events = 0;
+    for attempt in 0..ATTEMPTS {
+        signal(&app, SIGUSR1).unwrap();
+        // A few seconds apart, so every run gets a name of its own
+        app.write().unwrap().start_date =
+            first_start + chrono::Duration::seconds(5 * attempt as i64);
+        match attempt % 3 {
+            // Reset right away
+            0 => events += 2,
+            // Reset in the second section
+            1 => {
+                signal(&app, SIGUSR1).unwrap();
+                events += 3;
+            }
+            // Finished
+            _ => {
+                signal(&app, SIGUSR1).unwrap();
+                signal(&app, SIGUSR1).unwrap();
+                wait_for_save(&app);
+                events += 3;
+            }
+        }
+        signal(&app, SIGUSR2).unwrap();
+
+        let app = app.read().unwrap();
+        assert_eq!(app.error, None);
+        assert!(app.events.len() <= 50);
+        assert!(app.current_sections.is_empty());
+        assert!(app.drift_samples.is_empty() && app.clock_jumps.is_empty());
+        assert!(app.reset_counts.len() <= 2);
+    }
+
+    assert_eq!(app.read().unwrap().events.len(), 50);
+    // Everything that left the buffer is in session.log, oldest first
+    let log = read(&game, "session.log");
+    let log: Vec<_> = log.lines().collect();
+    assert_eq!(log.len(), events - 50);
+    assert!(log[0].ends_with("\tStart\t0"), "{}", log[0]);
+    assert!(log[1].ends_with("\tReset\t0"), "{}", log[1]);
+    // Runs reset in the first section have nothing to save
+    assert_eq!(list_runs(&game).unwrap().len(), 2 * ATTEMPTS / 3);
+    let resets = read(&game, "resets.log");
+    assert_eq!(resets.lines().count(), 2 * ATTEMPTS / 3);
+}
+
+#[test]
+fn run_filters_combine() {
+    let day = chrono::NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+    let midnight = day
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .unwrap();
+    let metadata = RunMetadata {
+        started_at: Some(midnight),
+        category: Some("Any%".to_owned()),
+        route: Some("Skip".to_owned()),
+        note: Some("New route for the Boss".to_owned()),
+        ..Default::default()
+    };
+    let all = filter::RunFilter {
+        complete_only: true,
+        since: Some(day),
+        category: Some("any%".to_owned()),
+        route: Some("SKIP".to_owned()),
+        with_note: Some("the boss".to_owned()),
+    };
+    assert!(RunFilter::default().is_empty() && !all.is_empty());
+    assert!(RunFilter::default().matches("x.run", false, &RunMetadata::default()));
+    assert!(all.matches("x.run", true, &metadata));
+
+    // Each filter alone keeps the run out
+    assert!(!all.matches("x.run", false, &metadata));
+    let earlier = RunMetadata {
+        started_at: Some(midnight - chrono::Duration::seconds(1)),
+        ..metadata.clone()
+    };
+    assert!(!all.matches("x.run", true, &earlier));
+    for (category, route, note) in [
+        (Some("100%"), Some("Skip"), Some("the boss")),
+        (None, Some("Skip"), Some("the boss")),
+        (Some("Any%"), Some("No skip"), Some("the boss")),
+        (Some("Any%"), None, Some("the boss")),
+        (Some("Any%"), Some("Skip"), Some("the bos")),
+        (Some("Any%"), Some("Skip"), None),
+    ] {
+        let other = RunMetadata {
+            category: category.map(str::to_owned),
+            route: route.map(str::to_owned),
+            note: note.map(|n| format!("{} fight", n)),
+            ..metadata.clone()
+        };
+        assert!(
+            !all.matches("x.run", true, &other),
+            "{:?} {:?} {:?}",
+            category,
+            route,
+            note
+        );
+    }
+    // Filters that aren't given don't look at the metadata
+    let mut some = all.clone();
+    some.category = None;
+    some.route = None;
+    let other = RunMetadata {
+        category: None,
+        route: Some("No skip".to_owned()),
+        ..metadata.clone()
+    };
+    assert!(some.matches("x.run", true, &other));
+
+    // Without a recorded start the run name counts, dated in local time
+    let since = filter::RunFilter {
+        since: Some(day),
+        ..Default::default()
+    };
+    let unrecorded = RunMetadata::default();
+    assert!(since.matches("2024-03-10T00:00:00.run", false, &unrecorded));
+    assert!(since.matches("2024-03-11T12:00:00_2.run", false, &unrecorded));
+    assert!(!since.matches("2024-03-09T23:59:59.run", false, &unrecorded));
+    assert!(!since.matches("pb.run", false, &unrecorded));
+    // The recorded start wins over the name
+    assert!(since.matches("2024-03-09T23:59:59.run", false, &metadata));
+    assert!(!since.matches("2024-03-10T00:00:00.run", false, &earlier));
+    // A start in another time zone is the same instant
+    let elsewhere = RunMetadata {
+        started_at: Some(
+            midnight
+                .with_timezone(&chrono::FixedOffset::east_opt(-5 * 3600).unwrap())
+                .into(),
+        ),
+        ..Default::default()
+    };
+    assert!(since.matches("x.run", false, &elsewhere));
+}
+
+/// A bridge running `script` with sh, and the lines it hands on
+fn fake_bridge(script: &str) -> (bridge::Status, mpsc::Receiver<String>) {
+    use std::os::unix::fs::PermissionsExt;
+    let game = new_game(&["A"]);
+    let path = game_path(&game).join("bridge.sh");
+    fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+    let mut permissions = fs::metadata(&path).unwrap().permissions();
+    permissions.set_mode(0o755);
+    fs::set_permissions(&path, permissions).unwrap();
+
+    let (lines, received) = mpsc::channel();
+    let config = load_config(&game).unwrap();
+    let status = bridge::run(&config, &path, move |line| {
+        lines.send(line.to_owned()).is_ok()
+    });
+    (status.unwrap(), received)
+}
+
+/// The next line of a fake bridge, waiting a few seconds at most
+fn next_line(lines: &mpsc::Receiver<String>) -> String {
+    lines.recv_timeout(Duration::from_secs(5)).unwrap()
+}
+
+#[test]
+fn bridge_acknowledges_numbered_lines_and_drops_resent_ones() {
+    let (status, lines) = fake_bridge(
+        r#"
+echo "split #1"
+read ack; echo "got $ack"
+echo "split #1"
+read ack; echo "got $ack"
+echo start
+echo "split boss #2"
+read ack; echo "got $ack"
+echo "split #1"
+read ack; echo "got $ack"
+echo "checkpoint a #b"
+echo "split #-1"
+echo done
+"#,
+    );
+    let expected = [
+        "split",
+        "got ack 1",
+        // The resent split is acknowledged again, but not handed on
+        "got ack 1",
+        "start",
+        "split boss",
+        "got ack 2",
+        "got ack 1",
+        // Not sequence numbers
+        "checkpoint a #b",
+        "split #-1",
+        "done",
+    ];
+    for line in expected {
+        assert_eq!(next_line(&lines), line);
+    }
+    // Exits on its own
+    assert!(lines.recv_timeout(Duration::from_secs(5)).is_err());
+    assert_eq!(status.state(), bridge::State::Exited);
+}
+
+#[test]
+fn bridge_restarts_with_new_numbers() {
+    let (status, lines) = fake_bridge("echo \"start #1\"\nexec sleep 10\n");
+    assert_eq!(next_line(&lines), "start");
+    assert_eq!(status.state(), bridge::State::Running);
+
+    status.stop();
+    assert_eq!(status.state(), bridge::State::Stopped);
+    status.restart();
+    // The new process starts counting from 1 again
+    assert_eq!(next_line(&lines), "start");
+    assert_eq!(status.state(), bridge::State::Running);
+
+    status.kill();
+    assert_eq!(status.state(), bridge::State::Exited);
+    assert!(lines.recv_timeout(Duration::from_millis(200)).is_err());
+}
+
+/// A store that only lives in memory, for tests that don't care how runs are kept
+#[derive(Default)]
+struct MemoryStore {
+    /// By name, with their version
+    runs: Mutex<BTreeMap<String, (usize, Vec<Section>)>>,
+    metadata: Mutex<BTreeMap<String, String>>,
+    logs: Mutex<BTreeMap<String, Vec<String>>>,
+}
+
+impl RunStore for MemoryStore {
+    fn load_run(&self, run: &str) -> Result<Option<Vec<Section>>> {
+        let runs = self.runs.lock().unwrap();
+        Ok(runs.get(run).map(|(_, sections)| sections.clone()))
+    }
+
+    fn save_run(&self, run: &str, sections: &[Section]) -> Result<()> {
+        static VERSION: AtomicUsize = AtomicUsize::new(0);
+        let version = VERSION.fetch_add(1, Ordering::Relaxed);
+        let mut runs = self.runs.lock().unwrap();
+        runs.insert(run.to_owned(), (version, sections.to_vec()));
+        Ok(())
+    }
+
+    fn delete_run(&self, run: &str) -> Result<()> {
+        let deleted = self.runs.lock().unwrap().remove(run);
+        ensure!(deleted.is_some(), "{} does not exist", run);
+        Ok(())
+    }
+
+    fn rename_run(&self, from: &str, to: &str) -> Result<()> {
+        let mut runs = self.runs.lock().unwrap();
+        if let Some(run) = runs.remove(from) {
+            runs.insert(to.to_owned(), run);
+        }
+        let mut metadata = self.metadata.lock().unwrap();
+        if let Some(metadata_str) = metadata.remove(from) {
+            metadata.insert(to.to_owned(), metadata_str);
+        }
+        Ok(())
+    }
+
+    fn runs(&self) -> Result<Vec<String>> {
+        Ok(self.runs.lock().unwrap().keys().cloned().collect())
+    }
+
+    fn names(&self) -> Result<Vec<String>> {
+        let mut names = self.runs()?;
+        names.extend(self.metadata.lock().unwrap().keys().cloned());
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    fn version(&self, run: &str) -> Result<Option<String>> {
+        let runs = self.runs.lock().unwrap();
+        Ok(runs.get(run).map(|(version, _)| version.to_string()))
+    }
+
+    fn load_metadata(&self, run: &str) -> Result<Option<String>> {
+        Ok(self.metadata.lock().unwrap().get(run).cloned())
+    }
+
+    fn save_metadata(&self, run: &str, metadata: &str) -> Result<()> {
+        let mut all = self.metadata.lock().unwrap();
+        all.insert(run.to_owned(), metadata.to_owned());
+        Ok(())
+    }
+
+    fn read_log(&self, log: &str) -> Result<Vec<String>> {
+        Ok(self
+            .logs
+            .lock()
+            .unwrap()
+            .get(log)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn append_log(&self, log: &str, line: &str) -> Result<()> {
+        let mut logs = self.logs.lock().unwrap();
+        logs.entry(log.to_owned())
+            .or_default()
+            .push(line.to_owned());
+        Ok(())
+    }
+
+    fn write_log(&self, log: &str, lines: &[String]) -> Result<()> {
+        let mut logs = self.logs.lock().unwrap();
+        logs.insert(log.to_owned(), lines.to_vec());
+        Ok(())
+    }
+
+    fn location(&self, run: &str) -> PathBuf {
+        Path::new("memory").join(run)
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.runs.lock().unwrap().clear();
+        self.metadata.lock().unwrap().clear();
+        self.logs.lock().unwrap().clear();
+        Ok(())
+    }
+}
+
+/// What every store has to do, checked against each of them
+fn check_store(store: &dyn RunStore) {
+    assert!(store.runs().unwrap().is_empty());
+    assert_eq!(
+        store.load_run("pb.run").unwrap().map(|s| run_text(&s)),
+        None
+    );
+    assert_eq!(store.version("pb.run").unwrap(), None);
+    assert_eq!(store.read_log("golds.log").unwrap(), Vec::<String>::new());
+
+    let pb = sections(&[1000, 2500]);
+    store.save_run("pb.run", &pb).unwrap();
+    store.save_run("2024-01-01T10:00:00.run", &pb).unwrap();
+    store
+        .save_run("2024-01-01T10:00:00.run.bak", &sections(&[900]))
+        .unwrap();
+    store.save_run("shared/friend.run", &pb).unwrap();
+    store.save_run("empty.run", &[]).unwrap();
+    let loaded = store.load_run("pb.run").unwrap().unwrap();
+    assert_eq!(run_text(&loaded), run_text(&pb));
+    assert_eq!(store.load_run("empty.run").unwrap().unwrap().len(), 0);
+    let version = store.version("pb.run").unwrap().unwrap();
+    std::thread::sleep(Duration::from_millis(2));
+    store.save_run("pb.run", &sections(&[1000, 2400])).unwrap();
+    assert_ne!(store.version("pb.run").unwrap().unwrap(), version);
+
+    store
+        .save_metadata("2024-01-01T10:00:00.run", "note = \"first\"\n")
+        .unwrap();
+    store
+        .save_metadata("gone.run", "note = \"no run\"\n")
+        .unwrap();
+    assert_eq!(
+        store.load_metadata("2024-01-01T10:00:00.run").unwrap(),
+        Some("note = \"first\"\n".to_owned())
+    );
+    assert_eq!(store.load_metadata("pb.run").unwrap(), None);
+    assert_eq!(
+        store.runs().unwrap(),
+        [
+            "2024-01-01T10:00:00.run",
+            "2024-01-01T10:00:00.run.bak",
+            "empty.run",
+            "pb.run",
+            "shared/friend.run"
+        ]
+    );
+    assert!(store.names().unwrap().contains(&"gone.run".to_owned()));
+
+    store
+        .rename_run(
+            "2024-01-01T10:00:00.run",
+            "rejected/2024-01-01T10:00:00.run",
+        )
+        .unwrap();
+    assert_eq!(
+        store
+            .load_run("2024-01-01T10:00:00.run")
+            .unwrap()
+            .map(|s| s.len()),
+        None
+    );
+    assert_eq!(
+        store.load_metadata("2024-01-01T10:00:00.run").unwrap(),
+        None
+    );
+    assert!(store
+        .load_metadata("rejected/2024-01-01T10:00:00.run")
+        .unwrap()
+        .is_some());
+    store.delete_run("empty.run").unwrap();
+    assert!(store.delete_run("empty.run").is_err());
+
+    store.append_log("golds.log", "a\tA").unwrap();
+    store.append_log("golds.log", "b").unwrap();
+    store.append_log("resets.log", "r").unwrap();
+    assert_eq!(store.read_log("golds.log").unwrap(), ["a\tA", "b"]);
+    store.write_log("golds.log", &["b".to_owned()]).unwrap();
+    assert_eq!(store.read_log("golds.log").unwrap(), ["b"]);
+    assert_eq!(store.read_log("resets.log").unwrap(), ["r"]);
+
+    let copy = MemoryStore::default();
+    assert_eq!(store::copy(store, &copy).unwrap(), (4, 2));
+    store::ensure_same(store, &copy).unwrap();
+    copy.append_log("resets.log", "another").unwrap();
+    assert!(store::ensure_same(store, &copy).is_err());
+
+    store.clear().unwrap();
+    assert!(store.names().unwrap().is_empty());
+    assert!(store.read_log("resets.log").unwrap().is_empty());
+}
+
+#[test]
+fn memory_store() {
+    check_store(&MemoryStore::default());
+}
+
+#[test]
+fn file_store() {
+    let game = new_game(&["A", "B"]);
+    check_store(&store::FileStore::new(&game).unwrap());
+    // Only the config is left
+    let dir = game_dir(&game).unwrap();
+    let left: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(left, ["config.toml"]);
+}
+
+#[test]
+fn sqlite_store() {
+    let game = new_game(&["A", "B"]);
+    let path = game_dir(&game).unwrap().join("speedy.db");
+    check_store(&store::SqliteStore::open(&path).unwrap());
+}
+
+#[test]
+fn runs_are_saved_to_the_configured_store() {
+    let game = new_game(&["A", "B"]);
+    let memory = Arc::new(MemoryStore::default());
+    store::set(&game, memory.clone()).unwrap();
+    let app = app(&game);
+    for _ in 0..3 {
+        signal(&app, SIGUSR1).unwrap();
+    }
+    wait_for_save(&app);
+
+    let runs = memory.runs().unwrap();
+    assert_eq!(runs.len(), 3, "{:?}", runs);
+    let run = app.read().unwrap().saved_run.clone().unwrap();
+    assert_eq!(
+        run_text(&memory.load_run("pb.run").unwrap().unwrap()),
+        run_text(&memory.load_run(&run).unwrap().unwrap())
+    );
+    assert_eq!(memory.read_log("pb_history.log").unwrap().len(), 1);
+    assert_eq!(memory.read_log("golds.log").unwrap().len(), 1);
+    assert!(memory.load_metadata(&run).unwrap().is_some());
+    // Nothing went to the game directory
+    assert!(list_runs(&game).unwrap().len() == 1 && !game_path(&game).join(&run).exists());
+}
+
+#[test]
+fn percentiles_use_the_nearest_rank() {
+    assert_eq!(stats::percentile(&[], 50), None);
+    assert_eq!(stats::percentile(&[7], 0), Some(7));
+    assert_eq!(stats::percentile(&[7], 100), Some(7));
+    let sorted = [1, 2, 3, 4];
+    assert_eq!(stats::percentile(&sorted, 0), Some(1));
+    assert_eq!(stats::percentile(&sorted, 25), Some(1));
+    assert_eq!(stats::percentile(&sorted, 26), Some(2));
+    assert_eq!(stats::percentile(&sorted, 50), Some(2));
+    assert_eq!(stats::percentile(&sorted, 75), Some(3));
+    assert_eq!(stats::percentile(&sorted, 100), Some(4));
+}
+
+/// Complete runs around a minute in A, one far off and one that didn't finish
+fn finish_history() -> Vec<stats::HistoricalRun> {
+    [
+        &[59_000, 120_000, 180_000][..],
+        &[61_000, 125_000, 190_000],
+        &[60_000, 118_000, 175_000],
+        &[62_500, 130_000, 200_000],
+        &[57_500, 119_000, 170_000],
+        &[58_000, 121_000, 185_000],
+        &[70_000, 140_000, 210_000],
+        &[60_000, 120_000],
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, times)| stats::HistoricalRun {
+        name: format!("2024-01-0{}T10:00:00.run", i + 1),
+        sections: sections(times),
+    })
+    .collect()
+}
+
+#[test]
+fn finish_ranges_of_synthetic_histories() {
+    let history = finish_history();
+    // Moved by their difference at A, the finishes are 172.5s, 175s, 181s, 187s, 189s
+    // and 197.5s
+    assert_eq!(
+        stats::finish_range(3, &history, 0, 60_000, 3000),
+        Some((175_000, 189_000))
+    );
+    // Without the two runs 2.5s off there are too few
+    assert_eq!(stats::finish_range(3, &history, 0, 60_000, 2000), None);
+    assert_eq!(
+        stats::finish_range(3, &history, 1, 120_000, 6000),
+        Some((177_000, 184_000))
+    );
+    // Far ahead and with every complete run in reach, the range moves with the run
+    assert_eq!(
+        stats::finish_range(3, &history, 0, 50_000, 20_000),
+        Some((165_000, 187_500))
+    );
+    assert_eq!(stats::finish_range(3, &[], 0, 60_000, 3000), None);
+    assert_eq!(stats::finish_range(3, &history, 0, 30_000, 3000), None);
+}
+
+#[test]
+fn eta_is_the_likely_finish() {
+    let game = new_game(&["A", "B", "C"]);
+    let app = app(&game);
+    // In minutes instead of seconds, so the ends of the range are minutes apart
+    let minutes = |times: &[u32]| sections(&times.iter().map(|t| t * 60).collect::<Vec<_>>());
+    app.write().unwrap().history = finish_history()
+        .into_iter()
+        .map(|run| stats::HistoricalRun {
+            sections: minutes(&run.sections.iter().map(|s| s.time).collect::<Vec<_>>()),
+            ..run
+        })
+        .collect();
+    signal(&app, SIGUSR1).unwrap();
+    signal(&app, SIGUSR1).unwrap();
+    // The split found no finish range at its real time
+    let app = &mut app.write().unwrap();
+    assert_eq!(app.finish_range, None);
+    // Only loaded now, since its gold makes an early split restart the run
+    save_run(
+        &game,
+        "sum_of_best.run",
+        &minutes(&[50_000, 100_000, 150_000]),
+    )
+    .unwrap();
+    app.reload_comparisons().unwrap();
+    app.current_sections[0].time = 3_600_000;
+    app.finish_range = app.finish_range();
+
+    let clock = |app: &RunApp, time: u32| {
+        (app.start_date + chrono::Duration::milliseconds(time as i64))
+            .format("%H:%M")
+            .to_string()
+    };
+    let (low, high) = (175_000 * 60, 189_000 * 60);
+    assert_eq!(app.finish_range, Some((low, high)));
+    let eta = app.eta().unwrap();
+    let range = format!("{}–{}", clock(app, low), clock(app, high));
+    assert!(eta.starts_with(&range), "{} {}", eta, range);
+    let footer = &app.reference_lines()[0];
+    assert!(footer.contains("Likely finish"), "{}", footer);
+
+    // Without enough comparable runs both are the projection from the sum of best
+    app.history.truncate(3);
+    app.finish_range = app.finish_range();
+    assert_eq!(app.finish_range, None);
+    assert_eq!(app.projected_time(), Some(160_000 * 60));
+    let eta = app.eta().unwrap();
+    assert!(eta.starts_with(&clock(app, 160_000 * 60)), "{}", eta);
+    assert!(!eta.contains('–'), "{}", eta);
+}
+
+#[test]
+fn failed_background_save_resumes_without_a_second_run() {
+    let game = new_game(&["A", "B"]);
+    let app = app(&game);
+    signal(&app, SIGUSR1).unwrap();
+    signal(&app, SIGUSR1).unwrap();
+    // Finishes without saving, like the finish with async_save on
+    app.write().unwrap().saving = true;
+    signal(&app, SIGUSR1).unwrap();
+    app.write().unwrap().saving = false;
+
+    let blocker = game_dir(&game).unwrap().join("pb.run");
+    fs::create_dir_all(blocker.join("in the way")).unwrap();
+    RunApp::save_in_background(&app);
+    {
+        let app = app.read().unwrap();
+        assert_eq!(app.saved_run, None);
+        assert!(app.error.is_some());
+        assert!(!app.saving);
+    }
+    assert_eq!(list_runs(&game).unwrap().len(), 1);
+
+    fs::remove_dir_all(&blocker).unwrap();
+    RunApp::save_in_background(&app);
+    let app = app.read().unwrap();
+    let runs = list_runs(&game).unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(app.saved_run.as_ref(), Some(&runs[0]));
+    assert_eq!(app.error, None);
+    assert_eq!(app.history.len(), 1);
+    assert_eq!(read(&game, "pb.run").lines().count(), 2);
+}
+
+#[test]
+fn rebuilt_sum_of_best_is_never_slower() {
+    let game = new_game(&["A", "B", "C"]);
+    save_run(&game, "sum_of_best.run", &sections(&[1000, 2000, 3000])).unwrap();
+    let app = app(&game);
+    let app = app.read().unwrap();
+
+    assert_eq!(app.sum_of_best_problem(&sections(&[900, 1900, 2900])), None);
+    let problem = app.sum_of_best_problem(&sections(&[1000, 2200, 3200]));
+    assert!(problem.unwrap().contains(" B,"));
+    let problem = app.sum_of_best_problem(&sections(&[1000, 2000]));
+    assert!(problem.unwrap().contains("2 of 3 sections"));
+}
+
+#[test]
+fn stored_golds_of_zero_are_replaced() {
+    let game = new_game(&["A", "B"]);
+    save_run(&game, "sum_of_best.run", &sections(&[1000, 1000])).unwrap();
+    let app = app(&game);
+    let app = app.read().unwrap();
+
+    assert_eq!(app.sum_of_best_problem(&sections(&[1000, 1500])), None);
+}
+
+#[test]
+fn check_finds_missed_golds() {
+    let game = new_game(&["A", "B"]);
+    save_run(&game, "pb.run", &sections(&[1000, 2000])).unwrap();
+    save_run(&game, "sum_of_best.run", &sections(&[1000, 2000])).unwrap();
+    save_run(&game, "2024-01-01T10:00:00.run", &sections(&[1000, 2000])).unwrap();
+    let config = load_config(&game).unwrap();
+    assert_eq!(check_game(&config).unwrap(), Vec::<String>::new());
+
+    save_run(&game, "2024-01-02T10:00:00.run", &sections(&[800, 2100])).unwrap();
+    let problems = check_game(&config).unwrap();
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert!(problems[0].contains("misses the gold 0m00.800s of A"));
+    assert!(problems[1].contains("ends at 0m02.000s but the golds add up to 0m01.800s"));
+}
+
+#[test]
+fn relay_legs_get_their_part_of_the_run() {
+    let relay = new_game(&["A", "B", "C", "D"]);
+    let second = new_game(&["C", "D"]);
+    let app = app(&relay);
+    let app = &mut app.write().unwrap();
+    app.current_sections = sections(&[1000, 2000, 3000, 4500]);
+    app.split_dates = vec![app.start_date; 4];
+    app.checkpoints = vec![
+        Checkpoint {
+            section: 0,
+            name: "early".to_owned(),
+            time: 500,
+        },
+        Checkpoint {
+            section: 3,
+            name: "late".to_owned(),
+            time: 4000,
+        },
+    ];
+    app.drift_samples = vec![
+        DriftSample {
+            monotonic: 1500,
+            wall_clock: 1510,
+        },
+        DriftSample {
+            monotonic: 2500,
+            wall_clock: 2520,
+        },
+    ];
+    app.clock_jumps = vec![ClockJump {
+        at: 3500,
+        jump: 5000,
+    }];
+    let leg = RelayLeg {
+        config: load_config(&second).unwrap(),
+        settings: Settings {
+            gold_guard: 7,
+            ..Default::default()
+        },
+        pb_sections: None,
+        sum_of_best_sections: None,
+        first_section: 2,
+    };
+
+    let leg_app = app.leg_app(&leg);
+    assert_eq!(
+        run_text(&leg_app.current_sections),
+        "C: 0m01.000s\nD: 0m02.500s\n"
+    );
+    assert_eq!(leg_app.checkpoints.len(), 1);
+    assert_eq!(leg_app.checkpoints[0].section, 1);
+    assert_eq!(leg_app.checkpoints[0].time, 2000);
+    assert_eq!(leg_app.drift_samples.len(), 1);
+    assert_eq!(leg_app.drift_samples[0].monotonic, 500);
+    assert_eq!(leg_app.drift_samples[0].wall_clock, 520);
+    assert_eq!(leg_app.clock_jumps.len(), 1);
+    assert_eq!(leg_app.clock_jumps[0].at, 1500);
+    assert_eq!(leg_app.settings.gold_guard, 7);
+    assert!(leg_app.history.is_empty());
+}
+
+#[test]
+fn game_names_stay_in_the_data_directory() {
+    let game = new_game(&["A"]);
+    assert!(game_dir(&game).is_ok());
+    for name in ["", ".", ".."] {
+        assert!(game_dir(name).is_err(), "{:?}", name);
+    }
+    for name in ["../x", "a/../../b", "..\\x", ".hidden", "a b"] {
+        assert!(validate_name("game", name).is_err(), "{:?}", name);
+    }
+
+    // A game directory that is a link to somewhere else
+    let outside = tempfile::TempDir::new().unwrap();
+    let data = data_dir().unwrap();
+    std::os::unix::fs::symlink(outside.path(), data.join("elsewhere")).unwrap();
+    let error = game_dir("elsewhere").unwrap_err();
+    assert!(format!("{:#}", error).contains("outside of"), "{:#}", error);
+    std::os::unix::fs::symlink(data.join("elsewhere"), data.join("elsewhere2")).unwrap();
+    assert!(game_dir("elsewhere2").is_err());
+}
+
+#[test]
+fn run_selectors_stay_in_the_game_directory() {
+    let game = new_game(&["A"]);
+    save_run(&game, "pb.run", &sections(&[1000])).unwrap();
+    save_run(&game, "2024-01-01T10:00:00.run", &sections(&[1000])).unwrap();
+    fs::write(
+        game_dir(&game).unwrap().join("../outside.run"),
+        "A: 0m01.000s\n",
+    )
+    .unwrap();
+
+    assert!(resolve_run_selector(&game, "2024").is_ok());
+    for selector in [
+        "../outside.run",
+        "../../etc/passwd",
+        "/etc/passwd",
+        "./pb.run",
+    ] {
+        assert!(
+            resolve_run_selector(&game, selector).is_err(),
+            "{:?}",
+            selector
+        );
+    }
+
+    // pb.run as a link out of the game
+    let outside = tempfile::TempDir::new().unwrap();
+    fs::write(outside.path().join("pb.run"), "A: 0m01.000s\n").unwrap();
+    let pb = game_dir(&game).unwrap().join("pb.run");
+    fs::remove_file(&pb).unwrap();
+    std::os::unix::fs::symlink(outside.path().join("pb.run"), &pb).unwrap();
+    assert!(resolve_run_selector(&game, "pb").is_err());
+}
+
+#[test]
+fn shared_names_stay_in_the_shared_directory() {
+    let game = new_game(&["A"]);
+    assert_eq!(shared_run("friend.v2").unwrap(), "shared/friend.v2.run");
+    for name in ["../x", "../../x", "/etc/passwd", "..", "a/b", ""] {
+        assert!(shared_run(name).is_err(), "{:?}", name);
+    }
+
+    let outside = tempfile::TempDir::new().unwrap();
+    std::os::unix::fs::symlink(outside.path(), game_dir(&game).unwrap().join("shared")).unwrap();
+    let run = shared_run("friend").unwrap();
+    assert!(save_run(&game, &run, &sections(&[1000])).is_err());
+    assert!(load_run(&game, &run).is_err());
+    assert!(!outside.path().join("friend.run").exists());
+}
+
+#[test]
+fn category_names_only_match_categories() {
+    let game = new_game(&["A"]);
+    let config = load_config(&game).unwrap();
+    for name in ["../x", "..", "/", &format!("../{}", game)] {
+        assert_eq!(category_game(&config, name).unwrap(), None, "{:?}", name);
+    }
+}
+
+#[test]
+fn pitched_beeps_are_built_up_front() {
+    let pitch = DeltaPitch {
+        semitones: 5.0,
+        full_range_at: Some(10.0),
+    };
+    assert_eq!(pitch.step(0), 0);
+    assert_eq!(pitch.step(-10_000), PITCH_STEPS);
+    assert_eq!(pitch.step(i64::MAX), -PITCH_STEPS);
+    assert_eq!(pitch.step(i64::MIN), PITCH_STEPS);
+    let steps: Vec<i64> = (-200..=200).map(|d| pitch.step(d * 100)).collect();
+    assert!(steps.windows(2).all(|w| w[0] >= w[1]), "{:?}", steps);
+    assert!((pitch.frequency(PITCH_STEPS) - 440.0 * 2f32.powf(5.0 / 12.0)).abs() < 0.01);
+    assert_eq!(pitch.frequency(0), 440.0);
+
+    // Only ahead or behind without a full range
+    let pitch = DeltaPitch {
+        full_range_at: None,
+        ..pitch
+    };
+    assert_eq!(pitch.step(-1), PITCH_STEPS);
+    assert_eq!(pitch.step(1), -PITCH_STEPS);
+
+    let mut config = load_config(&new_game(&["A"])).unwrap();
+    config.sounds.silent = true;
+    config.sounds.delta_pitch = Some(pitch);
+    let sounds = Sounds::new(&config, |_| {}).unwrap();
+    assert_eq!(sounds.pitched.len() as i64, 2 * PITCH_STEPS + 1);
+    for delta in [i64::MIN, -1, 0, 1, i64::MAX] {
+        sounds.play_split(0, None, Some(delta));
+    }
+}
+
+#[test]
+fn shared_splits_round_trip() {
+    let game = new_game(&["[Boss]", "Escape"]);
+    let config = load_config(&game).unwrap();
+    let pb = vec![
+        Section {
+            name: "[Boss]".to_owned(),
+            time: 61000,
+        },
+        Section {
+            name: "Escape".to_owned(),
+            time: 90500,
+        },
+    ];
+    let text = share::text(&config, Some(&pb), None);
+
+    let shared = share::parse(&text).unwrap();
+    assert_eq!(shared.game, "Game");
+    assert_eq!(shared.sections, ["[Boss]", "Escape"]);
+    assert_eq!(run_text(&shared.pb.unwrap()), run_text(&pb));
+    assert!(shared.sum_of_best.is_none());
+}
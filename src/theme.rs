@@ -0,0 +1,224 @@
+//! User colors for the palette of the UI. Everything is drawn in the default palette and
+//! changed to the theme's colors afterwards, like the color mode does.
+
+use crate::layout::{Column, TableLayout};
+use crate::settings::ColorMode;
+use crate::{BG, BLUE, FG, GOLD, GREY, PURPLE, RED};
+use anyhow::{bail, Context, Result};
+use console_engine::crossterm::style::Stylize;
+use console_engine::Color;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Keys of a theme file with the palette color each one replaces
+const KEYS: [(&str, Color); 7] = [
+    ("foreground", FG),
+    ("background", BG),
+    ("muted", GREY),
+    ("ahead", BLUE),
+    ("behind", RED),
+    ("gold", GOLD),
+    ("highlight", PURPLE),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Theme {
+    /// Palette colors and what they are changed to
+    colors: Vec<(Color, Color)>,
+}
+
+impl Theme {
+    /// Reads a theme file of `key = "#rrggbb"` lines. Returns the theme and the keys it
+    /// doesn't set, which keep the default colors.
+    pub fn load(path: &Path) -> Result<(Self, Vec<&'static str>)> {
+        let theme_str = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the theme {}", path.display()))?;
+        let entries: BTreeMap<String, String> = toml::from_str(&theme_str)
+            .with_context(|| format!("Failed to parse the theme {}", path.display()))?;
+
+        if let Some(unknown) = entries
+            .keys()
+            .find(|key| !KEYS.iter().any(|(k, _)| k == key))
+        {
+            bail!(
+                "{}: unknown key {:?}, expected one of {}",
+                path.display(),
+                unknown,
+                KEYS.map(|(key, _)| key).join(", ")
+            );
+        }
+
+        let mut theme = Self::default();
+        let mut defaults = Vec::new();
+        for (key, palette) in KEYS {
+            match entries.get(key) {
+                Some(hex) => {
+                    let color = parse_hex(hex)
+                        .with_context(|| format!("{}: {} = {:?}", path.display(), key, hex))?;
+                    theme.colors.push((palette, color));
+                }
+                None => defaults.push(key),
+            }
+        }
+
+        Ok((theme, defaults))
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Changes a palette color to the theme's
+    pub fn apply(&self, color: Color) -> Color {
+        self.colors
+            .iter()
+            .find(|&&(palette, _)| palette == color)
+            .map_or(color, |&(_, themed)| themed)
+    }
+
+    /// Keys that end up with the same color. Anything drawn in the background color is
+    /// invisible, which is an error. Deltas and highlighted names that look like their
+    /// neighbours are only warned about.
+    pub fn conflicts(&self) -> (Vec<String>, Vec<String>) {
+        let color = |key: &str| {
+            let (_, palette) = KEYS.iter().find(|(k, _)| *k == key).unwrap();
+            self.apply(*palette)
+        };
+        let same = |a: &str, b: &str| format!("{} and {} are both {}", a, b, format_hex(color(a)));
+
+        let errors = KEYS
+            .iter()
+            .filter(|&&(key, _)| key != "background" && color(key) == color("background"))
+            .map(|&(key, _)| same(key, "background") + ", it would be invisible")
+            .collect();
+        let warnings = [
+            ("ahead", "behind"),
+            ("ahead", "gold"),
+            ("behind", "gold"),
+            ("foreground", "highlight"),
+        ]
+        .into_iter()
+        .filter(|&(a, b)| color(a) == color(b))
+        .map(|(a, b)| same(a, b) + ", they can't be told apart")
+        .collect();
+
+        (errors, warnings)
+    }
+}
+
+fn format_hex(color: Color) -> String {
+    match color {
+        Color::Rgb { r, g, b } => format!("\"#{:02x}{:02x}{:02x}\"", r, g, b),
+        color => format!("{:?}", color),
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Color> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("not a color like \"#f3f2cc\"");
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap();
+
+    Ok(Color::Rgb {
+        r: channel(0),
+        g: channel(2),
+        b: channel(4),
+    })
+}
+
+/// A made up row of the preview: name and its color, comparison, then time and delta of the
+/// total and the segment with their colors
+type PreviewRow = (
+    (&'static str, Color),
+    &'static str,
+    [(&'static str, Color); 2],
+    [(&'static str, Color); 2],
+);
+
+const PREVIEW_ROWS: [PreviewRow; 5] = [
+    (
+        ("%s", FG),
+        "1:02",
+        [("1:00", FG), ("(-0:02)", BLUE)],
+        [("1:00", FG), ("(-0:02)", BLUE)],
+    ),
+    (
+        ("%s", FG),
+        "2:30",
+        [("2:35", FG), ("(+0:05)", RED)],
+        [("1:35", FG), ("(+0:07)", RED)],
+    ),
+    (
+        ("Boss", PURPLE),
+        "3:40",
+        [("3:39", FG), ("(-0:01)", BLUE)],
+        [("1:04", FG), ("(-0:06)", GOLD)],
+    ),
+    (
+        ("%s", FG),
+        "4:50",
+        [("4:12", FG), ("", FG)],
+        [("0:33", FG), ("", FG)],
+    ),
+    (
+        ("%s", FG),
+        "6:00",
+        [("5:49", GREY), ("", FG)],
+        [("", FG), ("", FG)],
+    ),
+];
+
+/// Prints a split table with every state a row can be in, in the colors the UI would use
+pub fn preview(theme: &Theme, mode: ColorMode, layout: &TableLayout) {
+    let bg = mode.apply(theme.apply(BG));
+    let line = |mut parts: Vec<(String, Color)>| {
+        // Filled with the background color to the width of the table
+        let visible = parts
+            .iter()
+            .map(|(text, _)| text.chars().count())
+            .sum::<usize>();
+        let padding = (layout.width as usize + 1).saturating_sub(visible);
+        parts.push((" ".repeat(padding), FG));
+        for (text, color) in parts {
+            print!("{}", text.with(mode.apply(theme.apply(color))).on(bg));
+        }
+        println!();
+    };
+
+    line(vec![(layout.header(), FG)]);
+    line(vec![(layout.divider(), FG)]);
+    for (name, comparison, total, segment) in PREVIEW_ROWS {
+        let mut parts = vec![(" ".to_owned(), FG)];
+        for (n, &(column, _)) in layout.columns.iter().enumerate() {
+            if n > 0 {
+                parts.push((" | ".to_owned(), FG));
+            }
+            let width = layout.column_width(column);
+            let time = |text: &str| format!("{:>width$}", text, width = layout.time_width);
+            match column {
+                Column::Name => parts.push((format!("{:width$}", name.0), name.1)),
+                Column::Comparison => parts.push((time(comparison), FG)),
+                Column::Total | Column::Segment => {
+                    let [(t, t_color), (d, d_color)] = if column == Column::Total {
+                        total
+                    } else {
+                        segment
+                    };
+                    let rest = width - layout.time_width - 1;
+                    parts.push((time(t), t_color));
+                    parts.push((format!(" {:rest$}", d), d_color));
+                }
+                Column::Gold => parts.push((time("1:00"), GREY)),
+                Column::Median => parts.push((time("1:10"), GREY)),
+            }
+        }
+        line(parts);
+    }
+    line(vec![(" Finished in 5:49, new PB".to_owned(), GOLD)]);
+    line(vec![(
+        " Ready, waiting for the start signal".to_owned(),
+        GREY,
+    )]);
+}
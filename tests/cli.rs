@@ -0,0 +1,484 @@
+//! Runs the speedy binary against a temporary data directory, playing runs with `--script`
+//! and checking the files they leave behind.
+
+use assert_cmd::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// A data directory with one game of three sections, `g`
+struct Data {
+    dir: TempDir,
+}
+
+impl Data {
+    fn new() -> Self {
+        let data = Self {
+            dir: TempDir::new().unwrap(),
+        };
+        data.add_game("g", &["A", "B", "C"]);
+        data
+    }
+
+    fn add_game(&self, game: &str, sections: &[&str]) {
+        let sections = sections
+            .iter()
+            .map(|s| format!("{:?}", s))
+            .collect::<Vec<_>>()
+            .join(", ");
+        fs::create_dir_all(self.game_dir(game)).unwrap();
+        fs::write(
+            self.game_dir(game).join("config.toml"),
+            format!(
+                "version = 1\nfull_game_name = \"Game\"\nsections = [{}]\n",
+                sections
+            ),
+        )
+        .unwrap();
+    }
+
+    fn game_dir(&self, game: &str) -> PathBuf {
+        self.dir.path().join("data").join(game)
+    }
+
+    /// speedy with every directory inside the temporary one and without prompts
+    fn speedy(&self) -> Command {
+        let mut cmd = Command::cargo_bin("speedy").unwrap();
+        cmd.env("XDG_CONFIG_HOME", self.dir.path().join("config"))
+            .env_remove("SPEEDY_ASSUME_YES")
+            .arg("--data-dir")
+            .arg(self.dir.path().join("data"))
+            .arg("--yes");
+        cmd
+    }
+
+    /// Plays a script like `at 0ms start; at 1000ms split` on `g`
+    fn play(&self, script: &str, args: &[&str]) -> String {
+        let path = self.dir.path().join("script");
+        fs::write(&path, script).unwrap();
+        let output = self
+            .speedy()
+            .args(["run", "g", "--script"])
+            .arg(&path)
+            .args(args)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn read(&self, file: &str) -> String {
+        fs::read_to_string(self.game_dir("g").join(file)).unwrap()
+    }
+
+    /// The dated runs of `g`, oldest first
+    fn dated_runs(&self) -> Vec<String> {
+        dated_runs(&self.game_dir("g"))
+    }
+}
+
+fn dated_runs(dir: &Path) -> Vec<String> {
+    let mut runs: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".run") && name.starts_with(|c: char| c.is_ascii_digit()))
+        .collect();
+    runs.sort();
+    runs
+}
+
+const FIRST_RUN: &str = "at 0ms start; at 1000ms split; at 2000ms split; at 3500ms split";
+
+#[test]
+fn pb_run() {
+    let data = Data::new();
+    let out = data.play(FIRST_RUN, &[]);
+
+    let runs = data.dated_runs();
+    assert_eq!(runs.len(), 1);
+    assert!(out.contains(&format!("saved {}", runs[0])), "{}", out);
+    let expected = "A: 0m01.000s\nB: 0m02.000s\nC: 0m03.500s\n";
+    assert_eq!(data.read(&runs[0]), expected);
+    assert_eq!(data.read("pb.run"), expected);
+    assert_eq!(data.read("sum_of_best.run"), expected);
+
+    // Slower, with a gold in B
+    data.play(
+        "at 0ms start; at 1500ms split; at 2200ms split; at 4000ms split",
+        &[],
+    );
+    let runs = data.dated_runs();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(
+        data.read(&runs[1]),
+        "A: 0m01.500s\nB: 0m02.200s\nC: 0m04.000s\n"
+    );
+    assert_eq!(data.read("pb.run"), expected);
+    assert_eq!(
+        data.read("sum_of_best.run"),
+        "A: 0m01.000s\nB: 0m01.700s\nC: 0m03.200s\n"
+    );
+}
+
+#[test]
+fn reset_keeps_the_completed_sections() {
+    let data = Data::new();
+    data.play("at 0ms start; at 1000ms split; at 1800ms reset", &[]);
+
+    let runs = data.dated_runs();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(data.read(&runs[0]), "A: 0m01.000s\n");
+    assert!(!data.game_dir("g").join("pb.run").exists());
+    let resets = data.read("resets.log");
+    assert_eq!(resets.lines().count(), 1, "{}", resets);
+    // Abandoned in the second section, 1.8s into the run
+    assert!(resets.ends_with("\t1\t0m01.800s\n"), "{}", resets);
+}
+
+#[test]
+fn undo_restores_the_pb() {
+    let data = Data::new();
+    data.play(FIRST_RUN, &[]);
+    data.play(
+        "at 0ms start; at 900ms split; at 1800ms split; at 3000ms split",
+        &[],
+    );
+    assert_eq!(
+        data.read("pb.run"),
+        "A: 0m00.900s\nB: 0m01.800s\nC: 0m03.000s\n"
+    );
+    let newest = data.dated_runs().pop().unwrap();
+
+    data.speedy().args(["undo-last", "g"]).assert().success();
+
+    assert_eq!(
+        data.read("pb.run"),
+        "A: 0m01.000s\nB: 0m02.000s\nC: 0m03.500s\n"
+    );
+    assert_eq!(
+        data.read("sum_of_best.run"),
+        "A: 0m01.000s\nB: 0m02.000s\nC: 0m03.500s\n"
+    );
+    assert_eq!(data.dated_runs().len(), 1);
+    assert!(data.game_dir("g").join("rejected").join(&newest).exists());
+}
+
+#[test]
+fn signal_flood_around_the_finish() {
+    let data = Data::new();
+    let mut script = String::from("at 0ms start; at 1000ms split; at 2000ms split\n");
+    for _ in 0..100 {
+        script += "at 3500ms split\n";
+    }
+    for _ in 0..100 {
+        script += "at 3501ms split\n";
+    }
+    let out = data.play(&script, &[]);
+
+    let runs = data.dated_runs();
+    assert_eq!(runs.len(), 1, "{}", out);
+    assert_eq!(out.matches("saved").count(), 1, "{}", out);
+    assert_eq!(
+        data.read(&runs[0]),
+        "A: 0m01.000s\nB: 0m02.000s\nC: 0m03.500s\n"
+    );
+    assert_eq!(data.read("pb.run"), data.read(&runs[0]));
+    assert_eq!(data.read("pb_history.log").lines().count(), 1);
+    assert_eq!(data.read("golds.log").lines().count(), 1);
+}
+
+#[test]
+fn since_counts_from_local_midnight() {
+    let data = Data::new();
+    let run = "A: 0m01.000s\nB: 0m02.000s\nC: 0m03.500s\n";
+    for name in [
+        "2024-01-01T23:30:00",
+        "2024-01-01T23:59:00",
+        "2024-01-02T00:30:00",
+    ] {
+        fs::write(data.game_dir("g").join(format!("{}.run", name)), run).unwrap();
+    }
+    // Timed in UTC, which is already the next day in Japan
+    fs::write(
+        data.game_dir("g").join("2024-01-01T23:30:00.toml"),
+        "started_at = \"2024-01-01T23:30:00Z\"\n",
+    )
+    .unwrap();
+
+    let listed = |tz: &str| {
+        let out = data
+            .speedy()
+            .env("TZ", tz)
+            .args(["list-runs", "g", "--since", "2024-01-02"])
+            .assert()
+            .success();
+        let out = String::from_utf8(out.get_output().stdout.clone()).unwrap();
+        let mut runs: Vec<&str> = [
+            "2024-01-01T23:30:00",
+            "2024-01-01T23:59:00",
+            "2024-01-02T00:30:00",
+        ]
+        .into_iter()
+        .filter(|name| out.contains(name))
+        .collect();
+        runs.sort();
+        runs
+    };
+    // Runs without a recorded start are dated by their name, in local time
+    assert_eq!(listed("UTC0"), ["2024-01-02T00:30:00"]);
+    assert_eq!(
+        listed("JST-9"),
+        ["2024-01-01T23:30:00", "2024-01-02T00:30:00"]
+    );
+    assert_eq!(listed("EST5"), ["2024-01-02T00:30:00"]);
+}
+
+impl Data {
+    /// Copies a game from `tests/fixtures` into the data directory
+    fn add_fixture(&self, fixture: &str) {
+        let from = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(fixture);
+        fs::create_dir_all(self.game_dir(fixture)).unwrap();
+        for entry in fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            fs::copy(entry.path(), self.game_dir(fixture).join(entry.file_name())).unwrap();
+        }
+    }
+
+    fn stats_json(&self, game: &str, args: &[&str]) -> serde_json::Value {
+        let out = self
+            .speedy()
+            .args(["stats", game, "--json"])
+            .args(args)
+            .assert()
+            .success();
+        serde_json::from_slice(&out.get_output().stdout).unwrap()
+    }
+}
+
+/// The time spent of every section and in total, in seconds
+fn time_spent(stats: &serde_json::Value) -> (Vec<u64>, u64) {
+    let sections = stats["sections"].as_array().unwrap();
+    let spent = sections
+        .iter()
+        .map(|s| s["time_spent"].as_u64().unwrap() / 1000)
+        .collect();
+    (spent, stats["time_spent"].as_u64().unwrap() / 1000)
+}
+
+#[test]
+fn time_spent_adds_up_every_attempt() {
+    let data = Data::new();
+    data.add_fixture("time_spent");
+
+    // Segments of the four runs, the two abandoned sections of their resets and the reset in
+    // A that left no run. The unparsable line and the reset of a deleted run are skipped.
+    let stats = data.stats_json("time_spent", &[]);
+    assert_eq!(
+        time_spent(&stats),
+        (
+            vec![60 + 50 + 70 + 55 + 20, 90 + 70 + 90 + 30, 90 + 80 + 40],
+            745
+        )
+    );
+    let percent: Vec<_> = stats["sections"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["time_spent_percent"].as_u64().unwrap())
+        .collect();
+    assert_eq!(percent, [34, 37, 28]);
+    let pb_percent: Vec<_> = stats["sections"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["pb_percent"].as_u64().unwrap())
+        .collect();
+    assert_eq!(pb_percent, [24, 40, 35]);
+
+    // A filtered history only counts the resets of its own runs
+    let stats = data.stats_json("time_spent", &["--complete-only"]);
+    assert_eq!(time_spent(&stats), (vec![60 + 55, 90 + 90, 90 + 80], 465));
+    let stats = data.stats_json("time_spent", &["--since", "2030-01-01"]);
+    assert_eq!(time_spent(&stats), (vec![0, 0, 0], 0));
+
+    let out = data
+        .speedy()
+        .args(["stats", "time_spent"])
+        .assert()
+        .success();
+    let out = String::from_utf8(out.get_output().stdout.clone()).unwrap();
+    assert!(
+        out.contains("Time spent over all attempts (0h12m25s)"),
+        "{}",
+        out
+    );
+    assert!(out.contains("\nB     0h04m40s   37%   40%\n"), "{}", out);
+}
+
+/// The runs, metadata and logs of `g`, by file name
+fn stored_files(data: &Data) -> Vec<(String, String)> {
+    fn walk(dir: &Path, prefix: &str, files: &mut Vec<(String, String)>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let entry = entry.unwrap();
+            let name = format!("{}{}", prefix, entry.file_name().into_string().unwrap());
+            if entry.file_type().unwrap().is_dir() {
+                walk(&entry.path(), &format!("{}/", name), files);
+            } else if !matches!(
+                &*name,
+                "config.toml" | "index.toml" | "speedy.db" | ".lock" | "current.log"
+            ) {
+                files.push((name, fs::read_to_string(entry.path()).unwrap()));
+            }
+        }
+    }
+    let mut files = Vec::new();
+    walk(&data.game_dir("g"), "", &mut files);
+    files.sort();
+    files
+}
+
+/// What the read-only commands print about `g`, with the run locations of the SQLite
+/// storage written like files
+fn read_only_output(data: &Data) -> Vec<String> {
+    let report = data.dir.path().join("report.html");
+    let commands: &[&[&str]] = &[
+        &["list-runs", "g"],
+        &["stats", "g"],
+        &["stats", "g", "--json"],
+        &["check", "g"],
+        &["show", "g"],
+        &["show", "g", "pb"],
+        &["golds", "g", "latest-1"],
+        &["compare", "g"],
+        &["export", "g"],
+        &["report", "g", "-o", report.to_str().unwrap()],
+    ];
+    let mut output = Vec::new();
+    for args in commands {
+        let out = data
+            .speedy()
+            .args(*args)
+            .assert()
+            .success()
+            .get_output()
+            .clone();
+        let text = String::from_utf8(out.stdout).unwrap() + &String::from_utf8(out.stderr).unwrap();
+        output.push(format!("{:?}\n{}", args, text.replace("speedy.db:", "")));
+    }
+    output.push(fs::read_to_string(report).unwrap());
+    output
+}
+
+#[test]
+fn migrating_keeps_everything() {
+    let data = Data::new();
+    data.play(FIRST_RUN, &[]);
+    data.play(
+        "at 0ms start; at 1500ms split; at 2200ms split; at 4000ms split",
+        &[],
+    );
+    data.play("at 0ms start; at 1000ms split; at 1800ms reset", &[]);
+    // A rejected run and .bak copies
+    data.play(
+        "at 0ms start; at 900ms split; at 1800ms split; at 3000ms split",
+        &[],
+    );
+    data.speedy().args(["undo-last", "g"]).assert().success();
+    data.speedy()
+        .args(["retime", "g", "100"])
+        .assert()
+        .success();
+    let files = stored_files(&data);
+    assert!(files.iter().any(|(name, _)| name.starts_with("rejected/")));
+    assert!(files.iter().any(|(name, _)| name.ends_with(".bak")));
+    let output = read_only_output(&data);
+
+    data.speedy()
+        .args(["migrate", "g", "sqlite"])
+        .assert()
+        .success();
+    assert!(data.game_dir("g").join("speedy.db").exists());
+    assert!(data.read("config.toml").contains("storage = \"sqlite\""));
+    assert_eq!(stored_files(&data), Vec::new());
+    assert_eq!(read_only_output(&data), output);
+
+    data.speedy()
+        .args(["migrate", "g", "sqlite"])
+        .assert()
+        .failure();
+
+    data.speedy()
+        .args(["migrate", "g", "files"])
+        .assert()
+        .success();
+    assert!(!data.game_dir("g").join("speedy.db").exists());
+    assert!(!data.read("config.toml").contains("storage"));
+    assert_eq!(stored_files(&data), files);
+
+    // New runs go to the database
+    data.speedy()
+        .args(["migrate", "g", "sqlite"])
+        .assert()
+        .success();
+    data.play(FIRST_RUN, &[]);
+    assert_eq!(stored_files(&data), Vec::new());
+    let out = data.speedy().args(["list-runs", "g"]).assert().success();
+    let out = String::from_utf8(out.get_output().stdout.clone()).unwrap();
+    // The comparisons and four dated runs
+    assert_eq!(out.matches(".run").count(), 6, "{}", out);
+}
+
+#[test]
+fn three_splits_in_the_same_instant() {
+    let data = Data::new();
+    let metadata = |run: &str| data.read(&run.replace(".run", ".toml"));
+    // Without a gold yet, even a split in the instant of the start is kept
+    data.play(
+        "at 0ms start; at 0ms split; at 0ms split; at 0ms split",
+        &[],
+    );
+    let runs = data.dated_runs();
+    let instant = "A: 0m00.000s\nB: 0m00.000s\nC: 0m00.000s\n";
+    assert_eq!(data.read(&runs[0]), instant);
+    assert_eq!(data.read("pb.run"), instant);
+    let toml = metadata(&runs[0]);
+    assert!(
+        toml.contains("instant_splits = [\n    \"A\",\n    \"B\",\n    \"C\",\n]"),
+        "{}",
+        toml
+    );
+
+    // The sections of 0ms never became golds, any real time beats them
+    data.play(
+        "at 0ms start; at 1000ms split; at 1500ms split; at 2000ms split",
+        &[],
+    );
+    let runs = data.dated_runs();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(
+        data.read("sum_of_best.run"),
+        "A: 0m01.000s\nB: 0m01.500s\nC: 0m02.000s\n"
+    );
+    assert!(!metadata(&runs[1]).contains("instant_splits"));
+}
+
+#[test]
+fn unknown_game_is_named() {
+    let data = Data::new();
+    for command in ["list-runs", "report", "stats", "show"] {
+        let output = data
+            .speedy()
+            .args([command, "nosuchgame"])
+            .assert()
+            .failure()
+            .get_output()
+            .stderr
+            .clone();
+        let stderr = String::from_utf8(output).unwrap();
+        assert!(stderr.contains("nosuchgame"), "{}: {}", command, stderr);
+    }
+}